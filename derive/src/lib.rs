@@ -2,7 +2,8 @@
 
 use proc_macro_error::{abort_if_dirty, emit_error, proc_macro_error};
 use quote::quote;
-use syn::{Attribute, Data, Meta, MetaList, Token};
+use syn::{parse_quote, Attribute, Data, Field, Fields, Meta, MetaList, Token, TraitBound};
+use syn::ext::IdentExt;
 use syn::punctuated::Punctuated;
 use synstructure::{AddBounds, decl_derive, Structure};
 
@@ -18,25 +19,7 @@ fn derive_trace_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
     .iter()
     .any(|attr| attr_contains(attr, UNSAFE_NO_DROP));
 
-    // Ignore every field and variant annotated with #[rust_cc(ignore)]
-    // Filter fields before variants to be able to emit all the errors in case of wrong attributes in ignored variants
-    s.filter(|bi| {
-        !bi.ast().attrs
-        .iter()
-        .any(|attr| attr_contains(attr, IGNORE))
-    });
-
-    // Filter variants only in case of enums
-    if let Data::Enum(_) = s.ast().data {
-        s.filter_variants(|vi| {
-            !vi.ast().attrs
-            .iter()
-            .any(|attr| attr_contains(attr, IGNORE))
-        });
-    }
-
-    // Abort if errors has been emitted
-    abort_if_dirty();
+    filter_ignored(&mut s);
 
     // Identifier for the ctx parameter of Trace::trace(...)
     // Shouldn't clash with any other identifier
@@ -97,6 +80,71 @@ fn drop(&mut self) {
     }
 }
 
+/// Ignores every field and variant annotated with #[rust_cc(ignore)]
+fn filter_ignored(s: &mut Structure<'_>) {
+    // Filter fields before variants to be able to emit all the errors in case of wrong attributes in ignored variants
+    s.filter(|bi| {
+        !bi.ast().attrs
+        .iter()
+        .any(|attr| attr_contains(attr, IGNORE))
+    });
+
+    // Filter variants only in case of enums
+    if let Data::Enum(_) = s.ast().data {
+        s.filter_variants(|vi| {
+            !vi.ast().attrs
+            .iter()
+            .any(|attr| attr_contains(attr, IGNORE))
+        });
+    }
+
+    // Abort if errors has been emitted
+    abort_if_dirty();
+}
+
+/// Collects the fields annotated with #[rust_cc(ignore)], including the fields of ignored variants
+fn ignored_fields<'a>(s: &Structure<'a>) -> Vec<&'a Field> {
+    let mut ignored: Vec<&Field> = Vec::new();
+    for vi in s.variants() {
+        let ignored_variant = vi.ast().attrs
+        .iter()
+        .any(|attr| attr_contains(attr, IGNORE));
+
+        for bi in vi.bindings() {
+            let ignored_field = bi.ast().attrs
+            .iter()
+            .any(|attr| attr_contains(attr, IGNORE));
+
+            if ignored_variant || ignored_field {
+                ignored.push(bi.ast());
+            }
+        }
+    }
+
+    // Abort if errors has been emitted
+    abort_if_dirty();
+
+    ignored
+}
+
+/// Bounds the types of the fields with `bound` and the types of the ignored fields with `ignored_bound`
+fn add_bounds_with_ignored(s: &mut Structure<'_>, ignored: &[&Field], bound: TraitBound, ignored_bound: TraitBound) {
+    let is_ignored = |field: &Field| ignored.iter().any(|ignored| core::ptr::eq(*ignored, field));
+
+    let mut where_clause = None;
+    let mut not_ignored = s.clone();
+    not_ignored.filter(|bi| !is_ignored(bi.ast()));
+    not_ignored.add_trait_bounds(&bound, &mut where_clause, AddBounds::Fields);
+    let mut only_ignored = s.clone();
+    only_ignored.filter(|bi| is_ignored(bi.ast()));
+    only_ignored.add_trait_bounds(&ignored_bound, &mut where_clause, AddBounds::Fields);
+
+    s.add_bounds(AddBounds::None);
+    for predicate in where_clause.into_iter().flat_map(|where_clause| where_clause.predicates) {
+        s.add_where_predicate(predicate);
+    }
+}
+
 fn get_meta_items(attr: &Attribute) -> Option<&MetaList> {
     if attr.path().is_ident("rust_cc") {
         match &attr.meta {
@@ -156,3 +204,236 @@ fn derive_finalize_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
         }
     })
 }
+
+decl_derive!([HeapSize, attributes(rust_cc)] => #[proc_macro_error] derive_heap_size_trait);
+
+fn derive_heap_size_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
+    filter_ignored(&mut s);
+
+    // Identifier for the ctx parameter of HeapSize::heap_size(...)
+    // Shouldn't clash with any other identifier
+    let ctx = quote::format_ident!("__rust_cc__HeapSize__ctx__");
+
+    let body = s.fold(quote!(0usize), |acc, bi| {
+        let ty = &bi.ast().ty;
+        quote! {
+            #acc + <#ty as rust_cc::heap_size::HeapSize>::heap_size(#bi, #ctx)
+        }
+    });
+
+    s.underscore_const(true);
+
+    s.add_bounds(AddBounds::Fields);
+    s.gen_impl(quote! {
+        extern crate rust_cc;
+
+        gen impl rust_cc::heap_size::HeapSize for @Self {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn heap_size(&self, #ctx: &mut rust_cc::heap_size::HeapSizeContext) -> usize {
+                match *self { #body }
+            }
+        }
+    })
+}
+
+decl_derive!([DeepClone, attributes(rust_cc)] => #[proc_macro_error] derive_deep_clone_trait);
+
+fn derive_deep_clone_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
+    let ignored = ignored_fields(&s);
+    let is_ignored = |field: &Field| ignored.iter().any(|ignored| core::ptr::eq(*ignored, field));
+
+    // Identifier for the ctx parameter of DeepClone::deep_clone(...)
+    // Shouldn't clash with any other identifier
+    let ctx = quote::format_ident!("__rust_cc__DeepClone__ctx__");
+
+    let body = s.each_variant(|vi| {
+        let bindings = vi.bindings();
+        vi.construct(|field, i| {
+            let bi = &bindings[i];
+            let ty = &field.ty;
+            if is_ignored(field) {
+                quote! { <#ty as core::clone::Clone>::clone(#bi) }
+            } else {
+                quote! { <#ty as rust_cc::deep_clone::DeepClone>::deep_clone(#bi, #ctx) }
+            }
+        })
+    });
+
+    // Ignored fields are cloned using Clone, so they must be bounded differently
+    add_bounds_with_ignored(&mut s, &ignored, parse_quote!(rust_cc::deep_clone::DeepClone), parse_quote!(core::clone::Clone));
+
+    s.underscore_const(true);
+    s.gen_impl(quote! {
+        extern crate core;
+        extern crate rust_cc;
+
+        gen unsafe impl rust_cc::deep_clone::DeepClone for @Self {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn deep_clone(&self, #ctx: &mut rust_cc::deep_clone::DeepCloneContext) -> Self {
+                match *self { #body }
+            }
+        }
+    })
+}
+
+decl_derive!([GraphDebug, attributes(rust_cc)] => #[proc_macro_error] derive_graph_debug_trait);
+
+fn derive_graph_debug_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
+    let ignored = ignored_fields(&s);
+    let is_ignored = |field: &Field| ignored.iter().any(|ignored| core::ptr::eq(*ignored, field));
+
+    // Identifiers for the parameters of GraphDebug::fmt_graph(...)
+    // Shouldn't clash with any other identifier
+    let f = quote::format_ident!("__rust_cc__GraphDebug__f__");
+    let ctx = quote::format_ident!("__rust_cc__GraphDebug__ctx__");
+
+    let body = s.each_variant(|vi| {
+        let name = vi.ast().ident.unraw().to_string();
+        let fields = vi.bindings().iter().map(|bi| {
+            let value = if is_ignored(bi.ast()) {
+                quote! { #bi }
+            } else {
+                quote! { &#ctx.debug(#bi) }
+            };
+            match &bi.ast().ident {
+                Some(ident) => {
+                    let ident = ident.unraw().to_string();
+                    quote! { .field(#ident, #value) }
+                },
+                None => quote! { .field(#value) },
+            }
+        });
+        match vi.ast().fields {
+            Fields::Named(_) => quote! { #f.debug_struct(#name) #(#fields)* .finish() },
+            Fields::Unnamed(_) => quote! { #f.debug_tuple(#name) #(#fields)* .finish() },
+            Fields::Unit => quote! { #f.write_str(#name) },
+        }
+    });
+
+    // Ignored fields are formatted using Debug, so they must be bounded differently
+    add_bounds_with_ignored(&mut s, &ignored, parse_quote!(rust_cc::graph::GraphDebug), parse_quote!(core::fmt::Debug));
+
+    s.underscore_const(true);
+    s.gen_impl(quote! {
+        extern crate core;
+        extern crate rust_cc;
+
+        gen impl rust_cc::graph::GraphDebug for @Self {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn fmt_graph(
+                &self,
+                #f: &mut core::fmt::Formatter<'_>,
+                #ctx: &rust_cc::graph::GraphDebugContext,
+            ) -> core::fmt::Result {
+                match *self { #body }
+            }
+        }
+    })
+}
+
+decl_derive!([GraphEq, attributes(rust_cc)] => #[proc_macro_error] derive_graph_eq_trait);
+
+fn derive_graph_eq_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
+    let ignored = ignored_fields(&s);
+    let is_ignored = |field: &Field| ignored.iter().any(|ignored| core::ptr::eq(*ignored, field));
+
+    // Identifiers for the parameters of GraphEq::graph_eq(...)
+    // Shouldn't clash with any other identifier
+    let other = quote::format_ident!("__rust_cc__GraphEq__other__");
+    let ctx = quote::format_ident!("__rust_cc__GraphEq__ctx__");
+
+    // Bind the fields of other to different names
+    let mut others = s.clone();
+    others.binding_name(|_, i| quote::format_ident!("__rust_cc__GraphEq__other_binding_{}", i));
+
+    let arms = s.variants().iter().zip(others.variants()).map(|(vi, other_vi)| {
+        let pat = vi.pat();
+        let other_pat = other_vi.pat();
+        let comparisons = vi.bindings().iter().zip(other_vi.bindings()).map(|(bi, other_bi)| {
+            let ty = &bi.ast().ty;
+            if is_ignored(bi.ast()) {
+                quote! { && <#ty as core::cmp::PartialEq>::eq(#bi, #other_bi) }
+            } else {
+                quote! { && <#ty as rust_cc::graph::GraphEq>::graph_eq(#bi, #other_bi, #ctx) }
+            }
+        });
+        quote! {
+            #pat => match *#other {
+                #other_pat => true #(#comparisons)*,
+                _ => false,
+            },
+        }
+    }).collect::<Vec<_>>();
+
+    // Ignored fields are compared using PartialEq, so they must be bounded differently
+    add_bounds_with_ignored(&mut s, &ignored, parse_quote!(rust_cc::graph::GraphEq), parse_quote!(core::cmp::PartialEq));
+
+    s.underscore_const(true);
+
+    s.gen_impl(quote! {
+        extern crate core;
+        extern crate rust_cc;
+
+        gen impl rust_cc::graph::GraphEq for @Self {
+            #[inline]
+            #[allow(non_snake_case, unreachable_patterns, unused_variables)]
+            fn graph_eq(&self, #other: &Self, #ctx: &mut rust_cc::graph::GraphEqContext) -> bool {
+                match *self { #(#arms)* }
+            }
+        }
+    })
+}
+
+decl_derive!([GraphHashable, attributes(rust_cc)] => #[proc_macro_error] derive_graph_hashable_trait);
+
+fn derive_graph_hashable_trait(mut s: Structure<'_>) -> proc_macro2::TokenStream {
+    let ignored = ignored_fields(&s);
+    let is_ignored = |field: &Field| ignored.iter().any(|ignored| core::ptr::eq(*ignored, field));
+
+    // Identifiers for the parameters of GraphHashable::graph_hash(...)
+    // Shouldn't clash with any other identifier
+    let state = quote::format_ident!("__rust_cc__GraphHashable__state__");
+    let ctx = quote::format_ident!("__rust_cc__GraphHashable__ctx__");
+
+    let body = s.each(|bi| {
+        let ty = &bi.ast().ty;
+        if is_ignored(bi.ast()) {
+            quote! { <#ty as core::hash::Hash>::hash(#bi, #state); }
+        } else {
+            quote! { <#ty as rust_cc::graph::GraphHashable>::graph_hash(#bi, #state, #ctx); }
+        }
+    });
+
+    // Hash the variant, like the derived Hash implementation does
+    let discriminant = if let Data::Enum(_) = s.ast().data {
+        quote! { core::hash::Hash::hash(&core::mem::discriminant(self), #state); }
+    } else {
+        quote! {}
+    };
+
+    // Ignored fields are hashed using Hash, so they must be bounded differently
+    add_bounds_with_ignored(&mut s, &ignored, parse_quote!(rust_cc::graph::GraphHashable), parse_quote!(core::hash::Hash));
+
+    s.underscore_const(true);
+
+    s.gen_impl(quote! {
+        extern crate core;
+        extern crate rust_cc;
+
+        gen impl rust_cc::graph::GraphHashable for @Self {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn graph_hash<__RustCcHasher: core::hash::Hasher>(
+                &self,
+                #state: &mut __RustCcHasher,
+                #ctx: &mut rust_cc::graph::GraphHashContext,
+            ) {
+                #discriminant
+                match *self { #body }
+            }
+        }
+    })
+}
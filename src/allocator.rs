@@ -0,0 +1,162 @@
+//! A global allocator which tracks the heap memory allocated by every thread.
+//!
+//! The memory allocated for [`Cc`][`crate::Cc`]s is always counted by the collector (see [`state::allocated_bytes`][`crate::state::allocated_bytes`]),
+//! however the memory owned by the values inside them (like the buffer of a [`Vec`]) is not, unless it is manually reported
+//! (see [`state::add_external_memory`][`crate::state::add_external_memory`]).
+//!
+//! The [`CcTrackingAllocator`] wraps another [`GlobalAlloc`] and counts the bytes allocated by every thread,
+//! allowing automatic collections to react to the real heap usage.
+//!
+//! Since the global allocator cannot be detected, the [`CcTrackingAllocator`] registered with `#[global_allocator]` must be
+//! [installed][`CcTrackingAllocator::install`] explicitly. Allocations are counted only after its installation.
+//!
+//! The count is kept per thread, without any synchronization. Memory deallocated by a thread different from the one
+//! which allocated it is subtracted from the count of the deallocating thread (which never goes below zero), so it is
+//! never subtracted from the count of the allocating thread. Programs which move heap memory across threads may
+//! thus observe counts higher than the real heap usage.
+//!
+//! # Example
+//! ```rust
+//!# use std::alloc::System;
+//! use rust_cc::allocator::CcTrackingAllocator;
+//!
+//! #[global_allocator]
+//! static GLOBAL: CcTrackingAllocator<System> = CcTrackingAllocator::new(System);
+//!
+//!# fn main() {
+//! GLOBAL.install();
+//!
+//! let vec: Vec<u8> = Vec::with_capacity(1024);
+//! assert!(rust_cc::allocator::heap_bytes().unwrap() >= 1024);
+//!# }
+//! ```
+//!
+//! [`Vec`]: `alloc::vec::Vec`
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::Cell;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::utils;
+
+utils::rust_cc_thread_local! {
+    // The bytes allocated minus the bytes deallocated by the current thread.
+    // Memory may be deallocated by a different thread than the one which allocated it, so the subtraction saturates
+    static HEAP_BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+// The address of the installed CcTrackingAllocator, or null if no CcTrackingAllocator has been installed
+static INSTALLED: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// A [`GlobalAlloc`] which counts the bytes allocated by every thread, wrapping another allocator.
+///
+/// When registered using `#[global_allocator]` and [installed][`CcTrackingAllocator::install`], the number of bytes allocated
+/// by the current thread can be retrieved using [`heap_bytes`] and is provided to the [collection policies][`crate::config::CollectionPolicy`].
+///
+/// See the [module-level documentation][`mod@crate::allocator`] for more details.
+#[derive(Debug, Default)]
+pub struct CcTrackingAllocator<A: GlobalAlloc> {
+    inner: A,
+}
+
+impl<A: GlobalAlloc> CcTrackingAllocator<A> {
+    /// Creates a new [`CcTrackingAllocator`] wrapping the provided allocator.
+    #[inline]
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    /// Returns a reference to the wrapped allocator.
+    #[inline]
+    pub const fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Installs this allocator, starting to count the bytes allocated through it.
+    ///
+    /// This method must be called on the allocator registered using `#[global_allocator]`, preferably at the start of
+    /// the program, since the memory allocated before is not counted. The allocations made through any other
+    /// [`CcTrackingAllocator`] are never counted.
+    ///
+    /// Calling this method again on the installed allocator does nothing, while calling it on a different allocator replaces it.
+    #[inline]
+    pub fn install(&'static self) {
+        INSTALLED.store(self.as_ptr(), Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn as_ptr(&self) -> *mut () {
+        self as *const Self as *mut ()
+    }
+
+    #[inline]
+    fn is_installed(&self) -> bool {
+        INSTALLED.load(Ordering::Relaxed) == self.as_ptr()
+    }
+
+    #[inline]
+    fn record_allocation(&self, bytes: usize) {
+        if self.is_installed() {
+            // The thread local may have already been destroyed if the thread is exiting
+            let _ = HEAP_BYTES.try_with(|heap_bytes| heap_bytes.set(heap_bytes.get().saturating_add(bytes)));
+        }
+    }
+
+    #[inline]
+    fn record_deallocation(&self, bytes: usize) {
+        if self.is_installed() {
+            let _ = HEAP_BYTES.try_with(|heap_bytes| heap_bytes.set(heap_bytes.get().saturating_sub(bytes)));
+        }
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CcTrackingAllocator<A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            self.record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.record_deallocation(layout.size());
+    }
+
+    #[inline]
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            self.record_deallocation(layout.size());
+            self.record_allocation(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Returns the number of bytes of heap memory allocated by the current thread, as counted by the [`CcTrackingAllocator`].
+///
+/// The returned value is an approximation, since memory deallocated by a different thread isn't subtracted
+/// (see the [module-level documentation][`mod@crate::allocator`]).
+///
+/// Returns [`None`] if no [`CcTrackingAllocator`] has been [installed][`CcTrackingAllocator::install`] or if the count cannot be accessed.
+#[inline]
+pub fn heap_bytes() -> Option<usize> {
+    if INSTALLED.load(Ordering::Relaxed).is_null() {
+        return None;
+    }
+    HEAP_BYTES.try_with(Cell::get).ok()
+}
@@ -10,7 +10,7 @@
 use core::fmt::{self, Debug, Display, Formatter, Pointer};
 use core::cmp::Ordering;
 use core::hash::{Hash, Hasher};
-use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::panic::{Location, RefUnwindSafe, UnwindSafe};
 #[cfg(feature = "nightly")]
 use core::{
     marker::CoercePointee,
@@ -21,7 +21,7 @@
 use crate::state::{replace_state_field, state, State, try_state};
 use crate::trace::{Context, ContextInner, Finalize, Trace};
 use crate::utils::*;
-use crate::POSSIBLE_CYCLES;
+use crate::{FROZEN, POSSIBLE_CYCLES};
 #[cfg(feature = "weak-ptrs")]
 use crate::weak::weak_counter_marker::WeakCounterMarker;
 
@@ -47,9 +47,13 @@ impl<T: Trace> Cc<T> {
     /// # Panics
     /// 
     /// Panics if the automatically-stared collection panics.
+    /// 
+    /// Panics if the allocation would exceed the [memory limit][`crate::config::Config::memory_limit`].
+    /// See [`Cc::try_new`] for a non-panicking alternative.
     #[must_use = "newly created Cc is immediately dropped"]
     #[track_caller]
     pub fn new(t: T) -> Cc<T> {
+        let site = Location::caller();
         state(|state| {
             #[cfg(debug_assertions)]
             if state.is_tracing() {
@@ -59,13 +63,49 @@ pub fn new(t: T) -> Cc<T> {
             #[cfg(feature = "auto-collect")]
             super::trigger_collection(state);
 
-            Cc {
-                inner: CcBox::new(t, state),
-                _phantom: PhantomData,
+            match CcBox::new(t, state, site) {
+                Ok(inner) => Cc {
+                    inner,
+                    _phantom: PhantomData,
+                },
+                Err(err) => panic!("{}", err),
             }
         })
     }
 
+    /// Creates a new `Cc`, returning an error if the allocation would exceed the [memory limit][`crate::config::Config::memory_limit`].
+    /// 
+    /// The provided value is dropped if an error is returned.
+    /// 
+    /// This method is available only when the `auto-collect` feature is enabled, since the memory limit is part of the
+    /// [configuration][`mod@crate::config`].
+    /// 
+    /// # Collection
+    /// 
+    /// This method may start a collection. See the [`config` module documentation][`mod@crate::config`] for more details.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the automatically-stared collection panics.
+    #[cfg(feature = "auto-collect")]
+    #[track_caller]
+    pub fn try_new(t: T) -> Result<Cc<T>, crate::config::MemoryLimitError> {
+        let site = Location::caller();
+        state(|state| {
+            #[cfg(debug_assertions)]
+            if state.is_tracing() {
+                panic!("Cannot create a new Cc while tracing!");
+            }
+
+            super::trigger_collection(state);
+
+            CcBox::new(t, state, site).map(|inner| Cc {
+                inner,
+                _phantom: PhantomData,
+            })
+        })
+    }
+
     /// Returns the inner value, if the [`Cc`] has exactly one strong reference and the collector is not collecting, finalizing or dropping.
     /// 
     /// Otherwise, an [`Err`] is returned with the same [`Cc`] this method was called on.
@@ -94,6 +134,7 @@ pub fn try_unwrap(self) -> Result<T, Self> {
             }
 
             remove_from_list(cc.inner.cast());
+            remove_from_frozen(cc.inner.cast());
 
             // SAFETY: cc is unique
             let t = unsafe { ptr::read(cc.inner().get_elem()) };
@@ -157,6 +198,45 @@ pub fn already_finalized(&self) -> bool {
         !self.counter_marker().needs_finalization()
     }
 
+    /// Consumes the [`Cc`], returning a reference to the managed value which is valid for the rest of the program.
+    ///
+    /// The managed allocation becomes *immortal*: it is never deallocated, buffered, finalized or collected, and the collector
+    /// stops tracing when reaching it. Thus, every object reachable from it is kept alive (unless it's removed from the
+    /// immortal object through interior mutability). Immortal objects behave like [frozen][`crate::freeze`] ones,
+    /// but they're not counted by [`frozen_objects_count`][`crate::state::frozen_objects_count`] and [`unfreeze`][`crate::unfreeze`]
+    /// doesn't affect them.
+    ///
+    /// Other [`Cc`]s pointing to the same allocation can still be created and dropped as usual.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection.
+    #[inline]
+    #[track_caller]
+    pub fn leak(self) -> &'static T {
+        // Like in Cc::finalize_again, is_finalizing and is_dropping are checked since Cc::drop doesn't set is_collecting
+        #[cfg(feature = "finalization")]
+        let collecting = state(|state| state.is_collecting() || state.is_finalizing() || state.is_dropping());
+        #[cfg(not(feature = "finalization"))]
+        let collecting = state(|state| state.is_collecting() || state.is_dropping());
+        assert!(!collecting, "Cc::leak cannot be called while collecting");
+
+        // Never drop the Cc, so that the counter never reaches zero
+        let cc = ManuallyDrop::new(self);
+
+        if !cc.inner().is_immortal() {
+            remove_from_list(cc.inner.cast());
+            remove_from_frozen(cc.inner.cast());
+
+            // Immortal objects are also frozen (so they're never buffered or traced), but they're kept outside
+            // of the frozen list. Thus, unfreeze() cannot reach them and make them mortal again
+            cc.inner().set_immortal();
+        }
+
+        // SAFETY: the allocation is never deallocated, since its reference counter never reaches zero
+        unsafe { &*cc.inner.as_ptr() }.get_elem()
+    }
+
     /// Marks the managed allocation as *alive*.
     /// 
     /// Every time a [`Cc`] is dropped, the pointed allocation is buffered to be processed in the next collection.
@@ -169,6 +249,20 @@ pub fn mark_alive(&self) {
         remove_from_list(self.inner.cast());
     }
 
+    /// [Freezes][`crate::freeze`] the managed allocation, together with every object reachable from it.
+    ///
+    /// Unlike [`freeze`][`crate::freeze`], which starts from the buffered objects, this method freezes
+    /// the objects reachable from this [`Cc`] even if they are not buffered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection or while a [`Cc`] is being dropped.
+    #[inline]
+    #[track_caller]
+    pub fn freeze(&self) {
+        crate::freeze_reachable(|_, roots| roots.push(self.inner.cast()));
+    }
+
     #[inline(always)]
     fn counter_marker(&self) -> &CounterMarker {
         &self.inner().counter_marker
@@ -179,13 +273,11 @@ pub(crate) fn inner(&self) -> &CcBox<T> {
         unsafe { self.inner.as_ref() }
     }
 
-    #[cfg(feature = "weak-ptrs")]
     #[inline(always)]
     pub(crate) fn inner_ptr(&self) -> NonNull<CcBox<T>> {
         self.inner
     }
 
-    #[cfg(feature = "weak-ptrs")] // Currently used only here
     #[inline(always)]
     #[must_use]
     pub(crate) fn __new_internal(inner: NonNull<CcBox<T>>) -> Cc<T> {
@@ -297,6 +389,7 @@ fn handle_possible_cycle<T: ?Sized + Trace>(cc: &Cc<T>) {
 
                 decrement_counter(self);
                 remove_from_list(self.inner.cast());
+                remove_from_frozen(self.inner.cast());
 
                 let _dropping_guard = replace_state_field!(dropping, true, state);
                 let layout = self.inner().layout();
@@ -358,8 +451,9 @@ pub(crate) struct CcBox<T: ?Sized + Trace + 'static> {
 }
 
 impl<T: Trace> CcBox<T> {
-    #[must_use]
-    fn new(t: T, state: &State) -> NonNull<CcBox<T>> {
+    /// `site` is the location where the `Cc` is being created.
+    #[cfg_attr(not(feature = "finalization"), allow(unused_variables))]
+    fn new(t: T, state: &State, site: &'static Location<'static>) -> Result<NonNull<CcBox<T>>, CcAllocError> {
         let layout = Layout::new::<CcBox<T>>();
 
         #[cfg(feature = "finalization")]
@@ -368,7 +462,7 @@ fn new(t: T, state: &State) -> NonNull<CcBox<T>> {
         let already_finalized = false;
 
         unsafe {
-            let ptr: NonNull<CcBox<T>> = cc_alloc(layout, state);
+            let ptr: NonNull<CcBox<T>> = cc_alloc(layout, state)?;
             ptr::write(
                 ptr.as_ptr(),
                 CcBox {
@@ -380,14 +474,22 @@ fn new(t: T, state: &State) -> NonNull<CcBox<T>> {
                     elem: UnsafeCell::new(t),
                 },
             );
-            ptr
+
+            #[cfg(feature = "finalization")]
+            if state.resurrection_detection() != crate::resurrection::DetectionMode::Disabled {
+                crate::resurrection::record_allocation_site(ptr.cast(), site);
+            }
+
+            Ok(ptr)
         }
     }
 
     #[cfg(all(test, feature = "std"))] // Only used in unit tests
     #[must_use]
+    #[track_caller]
     pub(crate) fn new_for_tests(t: T) -> NonNull<CcBox<T>> {
-        state(|state| CcBox::new(t, state))
+        let site = Location::caller();
+        state(|state| CcBox::new(t, state, site).unwrap_or_else(|err| panic!("{}", err)))
     }
 }
 
@@ -489,6 +591,36 @@ pub(super) fn get_next(&self) -> *mut Option<NonNull<CcBox<()>>> {
     pub(super) fn get_prev(&self) -> *mut Option<NonNull<CcBox<()>>> {
         self.prev.get()
     }
+
+    /// Returns whether the `CcBox` is [frozen][`crate::freeze`]. Immortal objects (see [`Cc::leak`]) are frozen too.
+    ///
+    /// The frozen state is not stored inside the counter: frozen objects are the only non-marked objects
+    /// with a `prev` link, since objects outside every list have `prev` set to `None` and the links of
+    /// the elements inside the frozen list are never `None` (see [`FrozenList`][`crate::lists::FrozenList`]).
+    #[inline]
+    pub(crate) fn is_frozen(&self) -> bool {
+        self.counter_marker.is_non_marked() && unsafe { (*self.get_prev()).is_some() }
+    }
+
+    /// Returns whether the `CcBox` has been [leaked][`Cc::leak`].
+    ///
+    /// Immortal objects are not inside the frozen list: their `prev` link points to themselves and their `next` link is `None`.
+    #[inline]
+    pub(crate) fn is_immortal(&self) -> bool {
+        self.is_frozen() && unsafe { (*self.get_next()).is_none() }
+    }
+
+    /// Makes the `CcBox` immortal. The `CcBox` must not be inside any list.
+    #[inline]
+    pub(crate) fn set_immortal(&self) {
+        debug_assert!(self.counter_marker.is_non_marked());
+        unsafe {
+            debug_assert!((*self.get_next()).is_none());
+            debug_assert!((*self.get_prev()).is_none());
+
+            *self.get_prev() = Some(NonNull::from(self).cast());
+        }
+    }
 }
 
 unsafe impl<T: ?Sized + Trace> Trace for CcBox<T> {
@@ -529,6 +661,11 @@ pub(crate) fn remove_from_list(ptr: NonNull<CcBox<()>>) {
 
 #[inline]
 pub(crate) fn add_to_list(ptr: NonNull<CcBox<()>>) {
+    if unsafe { ptr.as_ref() }.is_frozen() {
+        // Frozen (and immortal) objects are never buffered
+        return;
+    }
+
     let counter_marker = unsafe { ptr.as_ref() }.counter_marker();
 
     if !counter_marker.is_in_possible_cycles() {
@@ -554,6 +691,21 @@ pub(crate) fn add_to_list(ptr: NonNull<CcBox<()>>) {
     }
 }
 
+/// Removes a frozen `CcBox` from the frozen list. Must be called before deallocating a `CcBox`.
+#[inline]
+pub(crate) fn remove_from_frozen(ptr: NonNull<CcBox<()>>) {
+    let cc_box = unsafe { ptr.as_ref() };
+
+    // Immortal objects are never inside the frozen list
+    if cc_box.is_frozen() && !cc_box.is_immortal() {
+        cold(); // Frozen objects are rarely deallocated
+
+        let _ = FROZEN.try_with(|frozen| {
+            frozen.remove(ptr);
+        });
+    }
+}
+
 // Functions in common between every CcBox<_>
 impl CcBox<()> {
     #[inline]
@@ -579,6 +731,14 @@ pub(super) fn finalize_inner(ptr: NonNull<Self>) -> bool {
         }
     }
 
+    #[cfg(feature = "finalization")]
+    #[inline]
+    pub(crate) fn type_name_inner(ptr: NonNull<Self>) -> &'static str {
+        unsafe {
+            CcBox::get_traceable(ptr).as_ref().type_name()
+        }
+    }
+
     /// SAFETY: `drop_in_place` conditions must be true.
     #[inline]
     pub(super) unsafe fn drop_inner(ptr: NonNull<Self>) {
@@ -597,7 +757,7 @@ fn get_traceable(ptr: NonNull<Self>) -> NonNull<dyn InternalTrace> {
         #[cfg(feature = "nightly")]
         unsafe {
             let vtable = ptr.as_ref().vtable().vtable;
-            NonNull::from_raw_parts(ptr.cast(), vtable)
+            NonNull::from_raw_parts(ptr.cast::<()>(), vtable)
         }
 
         #[cfg(not(feature = "nightly"))]
@@ -614,6 +774,7 @@ fn trace(ptr: NonNull<Self>, ctx: &mut Context<'_>) {
                 root_list,
                 non_root_list,
                 queue,
+                ..
             } => {
                 if counter_marker.is_in_list_or_queue() {
                     // Check counters invariant (tracing_counter is always less or equal to counter)
@@ -633,6 +794,11 @@ fn trace(ptr: NonNull<Self>, ctx: &mut Context<'_>) {
                         non_root_list.add(ptr);
                     }
                 } else {
+                    if unsafe { ptr.as_ref() }.is_frozen() {
+                        // Frozen objects are never collected, so tracing stops at them
+                        return;
+                    }
+
                     if counter_marker.is_in_possible_cycles() {
                         let res = counter_marker.increment_tracing_counter();
                         debug_assert!(res.is_ok());
@@ -660,6 +826,9 @@ fn trace(ptr: NonNull<Self>, ctx: &mut Context<'_>) {
                     counter_marker.mark(Mark::InQueue);
                 }
             },
+            ContextInner::Walking { visitor } => {
+                visitor(ptr);
+            },
         }
     }
 }
@@ -733,6 +902,9 @@ trait InternalTrace: Trace {
 
     /// Safety: see `drop_in_place`
     unsafe fn drop_elem(&self);
+
+    #[cfg(feature = "finalization")]
+    fn type_name(&self) -> &'static str;
 }
 
 impl<T: ?Sized + Trace> InternalTrace for CcBox<T> {
@@ -741,6 +913,11 @@ fn finalize_elem(&self) {
         self.get_elem().finalize();
     }
 
+    #[cfg(feature = "finalization")]
+    fn type_name(&self) -> &'static str {
+        core::any::type_name::<T>()
+    }
+
     unsafe fn drop_elem(&self) {
         drop_in_place(self.get_elem_mut());
     }
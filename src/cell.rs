@@ -0,0 +1,418 @@
+//! Shareable mutable containers aware of the collector.
+//!
+//! The [`Trace`] implementation of [`RefCell`] doesn't trace the contained value while the [`RefCell`] is borrowed,
+//! even when the borrow is a shared one. Thus, a collection happening while a [`RefCell`] is borrowed (for example,
+//! because it has been triggered by a [`Cc::new`][`crate::Cc::new`] call inside a method holding a [`borrow`][`RefCell::borrow`])
+//! treats the contained [`Cc`][`crate::Cc`]s as alive.
+//!
+//! [`CcRefCell`] provides the same API of [`RefCell`], but its contents are traced also while shared-borrowed.
+//! Only mutably borrowed [`CcRefCell`]s are skipped, and the number of skipped cells is reported by
+//! [`CollectionReport::skipped_borrowed`][`crate::state::CollectionReport::skipped_borrowed`].
+//!
+//! [`CcSlot`] is a [`Cell`]-like container for an optional [`Cc`][`crate::Cc`], which is always traced and
+//! doesn't need any borrow flag, since it never hands out references to its content.
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r"# use rust_cc::*;
+# use rust_cc::cell::CcRefCell;
+#[derive(Trace, Finalize)]
+struct Node {
+    next: CcRefCell<Option<Cc<Node>>>,
+}
+
+let node = Cc::new(Node {
+    next: CcRefCell::new(None),
+});
+*node.next.borrow_mut() = Some(node.clone());
+
+let borrow = node.next.borrow();
+let other = Cc::new(Node {
+    next: CcRefCell::new(borrow.clone()),
+});
+# drop(borrow);
+# drop(other);
+```"]
+
+use core::cell::{BorrowError, BorrowMutError, Cell, Ref, RefCell, RefMut};
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Formatter};
+
+use crate::trace::{Context, Finalize, Trace};
+use crate::Cc;
+
+/// A [`RefCell`] whose contents are traced also while shared-borrowed.
+///
+/// See the [module-level documentation][`mod@crate::cell`] for more details.
+#[derive(Default)]
+pub struct CcRefCell<T: ?Sized> {
+    cell: RefCell<T>,
+}
+
+impl<T> CcRefCell<T> {
+    /// Creates a new [`CcRefCell`] containing `value`.
+    #[inline]
+    pub const fn new(value: T) -> CcRefCell<T> {
+        CcRefCell {
+            cell: RefCell::new(value),
+        }
+    }
+
+    /// Consumes the [`CcRefCell`], returning the wrapped value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner()
+    }
+
+    /// Replaces the wrapped value with a new one, returning the old value. See [`RefCell::replace`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    #[inline]
+    #[track_caller]
+    pub fn replace(&self, value: T) -> T {
+        self.cell.replace(value)
+    }
+
+    /// Replaces the wrapped value with a new one computed from `f`, returning the old value. See [`RefCell::replace_with`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    #[inline]
+    #[track_caller]
+    pub fn replace_with<F: FnOnce(&mut T) -> T>(&self, f: F) -> T {
+        self.cell.replace_with(f)
+    }
+
+    /// Swaps the wrapped value of `self` with the wrapped value of `other`. See [`RefCell::swap`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value in either [`CcRefCell`] is currently borrowed, or if `self` and `other` point to the same [`CcRefCell`].
+    #[inline]
+    pub fn swap(&self, other: &CcRefCell<T>) {
+        self.cell.swap(&other.cell)
+    }
+}
+
+impl<T: Default> CcRefCell<T> {
+    /// Takes the wrapped value, leaving [`Default::default`] in its place. See [`RefCell::take`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    #[inline]
+    pub fn take(&self) -> T {
+        self.cell.take()
+    }
+}
+
+impl<T: ?Sized> CcRefCell<T> {
+    /// Immutably borrows the wrapped value. See [`RefCell::borrow`].
+    ///
+    /// The value is still traced by the collector while the returned [`Ref`] is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    #[inline]
+    #[track_caller]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.cell.borrow()
+    }
+
+    /// Immutably borrows the wrapped value, returning an error if the value is currently mutably borrowed. See [`RefCell::try_borrow`].
+    #[inline]
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        self.cell.try_borrow()
+    }
+
+    /// Mutably borrows the wrapped value. See [`RefCell::borrow_mut`].
+    ///
+    /// The value is not traced by the collector while the returned [`RefMut`] is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    #[inline]
+    #[track_caller]
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.cell.borrow_mut()
+    }
+
+    /// Mutably borrows the wrapped value, returning an error if the value is currently borrowed. See [`RefCell::try_borrow_mut`].
+    #[inline]
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        self.cell.try_borrow_mut()
+    }
+
+    /// Returns a raw pointer to the underlying data in this cell. See [`RefCell::as_ptr`].
+    #[inline]
+    pub fn as_ptr(&self) -> *mut T {
+        self.cell.as_ptr()
+    }
+
+    /// Returns a mutable reference to the underlying data. See [`RefCell::get_mut`].
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.cell.get_mut()
+    }
+}
+
+// During a tracing phase no code other than Trace implementations is executed. Thus, no borrow can be started,
+// ended or upgraded until the end of the tracing phase and every trace call behaves the same.
+// A shared borrow only gives out shared references, so reading the value to trace it is fine.
+// Mutably borrowed values are skipped instead, since they may be in the middle of a modification
+unsafe impl<T: ?Sized + Trace> Trace for CcRefCell<T> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        match self.cell.try_borrow() {
+            Ok(borrow) => borrow.trace(ctx),
+            Err(_) => ctx.record_skipped_borrow(),
+        }
+    }
+}
+
+impl<T: ?Sized + Finalize> Finalize for CcRefCell<T> {
+    #[inline]
+    fn finalize(&self) {
+        if let Ok(borrow) = self.cell.try_borrow() {
+            borrow.finalize();
+        }
+    }
+}
+
+impl<T> From<T> for CcRefCell<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        CcRefCell::new(value)
+    }
+}
+
+impl<T: Clone> Clone for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn clone(&self) -> Self {
+        CcRefCell {
+            cell: self.cell.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the value in either [`CcRefCell`] is currently mutably borrowed.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cell == other.cell
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for CcRefCell<T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the value in either [`CcRefCell`] is currently mutably borrowed.
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.cell.partial_cmp(&other.cell)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the value in either [`CcRefCell`] is currently mutably borrowed.
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cell.cmp(&other.cell)
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for CcRefCell<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.cell.try_borrow() {
+            Ok(borrow) => f.debug_struct("CcRefCell").field("value", &&*borrow).finish(),
+            Err(_) => f.debug_struct("CcRefCell").field("value", &format_args!("<borrowed>")).finish(),
+        }
+    }
+}
+
+/// A [`Cell`]-like slot containing an optional [`Cc`].
+///
+/// Unlike a `RefCell<Option<Cc<T>>>`, a [`CcSlot`] doesn't need any borrow flag and it's always traced,
+/// since its API never hands out references to the contained [`Cc`].
+///
+/// # Example
+#[cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#[cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#[doc = r"# use rust_cc::*;
+# use rust_cc::cell::CcSlot;
+#[derive(Trace, Finalize)]
+struct Node {
+    next: CcSlot<Node>,
+}
+
+let node = Cc::new(Node {
+    next: CcSlot::empty(),
+});
+node.next.set(Some(node.clone()));
+assert!(node.next.get().is_some_and(|next| Cc::ptr_eq(&next, &node)));
+assert!(node.next.take().is_some());
+assert!(node.next.is_none());
+```"]
+pub struct CcSlot<T: ?Sized + Trace + 'static> {
+    cell: Cell<Option<Cc<T>>>,
+}
+
+impl<T: ?Sized + Trace + 'static> CcSlot<T> {
+    /// Creates a new [`CcSlot`] containing `value`.
+    #[inline]
+    pub const fn new(value: Option<Cc<T>>) -> CcSlot<T> {
+        CcSlot {
+            cell: Cell::new(value),
+        }
+    }
+
+    /// Creates a new empty [`CcSlot`].
+    #[inline]
+    pub const fn empty() -> CcSlot<T> {
+        CcSlot::new(None)
+    }
+
+    /// Returns a reference to the content of the slot.
+    ///
+    /// # Safety
+    ///
+    /// The slot must not be modified while the returned reference is alive.
+    /// Thus, no user code (like drop glues or [`Trace`] implementations) must be executed while using it.
+    #[inline(always)]
+    unsafe fn as_ref(&self) -> &Option<Cc<T>> {
+        &*self.cell.as_ptr()
+    }
+
+    /// Sets the content of the slot, dropping the previous one.
+    #[inline]
+    pub fn set(&self, value: Option<Cc<T>>) {
+        // The previous value is dropped after the slot has been updated
+        drop(self.cell.replace(value));
+    }
+
+    /// Replaces the content of the slot, returning the previous one.
+    #[inline]
+    pub fn replace(&self, value: Option<Cc<T>>) -> Option<Cc<T>> {
+        self.cell.replace(value)
+    }
+
+    /// Takes the content of the slot, leaving it empty.
+    #[inline]
+    pub fn take(&self) -> Option<Cc<T>> {
+        self.cell.take()
+    }
+
+    /// Returns a clone of the content of the slot.
+    #[inline]
+    #[track_caller]
+    pub fn get(&self) -> Option<Cc<T>> {
+        // SAFETY: Cloning a Cc doesn't execute any user code
+        unsafe { self.as_ref() }.clone()
+    }
+
+    /// Returns `true` if the slot contains a [`Cc`].
+    #[inline]
+    pub fn is_some(&self) -> bool {
+        // SAFETY: No user code is executed
+        unsafe { self.as_ref() }.is_some()
+    }
+
+    /// Returns `true` if the slot is empty.
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        !self.is_some()
+    }
+
+    /// Returns a mutable reference to the content of the slot.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut Option<Cc<T>> {
+        self.cell.get_mut()
+    }
+
+    /// Consumes the slot, returning its content.
+    #[inline]
+    pub fn into_inner(self) -> Option<Cc<T>> {
+        self.cell.into_inner()
+    }
+}
+
+// During tracing only the Trace implementations are executed and no Trace implementation can modify a CcSlot
+// (since that would require creating or dropping a Cc), so the slot cannot change while it's being traced
+unsafe impl<T: ?Sized + Trace + 'static> Trace for CcSlot<T> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        // SAFETY: Tracing a Cc doesn't execute any user code, it only updates the collector's data
+        if let Some(cc) = unsafe { self.as_ref() } {
+            cc.trace(ctx);
+        }
+    }
+}
+
+impl<T: ?Sized + Trace + 'static> Finalize for CcSlot<T> {}
+
+impl<T: ?Sized + Trace + 'static> Default for CcSlot<T> {
+    #[inline]
+    fn default() -> Self {
+        CcSlot::empty()
+    }
+}
+
+impl<T: ?Sized + Trace + 'static> Clone for CcSlot<T> {
+    /// Creates a new [`CcSlot`] containing a clone of the content of `self`.
+    #[inline]
+    #[track_caller]
+    fn clone(&self) -> Self {
+        CcSlot::new(self.get())
+    }
+}
+
+impl<T: ?Sized + Trace + 'static> From<Cc<T>> for CcSlot<T> {
+    #[inline]
+    fn from(value: Cc<T>) -> Self {
+        CcSlot::new(Some(value))
+    }
+}
+
+impl<T: ?Sized + Trace + 'static> From<Option<Cc<T>>> for CcSlot<T> {
+    #[inline]
+    fn from(value: Option<Cc<T>>) -> Self {
+        CcSlot::new(value)
+    }
+}
+
+impl<T: ?Sized + Trace + 'static> Debug for CcSlot<T> {
+    /// The content of the slot isn't formatted, to avoid executing user code while referencing it.
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CcSlot").field("is_some", &self.is_some()).finish()
+    }
+}
@@ -0,0 +1,167 @@
+//! Traceable closures.
+//!
+//! Closures capturing [`Cc`]s cannot implement [`Trace`], so cycles passing through them (like a [`Cc`] owning a
+//! callback which captures the same [`Cc`]) are never collected.
+//!
+//! A [`TracedFn`] stores the captured values in a traceable environment, separated from the code of the closure,
+//! which is a plain function pointer. Thus, the captured [`Cc`]s are traced and cycles through the closure are collected.
+//!
+//! The [`cc_closure!`][`crate::cc_closure`] macro can be used to conveniently create a [`TracedFn`].
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r"# use std::cell::{Cell, RefCell};
+# use rust_cc::*;
+# use rust_cc::closure::TracedFn;
+#[derive(Trace, Finalize)]
+struct Widget {
+    clicks: Cell<u32>,
+    on_click: RefCell<Option<TracedFn<(u32,), u32>>>,
+}
+
+let widget = Cc::new(Widget {
+    clicks: Cell::new(0),
+    on_click: RefCell::new(None),
+});
+
+let this = widget.clone();
+let callback = cc_closure!(move |amount: u32| {
+    this.clicks.set(this.clicks.get() + amount);
+    this.clicks.get()
+}, captures = [this]);
+*widget.on_click.borrow_mut() = Some(callback);
+
+assert_eq!(2, widget.on_click.borrow().as_ref().unwrap().call((2,)));
+drop(widget);
+collect_cycles(); // The widget and the callback are collected
+```"]
+//!
+//! [`Cc`]: `crate::Cc`
+
+use alloc::boxed::Box;
+use core::fmt::{self, Debug, Formatter};
+
+use crate::trace::{Context, Finalize, Trace};
+
+/// A closure whose captured values are traced.
+///
+/// `Args` is the tuple of the arguments of the closure and `Ret` is its return type.
+/// See the [module-level documentation][`mod@crate::closure`] for more details.
+pub struct TracedFn<Args, Ret> {
+    env: Box<dyn Environment<Args, Ret>>,
+}
+
+trait Environment<Args, Ret>: Trace {
+    fn call(&self, args: Args) -> Ret;
+}
+
+struct Env<C: Trace, Args, Ret> {
+    captures: C,
+    code: fn(&C, Args) -> Ret,
+}
+
+impl<C: Trace, Args, Ret> Environment<Args, Ret> for Env<C, Args, Ret> {
+    #[inline]
+    fn call(&self, args: Args) -> Ret {
+        (self.code)(&self.captures, args)
+    }
+}
+
+unsafe impl<C: Trace, Args, Ret> Trace for Env<C, Args, Ret> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.captures.trace(ctx);
+    }
+}
+
+impl<C: Trace, Args, Ret> Finalize for Env<C, Args, Ret> {
+    #[inline]
+    fn finalize(&self) {
+        self.captures.finalize();
+    }
+}
+
+impl<Args: 'static, Ret: 'static> TracedFn<Args, Ret> {
+    /// Creates a new [`TracedFn`] from the captured values and the code of the closure.
+    ///
+    /// Since `code` is a function pointer, it cannot capture anything: every captured value must be put into `captures`,
+    /// which is passed by reference to `code` at every call.
+    #[inline]
+    pub fn new<C: Trace + 'static>(captures: C, code: fn(&C, Args) -> Ret) -> TracedFn<Args, Ret> {
+        TracedFn {
+            env: Box::new(Env { captures, code }),
+        }
+    }
+}
+
+impl<Args, Ret> TracedFn<Args, Ret> {
+    /// Calls the closure with the provided arguments.
+    #[inline]
+    pub fn call(&self, args: Args) -> Ret {
+        self.env.call(args)
+    }
+}
+
+unsafe impl<Args, Ret> Trace for TracedFn<Args, Ret> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.env.trace(ctx);
+    }
+}
+
+impl<Args, Ret> Finalize for TracedFn<Args, Ret> {
+    #[inline]
+    fn finalize(&self) {
+        self.env.finalize();
+    }
+}
+
+impl<Args, Ret> Debug for TracedFn<Args, Ret> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TracedFn").finish_non_exhaustive()
+    }
+}
+
+/// Creates a [`TracedFn`][`crate::closure::TracedFn`] from a closure and the list of its captured values.
+///
+/// The captured values are moved into the traceable environment of the [`TracedFn`]
+/// and are accessible by reference (using the same names) inside the body of the closure.
+/// The closure must not capture anything else, otherwise a compilation error is emitted.
+///
+/// The types of the arguments can be omitted if they can be inferred from the context.
+/// The resulting [`TracedFn`][`crate::closure::TracedFn`] takes the arguments as a tuple.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::*;
+///# use rust_cc::closure::TracedFn;
+/// let name = Cc::new(String::from("world"));
+/// let greet: TracedFn<(&str,), String> = cc_closure!(move |greeting| {
+///     format!("{greeting}, {name}!")
+/// }, captures = [name]);
+///
+/// assert_eq!("Hello, world!", greet.call(("Hello",)));
+/// ```
+#[macro_export]
+macro_rules! cc_closure {
+    ($(move)? || $body:expr, captures = [$($capture:ident),* $(,)?] $(,)?) => {
+        $crate::cc_closure!(| | $body, captures = [$($capture),*])
+    };
+    ($(move)? |$($arg:ident $(: $ty:ty)?),* $(,)?| $body:expr, captures = [$($capture:ident),* $(,)?] $(,)?) => {
+        $crate::closure::TracedFn::new(
+            ($($capture,)*),
+            #[allow(unused_variables)]
+            |($($capture,)*): &_, ($($arg,)*): ($($crate::cc_closure!(@ty $($ty)?),)*)| $body,
+        )
+    };
+    (@ty $ty:ty) => { $ty };
+    (@ty) => { _ };
+}
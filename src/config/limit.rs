@@ -0,0 +1,87 @@
+use alloc::alloc::Layout;
+use alloc::rc::Rc;
+use core::fmt::{self, Debug, Formatter};
+
+use thiserror::Error;
+use crate::state::State;
+
+/// An error returned when allocating a [`Cc`][`crate::Cc`] would exceed the
+/// [memory limit][`crate::config::Config::memory_limit`].
+#[non_exhaustive]
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("allocating {requested_bytes} bytes would exceed the memory limit of {limit} bytes ({allocated_bytes} bytes already allocated)")]
+pub struct MemoryLimitError {
+    /// The number of bytes requested by the failed allocation.
+    pub requested_bytes: usize,
+    /// The number of bytes allocated by the collector when the allocation failed (see [`state::allocated_bytes`][`crate::state::allocated_bytes`]).
+    pub allocated_bytes: usize,
+    /// The memory limit which would have been exceeded.
+    pub limit: usize,
+}
+
+type HandlerFn = dyn Fn(&MemoryLimitError) -> Option<usize>;
+
+#[derive(Clone)]
+pub(super) struct MemoryLimitHandler(pub(super) Rc<HandlerFn>);
+
+impl Debug for MemoryLimitHandler {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryLimitHandler").finish_non_exhaustive()
+    }
+}
+
+#[inline]
+fn exceeds(layout: Layout, state: &State, limit: usize) -> Result<(), MemoryLimitError> {
+    let allocated_bytes = state.allocated_bytes();
+    match allocated_bytes.checked_add(layout.size()) {
+        Some(total) if total <= limit => Ok(()),
+        _ => Err(MemoryLimitError {
+            requested_bytes: layout.size(),
+            allocated_bytes,
+            limit,
+        }),
+    }
+}
+
+/// Checks whether an allocation of the provided layout would exceed the memory limit.
+///
+/// If the limit would be exceeded, a collection is forced (if not already collecting) and then
+/// the memory limit handler, if any, is invoked.
+#[inline]
+pub(crate) fn check_memory_limit(layout: Layout, state: &State) -> Result<(), MemoryLimitError> {
+    // Don't enforce the limit if the configuration cannot be accessed (for example, when allocating inside a collection policy)
+    let Ok(Some(limit)) = super::config(|config| config.memory_limit) else {
+        return Ok(());
+    };
+
+    if exceeds(layout, state, limit).is_ok() {
+        return Ok(());
+    }
+
+    cold_check_memory_limit(layout, state, limit)
+}
+
+#[cold]
+#[inline(never)]
+fn cold_check_memory_limit(layout: Layout, state: &State, limit: usize) -> Result<(), MemoryLimitError> {
+    crate::force_collection(state);
+
+    let err = match exceeds(layout, state, limit) {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+
+    // Clone the handler to avoid holding the configuration borrowed while the handler is executing
+    let Ok(Some(handler)) = super::config(|config| config.memory_limit_handler.clone()) else {
+        return Err(err);
+    };
+
+    match (handler.0)(&err) {
+        Some(new_limit) => {
+            let _ = super::config(|config| config.memory_limit = Some(new_limit));
+            exceeds(layout, state, new_limit)
+        },
+        None => Err(err),
+    }
+}
@@ -0,0 +1,802 @@
+//! Configuration of the garbage collector.
+//!
+//! The configuration can be accessed using the [`config`][`fn@config`] function.
+//!
+//! # Automatic collection executions
+//!
+//! Collections can be automatically started if [`auto_collect`][`fn@Config::auto_collect`] is set to `true`.
+//!
+//! Whether to start a collection is decided by a [`CollectionPolicy`], which is invoked when calling a function which
+//! may start a collection (e.g. [`Cc::new`][`crate::Cc::new`]) and which is adjusted at the end of every collection.
+//!
+//! By default, the [`AdaptivePolicy`] is used: a *threshold* is kept over the number of allocated bytes (plus the external memory
+//! reported using [`state::add_external_memory`][`crate::state::add_external_memory`]) and a collection is started when this number exceeds it.
+//!
+//! At the end of the automatically started collection, if the *threshold* is still lower than the number of allocated bytes
+//! then it is doubled until it exceed it.
+//!
+//! Instead, if the number of allocated bytes exceed the *threshold* multiplied by the [`adjustment_percent`][`fn@Config::adjustment_percent`],
+//! then the *threshold* is halved until the condition becomes true.
+//!
+//! Finally, a collection may also happen if the number of objects buffered to be processed in the next collection (see [`Cc::mark_alive`][`crate::Cc::mark_alive`])
+//! exceeds the [`buffered_objects_threshold`][`fn@Config::buffered_objects_threshold`]. This parameter is disabled by default, but can be enabled by
+//! using [`set_buffered_objects_threshold`][`fn@Config::set_buffered_objects_threshold`].
+//!
+//! A different policy can be installed using [`set_collection_policy`][`fn@Config::set_collection_policy`]. Other than the default one,
+//! the [`FixedThresholdPolicy`], the [`AllocationCountPolicy`] and the [`PauseTimePolicy`] are also provided.
+//!
+//! The [`PauseTimePolicy`] is meant for interactive applications, where the duration of a single collection matters more than throughput.
+//! It can be installed using [`set_target_pause`][`fn@Config::set_target_pause`].
+//!
+//! # Memory limit
+//!
+//! A hard limit over the number of bytes allocated by the collector can be set using [`set_memory_limit`][`fn@Config::set_memory_limit`].
+//! When allocating a [`Cc`][`crate::Cc`] would exceed the limit, a collection is forced. If the limit would still be exceeded,
+//! the handler set with [`set_memory_limit_handler`][`fn@Config::set_memory_limit_handler`] is invoked, which can raise the limit.
+//! Otherwise, [`Cc::new`][`crate::Cc::new`] panics and [`Cc::try_new`][`crate::Cc::try_new`] returns a [`MemoryLimitError`].
+//!
+//! Like the rest of this module, the memory limit is available only when the `auto-collect` feature is enabled.
+//! Without it, allocations are never limited and [`Cc::try_new`][`crate::Cc::try_new`] isn't provided.
+//!
+//! # Finalization rounds
+//!
+//! When finalization is enabled, after running the finalizers a collection executes the collection algorithm again,
+//! to detect resurrected objects and to collect the garbage created by finalizers. The number of such rounds is limited
+//! by the [`finalization_round_limit`][`fn@Config::finalization_round_limit`] (10 by default), after which the remaining objects
+//! are left for the next collection. Whether the limit has been reached is reported by
+//! [`CollectionReport::round_limit_reached`][`crate::state::CollectionReport::round_limit_reached`].
+//!
+//! # Finalization order
+//!
+//! By default, the objects collected together are finalized and dropped in an unspecified order. Setting the
+//! [`FinalizationOrder::Topological`] order using [`set_finalization_order`][`fn@Config::set_finalization_order`],
+//! they are instead finalized and dropped following the references between them: if an object references another one,
+//! the referencing object is finalized (and dropped) first, so that the referenced object hasn't been finalized (or dropped) yet
+//! when the finalizer (or destructor) of the referencing one runs. References to objects which are not being collected are ignored.
+//!
+//! Objects referencing each other, directly or indirectly, form a *strongly connected component* (for example, the objects
+//! of a cycle) and there's no order satisfying the rule above between them. Components are thus ordered as a whole: a component
+//! is finalized after every component referencing it and before every component referenced by it. Inside a component, objects
+//! are finalized in the order they're visited by a depth-first traversal of their references. For example, if `a` references `b`,
+//! which references `c`, which references `b` again, then `a` is always finalized first, while `b` and `c` are finalized in
+//! the order they're visited. This order is deterministic, i.e. it only depends on the object graph and on the order of the
+//! operations executed by the program.
+//!
+//! Ordering requires tracing the collected objects one more time, so it makes collections slower.
+//!
+//! # Panics during collections
+//!
+//! By default, a panic in a [`Trace`][`crate::Trace`], [`Finalize`][`crate::Finalize`] or [`Drop`] implementation
+//! during a collection is propagated, leaving the collector in a consistent state (the objects which were being processed
+//! may be leaked). A different [`PanicPolicy`] can be set using [`set_panic_policy`][`fn@Config::set_panic_policy`],
+//! to either abort the process or to catch the panic.
+//!
+//! # Temporary overrides
+//!
+//! The [`override_config`][`fn@override_config`] function can be used to temporarily change some configuration values.
+//! It returns a [`ConfigGuard`] which restores the overridden values when dropped (also during a panic).
+//! The [`pause_auto_collect`][`fn@pause_auto_collect`] and [`with_config`][`fn@with_config`] utilities are built on top of it.
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::num::NonZeroUsize;
+use core::time::Duration;
+use core::marker::PhantomData;
+use alloc::vec::Vec;
+
+use thiserror::Error;
+use crate::lists::PossibleCycles;
+use crate::state::State;
+use crate::utils;
+use limit::MemoryLimitHandler;
+
+mod limit;
+mod policy;
+
+pub(crate) use limit::check_memory_limit;
+pub use limit::MemoryLimitError;
+pub use policy::{AdaptivePolicy, AllocationCountPolicy, CollectionPolicy, FixedThresholdPolicy, PauseTimePolicy, PolicyContext};
+
+utils::rust_cc_thread_local! {
+    pub(crate) static CONFIG: RefCell<Config> = const { RefCell::new(Config::new()) };
+
+    // The overrides of the ConfigGuards which haven't been dropped yet, in creation order
+    static OVERRIDES: RefCell<Overrides> = const { RefCell::new(Overrides::new()) };
+
+    // Restorations which couldn't be done when their ConfigGuard was dropped, since the configuration was being accessed
+    static PENDING_RESTORATIONS: RefCell<Vec<(Config, Fields)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Access the configuration.
+///
+/// Returns [`Err`] if the configuration is already being accessed.
+///
+/// # Panics
+///
+/// Panics if the provided closure panics.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::config::config;
+/// let res = config(|config| {
+///     // Edit the configuration
+/// }).unwrap();
+/// ```
+pub fn config<F, R>(f: F) -> Result<R, ConfigAccessError>
+where
+    F: FnOnce(&mut Config) -> R,
+{
+    CONFIG.try_with(|config| {
+        config
+        .try_borrow_mut()
+        .or(Err(ConfigAccessError::ConcurrentAccessError))
+        .map(|mut config| {
+            // Pending restorations are applied also before calling f, so that f never sees stale values
+            apply_pending_restorations(&mut config);
+            let res = f(&mut config);
+            apply_pending_restorations(&mut config);
+            res
+        })
+    }).unwrap_or(Err(ConfigAccessError::AccessError))
+}
+
+/// Temporarily overrides the configuration.
+///
+/// The provided closure can edit the configuration like in [`config`][`fn@config`]. The returned [`ConfigGuard`]
+/// restores the values modified by the closure when dropped. Values which haven't been modified are left untouched.
+///
+/// Returns [`Err`] if the configuration is already being accessed.
+///
+/// See [`ConfigGuard`] for more details.
+///
+/// # Panics
+///
+/// Panics if the provided closure panics.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::config::{config, override_config};
+/// let guard = override_config(|config| {
+///     config.set_adjustment_percent(0.5);
+/// }).unwrap();
+///
+/// assert_eq!(0.5, config(|config| config.adjustment_percent()).unwrap());
+/// drop(guard);
+/// assert_ne!(0.5, config(|config| config.adjustment_percent()).unwrap());
+/// ```
+pub fn override_config<F>(f: F) -> Result<ConfigGuard, ConfigAccessError>
+where
+    F: FnOnce(&mut Config),
+{
+    override_fields(f, Fields::NONE)
+}
+
+/// Like [`override_config`][`fn@override_config`], but `fields` are restored even if `f` didn't modify them.
+fn override_fields<F>(f: F, fields: Fields) -> Result<ConfigGuard, ConfigAccessError>
+where
+    F: FnOnce(&mut Config),
+{
+    let (previous, fields) = config(|config| {
+        let previous = config.clone();
+        f(config);
+        let fields = fields.union(Config::changed_fields(&previous, config));
+        (previous, fields)
+    })?;
+
+    OVERRIDES.try_with(|overrides| {
+        let mut overrides = overrides.try_borrow_mut().or(Err(ConfigAccessError::ConcurrentAccessError))?;
+        let id = overrides.next_id;
+        overrides.next_id += 1;
+        overrides.active.push(ActiveOverride { id, previous, fields });
+        Ok(ConfigGuard {
+            id,
+            _phantom: PhantomData,
+        })
+    }).unwrap_or(Err(ConfigAccessError::AccessError))
+}
+
+/// Temporarily disables the automatic execution of collections.
+///
+/// Automatic collections are enabled again (if they were enabled before calling this function) when the returned [`ConfigGuard`] is dropped.
+///
+/// Returns [`Err`] if the configuration is already being accessed.
+///
+/// See [`ConfigGuard`] for more details.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::config::pause_auto_collect;
+/// {
+///     let _guard = pause_auto_collect().unwrap();
+///     // Latency-critical code, no collection will be started automatically
+/// }
+/// // Collections are automatically started again
+/// ```
+#[inline]
+pub fn pause_auto_collect() -> Result<ConfigGuard, ConfigAccessError> {
+    // Always restore auto_collect, so that nested pauses keep collections disabled until the last one is dropped
+    override_fields(|config| config.set_auto_collect(false), Fields::AUTO_COLLECT)
+}
+
+/// Executes `f` with the configuration temporarily overridden by `override_fn`.
+///
+/// The overridden values are restored when `f` returns or panics.
+///
+/// Returns [`Err`] without calling `f` if the configuration is already being accessed.
+///
+/// See [`override_config`][`fn@override_config`] and [`ConfigGuard`] for more details.
+///
+/// # Panics
+///
+/// Panics if one of the provided closures panics.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::config::with_config;
+/// let res = with_config(|config| config.set_auto_collect(false), || {
+///     // Latency-critical code, no collection will be started automatically
+///     42
+/// }).unwrap();
+/// assert_eq!(42, res);
+/// ```
+pub fn with_config<O, F, R>(override_fn: O, f: F) -> Result<R, ConfigAccessError>
+where
+    O: FnOnce(&mut Config),
+    F: FnOnce() -> R,
+{
+    let _guard = override_config(override_fn)?;
+    Ok(f())
+}
+
+/// A guard which restores the configuration values overridden by [`override_config`][`fn@override_config`] when dropped.
+///
+/// # Nesting
+///
+/// Only the values which have been modified by the overriding closure are restored. Thus, guards can be nested
+/// and dropped in any order: a value is restored only when every guard overriding it has been dropped, and it always
+/// returns to the state it had before the creation of the first of those guards.
+///
+/// # Drop behavior
+///
+/// The configuration is restored when the guard is dropped, also during a collection (e.g. when dropped by a finalizer).
+/// The restored values are used by the collector the next time it reads the configuration.
+///
+/// If the guard is dropped while the configuration is being accessed, the restoration is postponed until the end of that
+/// access. This happens when the guard is dropped inside the closure passed to [`config`][`fn@config`] or by code invoked
+/// by the collector while it accesses the configuration, like a [`CollectionPolicy`] or a memory limit handler.
+/// The postponed restorations are always applied before the configuration is read again.
+///
+/// If the configuration cannot be accessed anymore (i.e. when the thread is being destroyed), nothing is restored.
+#[must_use = "the configuration is restored immediately if the guard is dropped"]
+pub struct ConfigGuard {
+    id: u64,
+    _phantom: PhantomData<Rc<()>>, // Make ConfigGuard !Send and !Sync, since overrides are thread-local
+}
+
+impl Drop for ConfigGuard {
+    #[inline]
+    fn drop(&mut self) {
+        // Values dropped while OVERRIDES is borrowed (like collection policies) may drop other guards,
+        // so they're moved out and dropped only after the borrow has ended
+        let mut to_drop = Vec::new();
+
+        let restoration = OVERRIDES.try_with(|overrides| {
+            let mut overrides = overrides.try_borrow_mut().ok()?;
+            let index = overrides.active.iter().position(|o| o.id == self.id)?;
+            let ActiveOverride { previous, mut fields, .. } = overrides.active.remove(index);
+
+            // Values also overridden by the guards created after this one are not restored now. Instead, the
+            // first of those guards takes this guard's previous values, restoring them when it gets dropped
+            for later in &mut overrides.active[index..] {
+                let shared = fields.intersection(later.fields);
+                if !shared.is_empty() {
+                    to_drop.push(later.previous.clone());
+                    later.previous.copy_fields(&previous, shared);
+                    fields = fields.difference(shared);
+                }
+            }
+            Some((previous, fields))
+        }).ok().flatten();
+        drop(to_drop);
+
+        let (previous, fields) = match restoration {
+            Some((previous, fields)) if !fields.is_empty() => (previous, fields),
+            _ => return,
+        };
+
+        let _ = CONFIG.try_with(|config| {
+            match config.try_borrow_mut() {
+                Ok(mut config) => {
+                    config.copy_fields(&previous, fields);
+                    // Restoring may drop values (like a collection policy) which drop other guards
+                    apply_pending_restorations(&mut config);
+                },
+                Err(_) => {
+                    // The configuration is being accessed, restore it at the end of the access (see the config function)
+                    let _ = PENDING_RESTORATIONS.try_with(|pending| {
+                        if let Ok(mut pending) = pending.try_borrow_mut() {
+                            pending.push((previous, fields));
+                        }
+                    });
+                },
+            }
+        });
+    }
+}
+
+struct Overrides {
+    next_id: u64,
+    active: Vec<ActiveOverride>,
+}
+
+impl Overrides {
+    #[inline]
+    const fn new() -> Self {
+        Self {
+            next_id: 0,
+            active: Vec::new(),
+        }
+    }
+}
+
+struct ActiveOverride {
+    id: u64,
+    // The values to restore, only the ones selected by fields are meaningful
+    previous: Config,
+    fields: Fields,
+}
+
+/// A set of configuration values which can be restored by a [`ConfigGuard`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Fields(u16);
+
+impl Fields {
+    const NONE: Fields = Fields(0);
+    const BYTES_THRESHOLD: Fields = Fields(1 << 0);
+    const ADJUSTMENT_PERCENT: Fields = Fields(1 << 1);
+    const BUFFERED_OBJECTS_THRESHOLD: Fields = Fields(1 << 2);
+    const COLLECTION_POLICY: Fields = Fields(1 << 3);
+    const MEMORY_LIMIT: Fields = Fields(1 << 4);
+    const MEMORY_LIMIT_HANDLER: Fields = Fields(1 << 5);
+    const AUTO_COLLECT: Fields = Fields(1 << 6);
+    const PANIC_POLICY: Fields = Fields(1 << 7);
+    const FINALIZATION_ORDER: Fields = Fields(1 << 8);
+    #[cfg(feature = "finalization")]
+    const FINALIZATION_ROUND_LIMIT: Fields = Fields(1 << 9);
+
+    #[inline]
+    fn contains(self, other: Fields) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    #[inline]
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    fn union(self, other: Fields) -> Fields {
+        Fields(self.0 | other.0)
+    }
+
+    #[inline]
+    fn intersection(self, other: Fields) -> Fields {
+        Fields(self.0 & other.0)
+    }
+
+    #[inline]
+    fn difference(self, other: Fields) -> Fields {
+        Fields(self.0 & !other.0)
+    }
+}
+
+#[inline]
+fn apply_pending_restorations(config: &mut Config) {
+    let _ = PENDING_RESTORATIONS.try_with(|pending| {
+        // Restoring may drop other guards, which add new pending restorations
+        loop {
+            let restorations = match pending.try_borrow_mut() {
+                Ok(mut pending) if !pending.is_empty() => core::mem::take(&mut *pending),
+                _ => return,
+            };
+            // Restore in the same order the guards have been dropped
+            for (previous, fields) in restorations {
+                config.copy_fields(&previous, fields);
+            }
+        }
+    });
+}
+
+/// An error returned by [`config`][`fn@config`].
+#[non_exhaustive]
+#[derive(Error, Debug)]
+pub enum ConfigAccessError {
+    /// The configuration couldn't be accessed.
+    #[error("couldn't access the configuration")]
+    AccessError,
+    /// The configuration was already being accessed.
+    #[error("the configuration is already being accessed")]
+    ConcurrentAccessError,
+}
+
+/// The configuration of the garbage collector.
+#[derive(Debug, Clone)]
+pub struct Config {
+    // The default policy is kept inline to allow a const initialization of the configuration
+    default_policy: AdaptivePolicy,
+    custom_policy: Option<Rc<RefCell<dyn CollectionPolicy>>>,
+    memory_limit: Option<usize>,
+    memory_limit_handler: Option<MemoryLimitHandler>,
+    auto_collect: bool,
+    panic_policy: PanicPolicy,
+    finalization_order: FinalizationOrder,
+    #[cfg(feature = "finalization")]
+    finalization_round_limit: Option<NonZeroUsize>,
+    _phantom: PhantomData<Rc<()>>, // Make Config !Send and !Sync
+}
+
+impl Config {
+    #[inline]
+    const fn new() -> Self {
+        Self {
+            default_policy: AdaptivePolicy::new(),
+            custom_policy: None,
+            memory_limit: None,
+            memory_limit_handler: None,
+            auto_collect: true,
+            panic_policy: PanicPolicy::Propagate,
+            finalization_order: FinalizationOrder::Unordered,
+            #[cfg(feature = "finalization")]
+            finalization_round_limit: Some(crate::DEFAULT_FINALIZATION_ROUND_LIMIT),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` if collections can be automatically started, `false` otherwise.
+    #[inline]
+    pub fn auto_collect(&self) -> bool {
+        self.auto_collect
+    }
+
+    /// Sets whether collections can be automatically started.
+    #[inline]
+    pub fn set_auto_collect(&mut self, auto_collect: bool) {
+        self.auto_collect = auto_collect;
+    }
+
+    /// Installs a [`CollectionPolicy`], replacing the previously installed one.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn set_collection_policy(&mut self, policy: impl CollectionPolicy + 'static) {
+        self.custom_policy = Some(Rc::new(RefCell::new(policy)));
+    }
+
+    /// Installs a [`PauseTimePolicy`] with the provided target pause, replacing the previously installed [`CollectionPolicy`].
+    ///
+    /// # Example
+    /// ```rust
+    ///# use std::time::Duration;
+    ///# use rust_cc::config::config;
+    /// config(|config| config.set_target_pause(Duration::from_millis(2))).unwrap();
+    ///# config(|config| config.reset_collection_policy()).unwrap();
+    /// ```
+    #[inline]
+    pub fn set_target_pause(&mut self, target: Duration) {
+        self.set_collection_policy(PauseTimePolicy::new(target));
+    }
+
+    /// Uninstalls the current [`CollectionPolicy`], returning to the default [`AdaptivePolicy`].
+    ///
+    /// The parameters of the default policy are not reset.
+    #[inline]
+    pub fn reset_collection_policy(&mut self) {
+        self.custom_policy = None;
+    }
+
+    /// Returns `true` if a [`CollectionPolicy`] different from the default one is installed, `false` otherwise.
+    #[inline]
+    pub fn has_custom_collection_policy(&self) -> bool {
+        self.custom_policy.is_some()
+    }
+
+    /// Returns the default [`AdaptivePolicy`], which is used when no other [`CollectionPolicy`] is installed.
+    #[inline]
+    pub fn default_policy(&self) -> &AdaptivePolicy {
+        &self.default_policy
+    }
+
+    /// Returns a mutable reference to the default [`AdaptivePolicy`], which is used when no other [`CollectionPolicy`] is installed.
+    #[inline]
+    pub fn default_policy_mut(&mut self) -> &mut AdaptivePolicy {
+        &mut self.default_policy
+    }
+
+    /// Returns the threshold adjustment percent of the default policy.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn adjustment_percent(&self) -> f64 {
+        self.default_policy.adjustment_percent()
+    }
+
+    /// Sets the threshold adjustment percent of the default policy.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided `percent` isn't between 0 and 1 (included).
+    #[inline]
+    #[track_caller]
+    pub fn set_adjustment_percent(&mut self, percent: f64) {
+        self.default_policy.set_adjustment_percent(percent);
+    }
+
+    /// Returns the buffered-objects threshold of the default policy (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+    ///
+    /// Returns [`None`] if this parameter isn't used to start a collection.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn buffered_objects_threshold(&self) -> Option<NonZeroUsize> {
+        self.default_policy.buffered_objects_threshold()
+    }
+
+    /// Sets the buffered-objects threshold of the default policy (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+    ///
+    /// If the provided `threshold` is [`None`], then this parameter will not be used to start a collection.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    #[track_caller]
+    pub fn set_buffered_objects_threshold(&mut self, threshold: Option<NonZeroUsize>) {
+        self.default_policy.set_buffered_objects_threshold(threshold);
+    }
+
+    /// Returns the memory limit, i.e. the maximum number of bytes which can be allocated by the collector.
+    ///
+    /// Returns [`None`] if there's no limit.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Sets the memory limit, i.e. the maximum number of bytes which can be allocated by the collector.
+    ///
+    /// If the provided `limit` is [`None`], then no limit is enforced. The limit is enforced even if
+    /// [`auto_collect`][`fn@Config::auto_collect`] is `false`.
+    ///
+    /// Note that only the memory allocated for [`Cc`][`crate::Cc`]s is considered (see [`state::allocated_bytes`][`crate::state::allocated_bytes`]),
+    /// external memory reported using [`state::add_external_memory`][`crate::state::add_external_memory`] isn't.
+    /// Setting a limit lower than the number of already allocated bytes doesn't deallocate anything,
+    /// but makes every subsequent allocation fail until enough memory is freed.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    /// Sets the handler invoked when an allocation would exceed the [memory limit][`fn@Config::memory_limit`]
+    /// even after a collection has been forced.
+    ///
+    /// The handler can return a new memory limit, which replaces the current one and is checked again.
+    /// If the handler returns [`None`], the allocation fails.
+    ///
+    /// The handler is invoked while the configuration is not being accessed, so it can access it.
+    ///
+    /// # Example
+    /// ```rust
+    ///# use rust_cc::config::config;
+    /// config(|config| {
+    ///     config.set_memory_limit(Some(1024 * 1024));
+    ///     config.set_memory_limit_handler(|err| {
+    ///         // Allow the limit to grow up to 2 MiB
+    ///         Some(err.limit * 2).filter(|&limit| limit <= 2 * 1024 * 1024)
+    ///     });
+    /// }).unwrap();
+    ///# config(|config| {
+    ///#     config.set_memory_limit(None);
+    ///#     config.remove_memory_limit_handler();
+    ///# }).unwrap();
+    /// ```
+    #[inline]
+    pub fn set_memory_limit_handler(&mut self, handler: impl Fn(&MemoryLimitError) -> Option<usize> + 'static) {
+        self.memory_limit_handler = Some(MemoryLimitHandler(Rc::new(handler)));
+    }
+
+    /// Removes the handler set with [`set_memory_limit_handler`][`fn@Config::set_memory_limit_handler`].
+    #[inline]
+    pub fn remove_memory_limit_handler(&mut self) {
+        self.memory_limit_handler = None;
+    }
+
+    /// Returns the [`PanicPolicy`] applied when a panic happens during a collection.
+    #[inline]
+    pub fn panic_policy(&self) -> PanicPolicy {
+        self.panic_policy
+    }
+
+    /// Sets the [`PanicPolicy`] applied when a panic happens during a collection.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn set_panic_policy(&mut self, panic_policy: PanicPolicy) {
+        self.panic_policy = panic_policy;
+    }
+
+    /// Returns the [`FinalizationOrder`] of the objects collected together.
+    #[inline]
+    pub fn finalization_order(&self) -> FinalizationOrder {
+        self.finalization_order
+    }
+
+    /// Sets the [`FinalizationOrder`] of the objects collected together.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[inline]
+    pub fn set_finalization_order(&mut self, order: FinalizationOrder) {
+        self.finalization_order = order;
+    }
+
+    /// Returns the maximum number of rounds executed by a single collection, or [`None`] if there's no limit.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[cfg(feature = "finalization")]
+    #[inline]
+    pub fn finalization_round_limit(&self) -> Option<NonZeroUsize> {
+        self.finalization_round_limit
+    }
+
+    /// Sets the maximum number of rounds executed by a single collection.
+    ///
+    /// If the provided `limit` is [`None`], collections run until no object is left to process.
+    ///
+    /// See the [module-level documentation][`mod@crate::config`] for more details.
+    #[cfg(feature = "finalization")]
+    #[inline]
+    pub fn set_finalization_round_limit(&mut self, limit: Option<NonZeroUsize>) {
+        self.finalization_round_limit = limit;
+    }
+
+    /// Returns the values which differ between `previous` and `overridden`.
+    fn changed_fields(previous: &Config, overridden: &Config) -> Fields {
+        let mut fields = Fields::NONE;
+        let mut add_if = |changed: bool, field: Fields| {
+            if changed {
+                fields = fields.union(field);
+            }
+        };
+
+        // The bytes threshold is adjusted by the collector, so it's restored only if it has been overridden
+        add_if(previous.default_policy.bytes_threshold() != overridden.default_policy.bytes_threshold(), Fields::BYTES_THRESHOLD);
+        add_if(previous.default_policy.adjustment_percent() != overridden.default_policy.adjustment_percent(), Fields::ADJUSTMENT_PERCENT);
+        add_if(
+            previous.default_policy.buffered_objects_threshold() != overridden.default_policy.buffered_objects_threshold(),
+            Fields::BUFFERED_OBJECTS_THRESHOLD,
+        );
+        let same_policy = match (&previous.custom_policy, &overridden.custom_policy) {
+            (Some(p1), Some(p2)) => Rc::ptr_eq(p1, p2),
+            (None, None) => true,
+            _ => false,
+        };
+        add_if(!same_policy, Fields::COLLECTION_POLICY);
+        add_if(previous.memory_limit != overridden.memory_limit, Fields::MEMORY_LIMIT);
+        let same_handler = match (&previous.memory_limit_handler, &overridden.memory_limit_handler) {
+            (Some(h1), Some(h2)) => Rc::ptr_eq(&h1.0, &h2.0),
+            (None, None) => true,
+            _ => false,
+        };
+        add_if(!same_handler, Fields::MEMORY_LIMIT_HANDLER);
+        add_if(previous.auto_collect != overridden.auto_collect, Fields::AUTO_COLLECT);
+        add_if(previous.panic_policy != overridden.panic_policy, Fields::PANIC_POLICY);
+        add_if(previous.finalization_order != overridden.finalization_order, Fields::FINALIZATION_ORDER);
+        #[cfg(feature = "finalization")]
+        add_if(previous.finalization_round_limit != overridden.finalization_round_limit, Fields::FINALIZATION_ROUND_LIMIT);
+
+        fields
+    }
+
+    /// Copies the values selected by `fields` from `from`.
+    fn copy_fields(&mut self, from: &Config, fields: Fields) {
+        if fields.contains(Fields::BYTES_THRESHOLD) {
+            if let Some(threshold) = NonZeroUsize::new(from.default_policy.bytes_threshold()) {
+                self.default_policy.set_bytes_threshold(threshold);
+            }
+        }
+        if fields.contains(Fields::ADJUSTMENT_PERCENT) {
+            self.default_policy.set_adjustment_percent(from.default_policy.adjustment_percent());
+        }
+        if fields.contains(Fields::BUFFERED_OBJECTS_THRESHOLD) {
+            self.default_policy.set_buffered_objects_threshold(from.default_policy.buffered_objects_threshold());
+        }
+        if fields.contains(Fields::COLLECTION_POLICY) {
+            self.custom_policy = from.custom_policy.clone();
+        }
+        if fields.contains(Fields::MEMORY_LIMIT) {
+            self.memory_limit = from.memory_limit;
+        }
+        if fields.contains(Fields::MEMORY_LIMIT_HANDLER) {
+            self.memory_limit_handler = from.memory_limit_handler.clone();
+        }
+        if fields.contains(Fields::AUTO_COLLECT) {
+            self.auto_collect = from.auto_collect;
+        }
+        if fields.contains(Fields::PANIC_POLICY) {
+            self.panic_policy = from.panic_policy;
+        }
+        if fields.contains(Fields::FINALIZATION_ORDER) {
+            self.finalization_order = from.finalization_order;
+        }
+        #[cfg(feature = "finalization")]
+        if fields.contains(Fields::FINALIZATION_ROUND_LIMIT) {
+            self.finalization_round_limit = from.finalization_round_limit;
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn should_collect(&mut self, state: &State, possible_cycles: &PossibleCycles) -> bool {
+        if !self.auto_collect {
+            return false;
+        }
+
+        let ctx = PolicyContext::new(state, possible_cycles);
+        match &self.custom_policy {
+            Some(policy) => policy.try_borrow_mut().is_ok_and(|mut policy| policy.should_collect(&ctx)),
+            None => self.default_policy.should_collect(&ctx),
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn adjust(&mut self, state: &State, possible_cycles: &PossibleCycles) {
+        let ctx = PolicyContext::new(state, possible_cycles);
+        match &self.custom_policy {
+            Some(policy) => {
+                if let Ok(mut policy) = policy.try_borrow_mut() {
+                    policy.adjust(&ctx);
+                }
+            },
+            None => self.default_policy.adjust(&ctx),
+        }
+    }
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What to do when a [`Trace`][`crate::Trace`], [`Finalize`][`crate::Finalize`] or [`Drop`] implementation panics during a collection.
+///
+/// See the [module-level documentation][`mod@crate::config`] for more details.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// The panic is propagated to the caller of the function which started the collection.
+    #[default]
+    Propagate,
+    /// The process is aborted.
+    Abort,
+    /// The panic is caught and the objects which were being processed by the collection may be leaked.
+    ///
+    /// [`try_collect_cycles`][`crate::try_collect_cycles`] returns [`CollectError::Panicked`][`crate::state::CollectError::Panicked`]
+    /// in this case, while [`collect_cycles`][`crate::collect_cycles`] and automatically-started collections ignore the panic.
+    #[cfg(feature = "std")]
+    CatchAndLeak,
+}
+
+/// The order in which the objects collected together are finalized and dropped.
+///
+/// See the [module-level documentation][`mod@crate::config`] for more details.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FinalizationOrder {
+    /// Objects are finalized and dropped in an unspecified order.
+    #[default]
+    Unordered,
+    /// Objects are finalized and dropped before the objects they reference.
+    Topological,
+}
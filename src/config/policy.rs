@@ -0,0 +1,472 @@
+use core::fmt::{self, Debug, Formatter};
+use core::num::NonZeroUsize;
+use core::time::Duration;
+
+use crate::lists::PossibleCycles;
+use crate::state::{CollectionReport, State};
+
+const DEFAULT_BYTES_THRESHOLD: usize = 100;
+
+/// A policy deciding when collections are automatically started.
+///
+/// A policy can be installed using [`Config::set_collection_policy`][`crate::config::Config::set_collection_policy`].
+/// When no policy is installed, the default [`AdaptivePolicy`] is used.
+///
+/// Policies are invoked while the configuration is being accessed, so they cannot access it.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::config::{config, CollectionPolicy, PolicyContext};
+/// // Start a collection every 1000 buffered objects
+/// struct EveryThousandBuffered;
+///
+/// impl CollectionPolicy for EveryThousandBuffered {
+///     fn should_collect(&mut self, ctx: &PolicyContext) -> bool {
+///         ctx.buffered_objects >= 1000
+///     }
+/// }
+///
+/// config(|config| config.set_collection_policy(EveryThousandBuffered)).unwrap();
+///# config(|config| config.reset_collection_policy()).unwrap();
+/// ```
+pub trait CollectionPolicy {
+    /// Returns whether a collection should be started.
+    ///
+    /// This method is called every time a collection may be automatically started (e.g. in [`Cc::new`][`crate::Cc::new`]),
+    /// if [`auto_collect`][`crate::config::Config::auto_collect`] is enabled.
+    fn should_collect(&mut self, ctx: &PolicyContext) -> bool;
+
+    /// Adjusts the policy after a collection.
+    ///
+    /// This method is called at the end of every collection, including the ones started by [`collect_cycles`][`crate::collect_cycles`].
+    /// Information about the just completed collection is available in [`PolicyContext::last_collection`].
+    ///
+    /// # Default implementation
+    ///
+    /// The default implementation is empty.
+    #[inline(always)]
+    fn adjust(&mut self, ctx: &PolicyContext) {
+        let _ = ctx;
+    }
+}
+
+/// The information about the collector provided to [`CollectionPolicy`]s.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug)]
+pub struct PolicyContext {
+    /// The number of bytes allocated by the collector (see [`state::allocated_bytes`][`crate::state::allocated_bytes`]).
+    pub allocated_bytes: usize,
+    /// The number of bytes of external memory reported to the collector (see [`state::add_external_memory`][`crate::state::add_external_memory`]).
+    pub external_bytes: usize,
+    /// The number of bytes of heap memory allocated by the current thread (see [`allocator::heap_bytes`][`crate::allocator::heap_bytes`]),
+    /// or [`None`] if the [`CcTrackingAllocator`][`crate::allocator::CcTrackingAllocator`] isn't [installed][`crate::allocator::CcTrackingAllocator::install`].
+    pub heap_bytes: Option<usize>,
+    /// The number of objects buffered to be processed in the next collection (see [`state::buffered_objects_count`][`crate::state::buffered_objects_count`]).
+    pub buffered_objects: usize,
+    /// The total number of executed collections (see [`state::executions_count`][`crate::state::executions_count`]).
+    pub executions_count: usize,
+    /// The number of allocations done since the start of the last collection.
+    pub allocations_count: usize,
+    /// The report of the last collection, if any.
+    pub last_collection: Option<CollectionReport>,
+}
+
+impl PolicyContext {
+    #[inline]
+    pub(super) fn new(state: &State, possible_cycles: &PossibleCycles) -> PolicyContext {
+        PolicyContext {
+            allocated_bytes: state.allocated_bytes(),
+            external_bytes: state.external_bytes(),
+            heap_bytes: crate::allocator::heap_bytes(),
+            buffered_objects: possible_cycles.size(),
+            executions_count: state.executions_count(),
+            allocations_count: state.allocations_count(),
+            last_collection: state.last_collection_report(),
+        }
+    }
+
+    /// Returns the total number of bytes managed by the collector, i.e. the sum of the
+    /// [allocated bytes][`PolicyContext::allocated_bytes`] and the [external bytes][`PolicyContext::external_bytes`].
+    ///
+    /// If the [`CcTrackingAllocator`][`crate::allocator::CcTrackingAllocator`] is installed, the [heap bytes][`PolicyContext::heap_bytes`]
+    /// are used in place of the allocated bytes (when greater), since they also include the memory owned by the values inside [`Cc`][`crate::Cc`]s.
+    /// In this case, only memory not allocated using the global allocator should be reported as external.
+    #[inline]
+    pub fn managed_bytes(&self) -> usize {
+        let allocated_bytes = match self.heap_bytes {
+            Some(heap_bytes) => heap_bytes.max(self.allocated_bytes),
+            None => self.allocated_bytes,
+        };
+        allocated_bytes.saturating_add(self.external_bytes)
+    }
+}
+
+/// The default [`CollectionPolicy`].
+///
+/// A *threshold* is kept over the number of [managed bytes][`PolicyContext::managed_bytes`], i.e. the number of bytes
+/// allocated by the collector plus the reported external memory. If the number of managed bytes exceeds the *threshold*
+/// a collection is started.
+///
+/// At the end of a collection, if the *threshold* is still lower than the number of managed bytes
+/// then it is doubled until it exceed it.
+///
+/// Instead, if the number of managed bytes exceed the *threshold* multiplied by the [`adjustment_percent`][`fn@AdaptivePolicy::adjustment_percent`],
+/// then the *threshold* is halved until the condition becomes true.
+///
+/// Finally, a collection may also happen if the number of objects buffered to be processed in the next collection (see [`Cc::mark_alive`][`crate::Cc::mark_alive`])
+/// exceeds the [`buffered_objects_threshold`][`fn@AdaptivePolicy::buffered_objects_threshold`]. This parameter is disabled by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptivePolicy {
+    // The invariant is:
+    // bytes_threshold * adjustment_percent < allocated_bytes < bytes_threshold
+    bytes_threshold: usize,
+    adjustment_percent: f64,
+    buffered_threshold: Option<NonZeroUsize>,
+}
+
+impl AdaptivePolicy {
+    /// Creates a new [`AdaptivePolicy`] with the default parameters.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            bytes_threshold: DEFAULT_BYTES_THRESHOLD,
+            adjustment_percent: 0.1,
+            buffered_threshold: None,
+        }
+    }
+
+    /// Returns the current bytes threshold.
+    #[inline]
+    pub fn bytes_threshold(&self) -> usize {
+        self.bytes_threshold
+    }
+
+    /// Sets the bytes threshold.
+    ///
+    /// Note that the threshold is adjusted at the end of every collection.
+    #[inline]
+    pub fn set_bytes_threshold(&mut self, threshold: NonZeroUsize) {
+        self.bytes_threshold = threshold.get();
+    }
+
+    /// Returns the threshold adjustment percent.
+    #[inline]
+    pub fn adjustment_percent(&self) -> f64 {
+        self.adjustment_percent
+    }
+
+    /// Sets the threshold adjustment percent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided `percent` isn't between 0 and 1 (included).
+    #[inline]
+    #[track_caller]
+    pub fn set_adjustment_percent(&mut self, percent: f64) {
+        assert!(
+            (0f64..=1f64).contains(&percent),
+            "percent must be between 0 and 1"
+        );
+        self.adjustment_percent = percent;
+    }
+
+    /// Returns the buffered-objects threshold (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+    ///
+    /// Returns [`None`] if this parameter isn't used to start a collection.
+    #[inline]
+    pub fn buffered_objects_threshold(&self) -> Option<NonZeroUsize> {
+        self.buffered_threshold
+    }
+
+    /// Sets the buffered-objects threshold (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+    ///
+    /// If the provided `threshold` is [`None`], then this parameter will not be used to start a collection.
+    #[inline]
+    pub fn set_buffered_objects_threshold(&mut self, threshold: Option<NonZeroUsize>) {
+        self.buffered_threshold = threshold;
+    }
+}
+
+impl CollectionPolicy for AdaptivePolicy {
+    #[inline]
+    fn should_collect(&mut self, ctx: &PolicyContext) -> bool {
+        if ctx.managed_bytes() > self.bytes_threshold {
+            return true;
+        }
+
+        if let Some(buffered_threshold) = self.buffered_threshold {
+            ctx.buffered_objects > buffered_threshold.get()
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    fn adjust(&mut self, ctx: &PolicyContext) {
+        let allocated_bytes = ctx.managed_bytes();
+
+        // First case: the threshold might have to be increased
+        if allocated_bytes >= self.bytes_threshold {
+
+            while let Some(new_threshold) = self.bytes_threshold.checked_shl(1) {
+                self.bytes_threshold = new_threshold;
+                if allocated_bytes < self.bytes_threshold {
+                    break;
+                }
+            }
+
+            return; // Skip the other case
+        }
+
+        // Second case: the threshold might have to be decreased
+        let allocated = allocated_bytes as f64;
+
+        // If adjustment_percent or the result of the multiplication is 0 do nothing
+        if ((self.bytes_threshold as f64) * self.adjustment_percent) == 0.0 {
+            return;
+        }
+
+        // No more cases after this, there's no need to use an additional if as above
+        while allocated <= ((self.bytes_threshold as f64) * self.adjustment_percent) {
+            let new_threshold = self.bytes_threshold >> 1;
+            if allocated_bytes >= new_threshold {
+                break; // If the shift produces a threshold <= allocated, then don't update bytes_threshold to maintain the invariant
+            }
+            if new_threshold <= DEFAULT_BYTES_THRESHOLD {
+                self.bytes_threshold = DEFAULT_BYTES_THRESHOLD;
+                break;
+            }
+            self.bytes_threshold = new_threshold;
+        }
+    }
+}
+
+impl Default for AdaptivePolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`CollectionPolicy`] using fixed thresholds, which are never adjusted.
+///
+/// A collection is started when the number of [managed bytes][`PolicyContext::managed_bytes`] exceeds the bytes threshold or,
+/// if set, when the number of buffered objects exceeds the buffered-objects threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedThresholdPolicy {
+    bytes_threshold: Option<NonZeroUsize>,
+    buffered_threshold: Option<NonZeroUsize>,
+}
+
+impl FixedThresholdPolicy {
+    /// Creates a new [`FixedThresholdPolicy`] with the provided bytes threshold.
+    ///
+    /// If the provided `bytes_threshold` is [`None`], then the number of managed bytes will not be used to start a collection.
+    #[inline]
+    pub const fn new(bytes_threshold: Option<NonZeroUsize>) -> Self {
+        Self {
+            bytes_threshold,
+            buffered_threshold: None,
+        }
+    }
+
+    /// Returns the bytes threshold.
+    #[inline]
+    pub fn bytes_threshold(&self) -> Option<NonZeroUsize> {
+        self.bytes_threshold
+    }
+
+    /// Sets the bytes threshold.
+    ///
+    /// If the provided `threshold` is [`None`], then the number of managed bytes will not be used to start a collection.
+    #[inline]
+    pub fn set_bytes_threshold(&mut self, threshold: Option<NonZeroUsize>) {
+        self.bytes_threshold = threshold;
+    }
+
+    /// Returns the buffered-objects threshold (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+    #[inline]
+    pub fn buffered_objects_threshold(&self) -> Option<NonZeroUsize> {
+        self.buffered_threshold
+    }
+
+    /// Sets the buffered-objects threshold (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+    ///
+    /// If the provided `threshold` is [`None`], then the number of buffered objects will not be used to start a collection.
+    #[inline]
+    pub fn set_buffered_objects_threshold(&mut self, threshold: Option<NonZeroUsize>) {
+        self.buffered_threshold = threshold;
+    }
+}
+
+impl CollectionPolicy for FixedThresholdPolicy {
+    #[inline]
+    fn should_collect(&mut self, ctx: &PolicyContext) -> bool {
+        let exceeds = |threshold: Option<NonZeroUsize>, value: usize| threshold.is_some_and(|t| value > t.get());
+
+        exceeds(self.bytes_threshold, ctx.managed_bytes()) || exceeds(self.buffered_threshold, ctx.buffered_objects)
+    }
+}
+
+/// A [`CollectionPolicy`] which starts a collection every fixed number of allocations.
+///
+/// A collection is started when the number of allocations done since the start of the last collection
+/// reaches the allocations threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocationCountPolicy {
+    allocations: NonZeroUsize,
+}
+
+impl AllocationCountPolicy {
+    /// Creates a new [`AllocationCountPolicy`] which starts a collection every `allocations` allocations.
+    #[inline]
+    pub const fn new(allocations: NonZeroUsize) -> Self {
+        Self { allocations }
+    }
+
+    /// Returns the allocations threshold.
+    #[inline]
+    pub fn allocations_threshold(&self) -> NonZeroUsize {
+        self.allocations
+    }
+
+    /// Sets the allocations threshold.
+    #[inline]
+    pub fn set_allocations_threshold(&mut self, allocations: NonZeroUsize) {
+        self.allocations = allocations;
+    }
+}
+
+impl CollectionPolicy for AllocationCountPolicy {
+    #[inline]
+    fn should_collect(&mut self, ctx: &PolicyContext) -> bool {
+        ctx.allocations_count >= self.allocations.get()
+    }
+}
+
+/// A [`CollectionPolicy`] which tries to keep the duration of collections under a target pause.
+///
+/// The duration of every collection is measured and used to estimate the cost of tracing an object, as well as how
+/// many objects are traced for every object buffered to be processed (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]).
+/// From these estimates a buffered-objects threshold is derived, which is tuned at the end of every collection so that
+/// the typical pause stays under the target. Collections are also started using the logic of the [`AdaptivePolicy`],
+/// to keep the number of managed bytes under control.
+///
+/// Note that the target is not a hard limit, since collections cannot be interrupted and finalizers and destructors
+/// may take an arbitrary amount of time.
+///
+/// Measuring time requires the `std` feature. Without it, this policy behaves exactly like the [`AdaptivePolicy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PauseTimePolicy {
+    target: Duration,
+    adaptive: AdaptivePolicy,
+    // Exponentially weighted moving averages of the last collections
+    nanos_per_traced: Option<f64>,
+    traced_per_buffered: Option<f64>,
+    buffered_threshold: Option<NonZeroUsize>,
+}
+
+impl PauseTimePolicy {
+    // The weight given to the last collection when updating the estimates
+    #[cfg(feature = "std")]
+    const WEIGHT: f64 = 0.25;
+
+    /// Creates a new [`PauseTimePolicy`] with the provided target pause.
+    #[inline]
+    pub const fn new(target: Duration) -> Self {
+        Self {
+            target,
+            adaptive: AdaptivePolicy::new(),
+            nanos_per_traced: None,
+            traced_per_buffered: None,
+            buffered_threshold: None,
+        }
+    }
+
+    /// Returns the target pause.
+    #[inline]
+    pub fn target_pause(&self) -> Duration {
+        self.target
+    }
+
+    /// Sets the target pause.
+    ///
+    /// The buffered-objects threshold is recomputed at the end of the next collection.
+    #[inline]
+    pub fn set_target_pause(&mut self, target: Duration) {
+        self.target = target;
+    }
+
+    /// Returns the buffered-objects threshold currently estimated to meet the target pause.
+    ///
+    /// Returns [`None`] if no estimate is available yet (for example, before the first collection or when the `std` feature is disabled).
+    #[inline]
+    pub fn buffered_objects_threshold(&self) -> Option<NonZeroUsize> {
+        self.buffered_threshold
+    }
+
+    /// Returns the [`AdaptivePolicy`] used to start collections based on the number of managed bytes.
+    #[inline]
+    pub fn adaptive_policy(&self) -> &AdaptivePolicy {
+        &self.adaptive
+    }
+
+    /// Returns a mutable reference to the [`AdaptivePolicy`] used to start collections based on the number of managed bytes.
+    #[inline]
+    pub fn adaptive_policy_mut(&mut self) -> &mut AdaptivePolicy {
+        &mut self.adaptive
+    }
+
+    #[cfg(feature = "std")]
+    fn update_estimates(&mut self, report: &CollectionReport) {
+        if report.traced_objects == 0 || report.buffered_objects_before == 0 {
+            return; // Nothing to learn from this collection
+        }
+
+        let traced = report.traced_objects as f64;
+        let nanos_per_traced = report.duration.as_nanos() as f64 / traced;
+        let traced_per_buffered = traced / report.buffered_objects_before as f64;
+
+        let ewma = |old: Option<f64>, new: f64| match old {
+            Some(old) => old + Self::WEIGHT * (new - old),
+            None => new,
+        };
+        let nanos_per_traced = ewma(self.nanos_per_traced, nanos_per_traced);
+        let traced_per_buffered = ewma(self.traced_per_buffered, traced_per_buffered);
+        self.nanos_per_traced = Some(nanos_per_traced);
+        self.traced_per_buffered = Some(traced_per_buffered);
+
+        let nanos_per_buffered = nanos_per_traced * traced_per_buffered;
+        self.buffered_threshold = if nanos_per_buffered > 0.0 {
+            // The cast saturates, so there's no risk of overflow
+            let threshold = (self.target.as_nanos() as f64 / nanos_per_buffered) as usize;
+            Some(NonZeroUsize::new(threshold).unwrap_or(NonZeroUsize::MIN))
+        } else {
+            None
+        };
+    }
+}
+
+impl CollectionPolicy for PauseTimePolicy {
+    #[inline]
+    fn should_collect(&mut self, ctx: &PolicyContext) -> bool {
+        self.adaptive.should_collect(ctx) || self.buffered_threshold.is_some_and(|t| ctx.buffered_objects > t.get())
+    }
+
+    #[inline]
+    fn adjust(&mut self, ctx: &PolicyContext) {
+        self.adaptive.adjust(ctx);
+
+        #[cfg(feature = "std")]
+        if let Some(report) = &ctx.last_collection {
+            self.update_estimates(report);
+        }
+    }
+}
+
+impl Debug for dyn CollectionPolicy {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CollectionPolicy").finish_non_exhaustive()
+    }
+}
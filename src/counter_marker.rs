@@ -32,7 +32,7 @@
 ///   * `IN_LIST`: in `root_list` or `non_root_list`
 ///   * `IN_QUEUE`: in queue to be traced
 /// * `B` is the tracing counter. The max value (the one with every bit set to 1) is reserved
-///       and indicates that the allocated value has already been dropped (but not yet deallocated)
+///   and indicates that the allocated value has already been dropped (but not yet deallocated)
 /// * `C` is `1` when metadata has been allocated, `0` otherwise
 /// * `D` is `1` when the element inside `CcBox` has already been finalized, `0` otherwise
 /// * `E` is the reference counter. The max value (the one with every bit set to 1) is reserved and should not be used
@@ -175,6 +175,12 @@ pub(crate) fn is_not_marked(&self) -> bool {
         (self.tracing_counter.get() & FIRST_BIT_MASK) == 0u16
     }
 
+    #[inline]
+    pub(crate) fn is_non_marked(&self) -> bool {
+        // Unlike is_not_marked, this is false for objects in possible_cycles
+        (self.tracing_counter.get() & BITS_MASK) == NON_MARKED
+    }
+
     #[inline]
     pub(crate) fn is_in_possible_cycles(&self) -> bool {
         (self.tracing_counter.get() & BITS_MASK) == IN_POSSIBLE_CYCLES
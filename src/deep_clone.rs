@@ -0,0 +1,482 @@
+//! Deep cloning of object graphs.
+//!
+//! Cloning a [`Cc`] only creates a new pointer to the same allocation. The [`DeepClone`] trait instead copies the
+//! whole object graph reachable from a value, reproducing in the copy the sharing of the original graph: every
+//! reachable [`Cc`] is copied only once, even if it is reachable through multiple paths (or cycles).
+//!
+//! [`Cc::deep_clone`] returns a deep copy of the object graph reachable from a [`Cc`].
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r"# use std::cell::RefCell;
+# use rust_cc::*;
+# use rust_cc::deep_clone::*;
+#[derive(Trace, Finalize, DeepClone)]
+struct Node {
+    value: u32,
+    next: RefCell<Option<Cc<Node>>>,
+}
+
+let first = Cc::new(Node { value: 1, next: RefCell::new(None) });
+let second = Cc::new(Node { value: 2, next: RefCell::new(Some(first.clone())) });
+*first.next.borrow_mut() = Some(second.clone());
+
+let copy = first.deep_clone();
+assert!(!Cc::ptr_eq(&first, &copy));
+assert_eq!(1, copy.value);
+
+// The cycle is reproduced in the copy
+let copy_next = copy.next.borrow().clone().unwrap();
+assert_eq!(2, copy_next.value);
+assert!(Cc::ptr_eq(&copy, copy_next.next.borrow().as_ref().unwrap()));
+```"]
+
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::any::Any;
+use core::cell::{Cell, OnceCell, RefCell};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::panic::AssertUnwindSafe;
+use core::pin::Pin;
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, RwLock, TryLockError};
+
+use crate::cc::CcBox;
+use crate::cell::{CcRefCell, CcSlot};
+use crate::pending::PendingCc;
+use crate::std_types::*;
+use crate::{Cc, Trace, Untraced};
+
+#[cfg(feature = "derive")]
+pub use crate::derives::DeepClone;
+
+/// Trait to copy the object graph reachable from a value.
+///
+/// Implementations should return a copy of `self`, calling [`deep_clone`][`DeepClone::deep_clone`] on every
+/// contained value which may (directly or indirectly) contain a [`Cc`]. The [`Cc`]s are copied using the provided
+/// [`DeepCloneContext`], which makes sure every allocation is copied only once.
+///
+/// This trait is implemented for the same types of the standard library [`Trace`] is implemented for, except for sets,
+/// maps and [`BinaryHeap`][`alloc::collections::BinaryHeap`]s, since inserting the copies into them would require to
+/// hash or compare the returned [`Cc`]s (see the safety section below).
+///
+/// # Derive macro
+///
+/// The [`DeepClone`][`macro@crate::deep_clone::DeepClone`] derive macro can be used to safely implement this trait
+/// by deep cloning every field.
+///
+/// # Safety
+///
+/// To reproduce cycles, the [`Cc`]s returned by [`deep_clone`][`DeepClone::deep_clone`] may point to allocations
+/// whose value is still being copied. Thus, implementations **must not** access the value pointed by any [`Cc`]
+/// returned by [`deep_clone`][`DeepClone::deep_clone`] (for example, by dereferencing it), they can only store it.
+pub unsafe trait DeepClone: Sized {
+    /// Returns a deep copy of `self`. See [`DeepClone`] for more information.
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self;
+}
+
+/// The context provided to every invocation of [`DeepClone::deep_clone`].
+pub struct DeepCloneContext {
+    // The copies of the already visited allocations
+    copies: BTreeMap<NonNull<CcBox<()>>, Box<dyn Any>>,
+    _phantom: PhantomData<*mut ()>, // Make DeepCloneContext !Send and !Sync
+}
+
+impl DeepCloneContext {
+    #[inline]
+    fn new() -> DeepCloneContext {
+        DeepCloneContext {
+            copies: BTreeMap::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    fn copy<T: Trace + DeepClone>(&mut self, cc: &Cc<T>) -> Cc<T> {
+        let ptr: NonNull<CcBox<()>> = cc.inner_ptr().cast();
+        if let Some(copied) = self.copies.get(&ptr) {
+            return copied
+                .downcast_ref::<Copied<T>>()
+                .expect("the same allocation has been reached with different types")
+                .copy
+                .to_cc();
+        }
+
+        // Insert before copying the value to correctly handle cycles
+        let copy = PendingCc::new();
+        self.copies.insert(ptr, Box::new(Copied {
+            _original: cc.clone(),
+            copy: copy.clone(),
+        }));
+
+        let value = (**cc).deep_clone(self);
+
+        // SAFETY: DeepClone implementations don't access the value of the returned Ccs
+        unsafe {
+            copy.init(value);
+        }
+        copy.to_cc()
+    }
+}
+
+/// Returns a deep copy of `value`.
+///
+/// See [`DeepClone`] for more details.
+#[inline]
+pub fn deep_clone<T: DeepClone>(value: &T) -> T {
+    value.deep_clone(&mut DeepCloneContext::new())
+}
+
+impl<T: Trace + DeepClone> Cc<T> {
+    /// Returns a deep copy of the object graph reachable from this [`Cc`].
+    ///
+    /// Every reachable [`Cc`] is copied only once, so shared allocations and cycles are reproduced in the copy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`DeepClone`] implementation panics. The allocations whose value was still being copied are leaked.
+    #[inline]
+    #[track_caller]
+    pub fn deep_clone(&self) -> Cc<T> {
+        DeepCloneContext::new().copy(self)
+    }
+}
+
+/// A copy made by [`DeepCloneContext`].
+struct Copied<T: Trace + 'static> {
+    // Keep the original alive, otherwise its address may be reused by another allocation
+    _original: Cc<T>,
+    copy: PendingCc<T>,
+}
+
+// #################################
+// #        DeepClone impls        #
+// #################################
+
+unsafe impl<T: Trace + DeepClone> DeepClone for Cc<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        ctx.copy(self)
+    }
+}
+
+// Weak pointers don't own the pointed allocation, so they're just cloned
+#[cfg(feature = "weak-ptrs")]
+unsafe impl<T: ?Sized + Trace> DeepClone for crate::weak::Weak<T> {
+    #[inline]
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        self.clone()
+    }
+}
+
+macro_rules! clone_deep_clone {
+    ($($this:ty),*,) => {
+        $(
+        unsafe impl $crate::deep_clone::DeepClone for $this {
+            #[inline(always)]
+            fn deep_clone(&self, _: &mut $crate::deep_clone::DeepCloneContext) -> Self {
+                ::core::clone::Clone::clone(self)
+            }
+        }
+        )*
+    };
+}
+
+plain_types!(clone_deep_clone);
+float_types!(clone_deep_clone);
+owned_string_types!(clone_deep_clone);
+
+clone_deep_clone! {
+    &'static str,
+}
+
+macro_rules! atomic_deep_clone {
+    ($($this:ty),*,) => {
+        $(
+        unsafe impl $crate::deep_clone::DeepClone for $this {
+            #[inline]
+            fn deep_clone(&self, _: &mut $crate::deep_clone::DeepCloneContext) -> Self {
+                <$this>::new(self.load(::core::sync::atomic::Ordering::Relaxed))
+            }
+        }
+        )*
+    };
+}
+
+atomic_types!(atomic_deep_clone);
+
+// Rc and Arc share their value, like Ccs do. However, they're not traced, so they're just cloned
+unsafe impl<T: ?Sized> DeepClone for Rc<T> {
+    #[inline]
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        self.clone()
+    }
+}
+
+unsafe impl<T: ?Sized> DeepClone for Arc<T> {
+    #[inline]
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        self.clone()
+    }
+}
+
+unsafe impl<T: ?Sized> DeepClone for PhantomData<T> {
+    #[inline(always)]
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        PhantomData
+    }
+}
+
+unsafe impl<T: Clone> DeepClone for Untraced<T> {
+    #[inline]
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        self.clone()
+    }
+}
+
+unsafe impl<T: Copy> DeepClone for Cell<T> {
+    #[inline]
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        self.clone()
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for Box<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        Box::new(T::deep_clone(self, ctx))
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for Pin<Box<T>> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        Box::pin(T::deep_clone(self, ctx))
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for ManuallyDrop<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        ManuallyDrop::new(T::deep_clone(self, ctx))
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for AssertUnwindSafe<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        AssertUnwindSafe(self.0.deep_clone(ctx))
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for RefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the [`RefCell`] is mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        RefCell::new(self.borrow().deep_clone(ctx))
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the [`CcRefCell`] is mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        CcRefCell::new(self.borrow().deep_clone(ctx))
+    }
+}
+
+unsafe impl<T: Trace + DeepClone> DeepClone for CcSlot<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        // Copy a clone, since DeepClone implementations may modify the slot
+        CcSlot::new(self.get().deep_clone(ctx))
+    }
+}
+
+// Like for RefCell, copying a locked Mutex or RwLock panics (instead of deadlocking). The copy of a poisoned lock isn't poisoned
+#[cfg(feature = "std")]
+unsafe impl<T: DeepClone> DeepClone for Mutex<T> {
+    /// # Panics
+    ///
+    /// Panics if the [`Mutex`] is locked.
+    #[inline]
+    #[track_caller]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        match self.try_lock() {
+            Ok(guard) => Mutex::new(guard.deep_clone(ctx)),
+            Err(TryLockError::Poisoned(err)) => Mutex::new(err.into_inner().deep_clone(ctx)),
+            Err(TryLockError::WouldBlock) => panic!("cannot deep clone a locked Mutex"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe impl<T: DeepClone> DeepClone for RwLock<T> {
+    /// # Panics
+    ///
+    /// Panics if the [`RwLock`] is write-locked.
+    #[inline]
+    #[track_caller]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        match self.try_read() {
+            Ok(guard) => RwLock::new(guard.deep_clone(ctx)),
+            Err(TryLockError::Poisoned(err)) => RwLock::new(err.into_inner().deep_clone(ctx)),
+            Err(TryLockError::WouldBlock) => panic!("cannot deep clone a write-locked RwLock"),
+        }
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for OnceCell<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        match self.get() {
+            Some(value) => OnceCell::from(value.deep_clone(ctx)),
+            None => OnceCell::new(),
+        }
+    }
+}
+
+// Only the owned variant owns its value, a borrowed Cow is just copied
+unsafe impl<B: ?Sized + ToOwned> DeepClone for Cow<'_, B>
+where B::Owned: DeepClone
+{
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        match self {
+            Cow::Borrowed(borrowed) => Cow::Borrowed(borrowed),
+            Cow::Owned(owned) => Cow::Owned(owned.deep_clone(ctx)),
+        }
+    }
+}
+
+unsafe impl<T: DeepClone> DeepClone for Option<T> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        self.as_ref().map(|value| value.deep_clone(ctx))
+    }
+}
+
+unsafe impl<R: DeepClone, E: DeepClone> DeepClone for Result<R, E> {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        match self {
+            Ok(value) => Ok(value.deep_clone(ctx)),
+            Err(err) => Err(err.deep_clone(ctx)),
+        }
+    }
+}
+
+unsafe impl<T: DeepClone, const N: usize> DeepClone for [T; N] {
+    #[inline]
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        core::array::from_fn(|i| self[i].deep_clone(ctx))
+    }
+}
+
+macro_rules! newtype_deep_clones {
+    ($($this:ty),*,) => {
+        $(
+        unsafe impl<T: DeepClone> DeepClone for $this {
+            #[inline]
+            fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+                Self(self.0.deep_clone(ctx))
+            }
+        }
+        )*
+    };
+}
+
+newtype_types!(newtype_deep_clones);
+
+// Sets, maps and BinaryHeaps are not supported, since inserting the copies into them would hash or compare
+// the copied Ccs, whose values may still be being copied
+macro_rules! iter_deep_clones {
+    ($($this:ty),*,) => {
+        $(
+        unsafe impl<T: DeepClone> DeepClone for $this {
+            #[inline]
+            fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+                self.iter().map(|elem| elem.deep_clone(ctx)).collect()
+            }
+        }
+        )*
+    };
+}
+
+sequence_types!(iter_deep_clones);
+
+// Function pointers don't own anything
+macro_rules! fn_deep_clone {
+    ($($args:ident),*) => {
+        unsafe impl<Ret, $($args),*> $crate::deep_clone::DeepClone for fn($($args),*) -> Ret {
+            #[inline(always)]
+            fn deep_clone(&self, _: &mut $crate::deep_clone::DeepCloneContext) -> Self {
+                *self
+            }
+        }
+
+        unsafe impl<Ret, $($args),*> $crate::deep_clone::DeepClone for unsafe fn($($args),*) -> Ret {
+            #[inline(always)]
+            fn deep_clone(&self, _: &mut $crate::deep_clone::DeepCloneContext) -> Self {
+                *self
+            }
+        }
+    }
+}
+
+macro_rules! fn_deep_clones {
+    ($(($($args:ident),*);)*) => {
+        $(
+            fn_deep_clone!($($args),*);
+        )*
+    }
+}
+
+fn_arities!(fn_deep_clones);
+
+macro_rules! tuple_deep_clone {
+    ($($args:ident),+) => {
+        #[allow(non_snake_case)]
+        unsafe impl<$($args),*> $crate::deep_clone::DeepClone for ($($args,)*)
+        where $($args: $crate::deep_clone::DeepClone),*
+        {
+            #[inline]
+            fn deep_clone(&self, ctx: &mut $crate::deep_clone::DeepCloneContext) -> Self {
+                match self {
+                    ($($args,)*) => {
+                        ($(
+                            <$args as $crate::deep_clone::DeepClone>::deep_clone($args, ctx),
+                        )*)
+                    }
+                }
+            }
+        }
+    }
+}
+
+macro_rules! tuple_deep_clones {
+    ($(($($args:ident $_lower:ident),+);)*) => {
+        $(
+            tuple_deep_clone!($($args),*);
+        )*
+    }
+}
+
+tuple_arities!(tuple_deep_clones);
@@ -89,3 +89,128 @@
 /// [`Cc`]: crate::Cc
 /// [`Drop`]: core::ops::Drop
 pub use rust_cc_derive::Trace;
+
+/// Derive macro for deriving [`HeapSize`][`trait@crate::heap_size::HeapSize`] implementations.
+///
+/// The derived implementation sums the heap size of every field of the implementing type.
+///
+/// # Ignoring fields
+/// Like for [`Trace`][`macro@crate::Trace`], the `#[rust_cc(ignore)]` attribute can be used to avoid measuring a field (or variant, in case of an enum).
+///
+/// # Example
+/// ```rust
+///# use std::cell::Cell;
+///# use rust_cc::*;
+///# use rust_cc::heap_size::*;
+/// #[derive(HeapSize)]
+/// struct Foo<T: Trace + HeapSize + 'static> {
+///     a_field: Cc<T>,
+///     another_field: Vec<u8>,
+///     #[rust_cc(ignore)] // Cell doesn't implement HeapSize, let's ignore it
+///     ignored_field: Cell<i32>,
+/// }
+/// ```
+pub use rust_cc_derive::HeapSize;
+
+/// Derive macro for safely deriving [`DeepClone`][`trait@crate::deep_clone::DeepClone`] implementations.
+///
+/// The derived implementation calls the [`deep_clone`][`method@crate::deep_clone::DeepClone::deep_clone`] method on every field of the implementing type.
+///
+/// # Ignoring fields
+/// Fields annotated with the `#[rust_cc(ignore)]` attribute (as well as every field of an ignored variant, in case of an enum)
+/// are cloned using [`Clone`] instead. Note that the [`Cc`]s contained in ignored fields are not copied, so the copy shares their allocations with the original.
+///
+/// # Example
+/// ```rust
+///# use std::cell::{Cell, RefCell};
+///# use rust_cc::*;
+///# use rust_cc::deep_clone::*;
+/// #[derive(DeepClone)]
+/// struct Foo<T: Trace + DeepClone + 'static> {
+///     a_field: Cc<T>,
+///     another_field: RefCell<Vec<Cc<T>>>,
+///     #[rust_cc(ignore)] // The copy shares the pointed allocation
+///     shared_field: Cc<T>,
+/// }
+/// ```
+///
+/// [`Cc`]: crate::Cc
+pub use rust_cc_derive::DeepClone;
+
+/// Derive macro for safely deriving [`GraphDebug`][`trait@crate::graph::GraphDebug`] implementations.
+///
+/// The derived implementation formats the implementing type like the derived [`Debug`] implementation would,
+/// formatting every field using [`GraphDebug`][`trait@crate::graph::GraphDebug`].
+///
+/// # Ignoring fields
+/// Fields annotated with the `#[rust_cc(ignore)]` attribute (as well as every field of an ignored variant, in case of an enum)
+/// are formatted using [`Debug`] instead. Note that the back-references contained in ignored fields are not detected.
+///
+/// # Example
+/// ```rust
+///# use std::cell::{Cell, RefCell};
+///# use rust_cc::*;
+///# use rust_cc::graph::*;
+/// #[derive(GraphDebug)]
+/// struct Foo<T: Trace + GraphDebug + 'static> {
+///     a_field: Cc<T>,
+///     another_field: RefCell<Vec<Cc<T>>>,
+///     #[rust_cc(ignore)] // Formatted using Debug
+///     ignored_field: Cell<*const u8>,
+/// }
+/// ```
+///
+/// [`Debug`]: core::fmt::Debug
+pub use rust_cc_derive::GraphDebug;
+
+/// Derive macro for deriving [`GraphEq`][`trait@crate::graph::GraphEq`] implementations.
+///
+/// The derived implementation is the conjunction of the comparisons of every field of the implementing type,
+/// made using [`GraphEq`][`trait@crate::graph::GraphEq`]. Values of different variants (in case of an enum) are never equal.
+///
+/// # Ignoring fields
+/// Fields annotated with the `#[rust_cc(ignore)]` attribute (as well as every field of an ignored variant, in case of an enum)
+/// are compared using [`PartialEq`] instead.
+///
+/// # Example
+/// ```rust
+///# use std::cell::RefCell;
+///# use rust_cc::*;
+///# use rust_cc::graph::*;
+/// #[derive(GraphEq)]
+/// struct Foo<T: Trace + GraphEq + 'static> {
+///     a_field: Cc<T>,
+///     another_field: RefCell<Vec<Cc<T>>>,
+///     #[rust_cc(ignore)] // Compared using PartialEq
+///     ignored_field: f64,
+/// }
+/// ```
+///
+/// [`PartialEq`]: core::cmp::PartialEq
+pub use rust_cc_derive::GraphEq;
+
+/// Derive macro for deriving [`GraphHashable`][`trait@crate::graph::GraphHashable`] implementations.
+///
+/// The derived implementation hashes the variant (in case of an enum) and every field of the implementing type
+/// using [`GraphHashable`][`trait@crate::graph::GraphHashable`], consistently with the derived [`GraphEq`][`trait@crate::graph::GraphEq`] implementation.
+///
+/// # Ignoring fields
+/// Fields annotated with the `#[rust_cc(ignore)]` attribute (as well as every field of an ignored variant, in case of an enum)
+/// are hashed using [`Hash`] instead.
+///
+/// # Example
+/// ```rust
+///# use std::cell::RefCell;
+///# use rust_cc::*;
+///# use rust_cc::graph::*;
+/// #[derive(GraphHashable)]
+/// struct Foo<T: Trace + GraphHashable + 'static> {
+///     a_field: Cc<T>,
+///     another_field: RefCell<Vec<Cc<T>>>,
+///     #[rust_cc(ignore)] // Hashed using Hash
+///     ignored_field: std::time::Instant,
+/// }
+/// ```
+///
+/// [`Hash`]: core::hash::Hash
+pub use rust_cc_derive::GraphHashable;
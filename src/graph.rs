@@ -0,0 +1,1076 @@
+//! Utilities to inspect object graphs containing cycles.
+//!
+//! The [`Debug`], [`PartialEq`] and [`Hash`] implementations of [`Cc`] forward to the implementations of the contained value,
+//! so they recurse infinitely (or until the stack overflows) on cyclic structures. This module provides cycle-aware alternatives.
+//!
+//! [`Cc::debug_graph`] returns a formatter which uses the [`GraphDebug`] trait to keep track of the [`Cc`]s being formatted,
+//! printing the back-references as `<cycle @0x...>` instead.
+//!
+//! Similarly, the [`graph_eq`] function uses the [`GraphEq`] trait to compare two object graphs structurally, considering
+//! equal two cyclic graphs which cannot be told apart by following their edges (i.e. which are *bisimilar*), while [`GraphHash`]
+//! uses the [`GraphHashable`] trait to hash an object graph up to a maximum depth in a way consistent with [`graph_eq`].
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r#"# use std::cell::RefCell;
+# use rust_cc::*;
+# use rust_cc::graph::*;
+#[derive(Trace, Finalize, GraphDebug)]
+struct Node {
+    value: u32,
+    next: RefCell<Option<Cc<Node>>>,
+}
+
+let node = Cc::new(Node {
+    value: 1,
+    next: RefCell::new(None),
+});
+*node.next.borrow_mut() = Some(node.clone());
+
+let formatted = format!("{:?}", node.debug_graph());
+assert!(formatted.starts_with("Node { value: 1, next: RefCell { value: Some(<cycle @"));
+```"#]
+
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::{Cell, OnceCell, RefCell};
+use core::cmp::Reverse;
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::num::{Saturating, Wrapping};
+use core::panic::AssertUnwindSafe;
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, RwLock, TryLockError},
+};
+
+use crate::cc::CcBox;
+use crate::cell::{CcRefCell, CcSlot};
+use crate::std_types::*;
+use crate::{Cc, Trace, Untraced};
+
+#[cfg(feature = "derive")]
+pub use crate::derives::{GraphDebug, GraphEq, GraphHashable};
+
+/// Trait to format a value like [`Debug`] does, printing the back-references of cyclic object graphs instead of recursing infinitely.
+///
+/// Implementations should format `self` like its [`Debug`] implementation would, formatting every contained value which
+/// may (directly or indirectly) contain a [`Cc`] through [`GraphDebugContext::debug`]. The [`Cc`]s are formatted using the
+/// provided [`GraphDebugContext`], which keeps track of the [`Cc`]s currently being formatted.
+///
+/// This trait is implemented for the same types of the standard library [`Trace`] is implemented for.
+///
+/// # Derive macro
+///
+/// The [`GraphDebug`][`macro@crate::graph::GraphDebug`] derive macro can be used to implement this trait by formatting every
+/// field like the derived [`Debug`] implementation would.
+pub trait GraphDebug {
+    /// Formats `self` using the given formatter. See [`GraphDebug`] for more information.
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result;
+}
+
+/// The context provided to every invocation of [`GraphDebug::fmt_graph`].
+pub struct GraphDebugContext {
+    // The Ccs currently being formatted
+    stack: RefCell<Vec<NonNull<CcBox<()>>>>,
+    _phantom: PhantomData<*mut ()>, // Make GraphDebugContext !Send and !Sync
+}
+
+impl GraphDebugContext {
+    #[inline]
+    fn new() -> GraphDebugContext {
+        GraphDebugContext {
+            stack: RefCell::new(Vec::new()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns a value whose [`Debug`] implementation formats `value` using [`GraphDebug::fmt_graph`] and this context.
+    ///
+    /// This is useful to pass the fields of a value to the helpers of [`Formatter`], like [`Formatter::debug_struct`].
+    #[inline]
+    pub fn debug<'a, T: ?Sized + GraphDebug>(&'a self, value: &'a T) -> impl Debug + 'a {
+        WithContext { value, ctx: self }
+    }
+}
+
+struct WithContext<'a, T: ?Sized + GraphDebug> {
+    value: &'a T,
+    ctx: &'a GraphDebugContext,
+}
+
+impl<T: ?Sized + GraphDebug> Debug for WithContext<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.value.fmt_graph(f, self.ctx)
+    }
+}
+
+/// A cycle-aware [`Debug`] formatter for a [`Cc`], as returned by [`Cc::debug_graph`].
+pub struct DebugGraph<'a, T: ?Sized + Trace + GraphDebug + 'static> {
+    cc: &'a Cc<T>,
+}
+
+impl<T: ?Sized + Trace + GraphDebug + 'static> Cc<T> {
+    /// Returns a [`Debug`] formatter which prints the back-references of the object graph as `<cycle @0x...>`,
+    /// instead of recursing infinitely.
+    ///
+    /// The object graph is formatted using [`GraphDebug`]. A [`Cc`] is printed as a back-reference only when it's
+    /// already being formatted, so a [`Cc`] reachable through multiple paths without forming a cycle is printed multiple times.
+    #[inline]
+    pub fn debug_graph(&self) -> DebugGraph<'_, T> {
+        DebugGraph { cc: self }
+    }
+}
+
+impl<T: ?Sized + Trace + GraphDebug + 'static> Debug for DebugGraph<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.cc.fmt_graph(f, &GraphDebugContext::new())
+    }
+}
+
+/// Trait to compare two values structurally, terminating also on cyclic object graphs.
+///
+/// Implementations should compare `self` and `other` like their [`PartialEq`] implementation would, comparing every
+/// contained value which may (directly or indirectly) contain a [`Cc`] through [`GraphEq::graph_eq`]. The [`Cc`]s are compared
+/// using the provided [`GraphEqContext`]: a pair of [`Cc`]s compared again while it is already being compared
+/// (because of a cycle) is assumed to be equal instead of being compared again.
+///
+/// Implementations **must** combine the results of the comparisons of the contained values using only
+/// conjunctions and disjunctions (like the derived implementations, which are the conjunction of the comparisons of the fields).
+/// Other combinations (like negations) may observe the assumptions made while comparing the [`Cc`]s, producing wrong results.
+///
+/// This trait is implemented for the types of the standard library [`Trace`] is implemented for which also implement
+/// [`PartialEq`], except for function pointers (whose comparisons are unreliable) and hash-based collections (whose
+/// iteration order is unspecified). Atomics, [`Mutex`][`std::sync::Mutex`]es, [`RwLock`][`std::sync::RwLock`]s and
+/// [`BinaryHeap`]s don't implement [`PartialEq`].
+///
+/// # Derive macro
+///
+/// The [`GraphEq`][`macro@crate::graph::GraphEq`] derive macro can be used to implement this trait by comparing every field.
+pub trait GraphEq {
+    /// Returns whether `self` and `other` are equal. See [`GraphEq`] for more information.
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool;
+}
+
+type CcPair = (NonNull<CcBox<()>>, NonNull<CcBox<()>>);
+
+/// The context provided to every invocation of [`GraphEq::graph_eq`].
+pub struct GraphEqContext {
+    // The pairs of Ccs assumed to be equal
+    assumed: BTreeSet<CcPair>,
+    // The pairs in assumed in insertion order, used to retract the assumptions which turned out to be wrong
+    log: Vec<CcPair>,
+    _phantom: PhantomData<*mut ()>, // Make GraphEqContext !Send and !Sync
+}
+
+impl GraphEqContext {
+    #[inline]
+    fn new() -> GraphEqContext {
+        GraphEqContext {
+            assumed: BTreeSet::new(),
+            log: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Compares two values structurally using [`GraphEq`], terminating also on cyclic object graphs.
+///
+/// A pair of [`Cc`]s compared again while it is already being compared (because of a cycle) is assumed to be equal.
+/// Thus, two cyclic graphs are considered equal if they cannot be told apart by following their edges, even when
+/// their cycles have different lengths. The assumptions which turn out to be wrong (i.e. made while comparing a pair of
+/// [`Cc`]s found to be different) are retracted, so they don't affect the comparisons made later.
+///
+/// # Example
+#[cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#[cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#[doc = r"# use std::cell::RefCell;
+# use rust_cc::*;
+# use rust_cc::graph::*;
+#[derive(Trace, Finalize, GraphEq)]
+struct Node {
+    value: u32,
+    next: RefCell<Option<Cc<Node>>>,
+}
+
+let node = |value| Cc::new(Node { value, next: RefCell::new(None) });
+
+// A self-loop
+let a = node(1);
+*a.next.borrow_mut() = Some(a.clone());
+
+// A cycle of length 2
+let b1 = node(1);
+let b2 = node(1);
+*b1.next.borrow_mut() = Some(b2.clone());
+*b2.next.borrow_mut() = Some(b1.clone());
+
+assert!(graph_eq(&a, &b1));
+
+*b2.next.borrow_mut() = Some(node(2));
+assert!(!graph_eq(&a, &b1));
+```"]
+#[inline]
+pub fn graph_eq<T: ?Sized + GraphEq>(a: &T, b: &T) -> bool {
+    a.graph_eq(b, &mut GraphEqContext::new())
+}
+
+/// Trait to hash a value structurally, terminating also on cyclic object graphs.
+///
+/// Implementations should hash `self` like its [`Hash`] implementation would, hashing every contained value which
+/// may (directly or indirectly) contain a [`Cc`] through [`GraphHashable::graph_hash`]. The [`Cc`]s are hashed using the provided
+/// [`GraphHashContext`], which stops hashing when the maximum depth is reached.
+///
+/// Implementations **must** be consistent with the [`GraphEq`] implementation (if any), i.e. values equal according to
+/// [`graph_eq`] must produce the same hash. Like for [`Hash`] and [`PartialEq`], the derived implementations are consistent with each other.
+///
+/// This trait is implemented for the types of the standard library [`GraphEq`] is implemented for which also implement [`Hash`].
+///
+/// # Derive macro
+///
+/// The [`GraphHashable`][`macro@crate::graph::GraphHashable`] derive macro can be used to implement this trait by hashing every field.
+pub trait GraphHashable {
+    /// Feeds `self` into the given [`Hasher`]. See [`GraphHashable`] for more information.
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext);
+}
+
+/// The context provided to every invocation of [`GraphHashable::graph_hash`].
+pub struct GraphHashContext {
+    // The remaining number of nested Ccs to hash
+    depth: usize,
+    _phantom: PhantomData<*mut ()>, // Make GraphHashContext !Send and !Sync
+}
+
+/// A wrapper around a [`Cc`] which implements [`Hash`] and [`Eq`] structurally, terminating also on cyclic graphs.
+///
+/// Hashing traverses the object graph using [`GraphHashable`] up to a maximum number of nested [`Cc`]s (the *depth*).
+/// **The values of deeper [`Cc`]s are not hashed at all**, so graphs which differ only after the maximum depth always
+/// collide. Since [`graph_eq`]-equal graphs are indistinguishable at any depth, equal [`GraphHash`]es always have the same hash,
+/// making [`GraphHash`] suitable as a key of hash maps (for example, for memoization).
+/// Note that hashing is exponential in the depth for graphs with many shared nodes.
+///
+/// Equality is implemented using [`graph_eq`]. Using [`GraphHash`] as the key of a map requires the [`GraphEq`]
+/// implementations to be reflexive.
+pub struct GraphHash<T: ?Sized + Trace + 'static> {
+    cc: Cc<T>,
+    depth: usize,
+}
+
+impl<T: ?Sized + Trace + 'static> GraphHash<T> {
+    /// Creates a new [`GraphHash`] with the provided maximum depth.
+    ///
+    /// A depth of 0 hashes nothing, a depth of 1 hashes only the value of the wrapped [`Cc`], and so on.
+    #[inline]
+    pub fn new(cc: Cc<T>, depth: usize) -> GraphHash<T> {
+        GraphHash { cc, depth }
+    }
+
+    /// Returns a reference to the wrapped [`Cc`].
+    #[inline]
+    pub fn cc(&self) -> &Cc<T> {
+        &self.cc
+    }
+
+    /// Returns the maximum depth.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Consumes the [`GraphHash`], returning the wrapped [`Cc`].
+    #[inline]
+    pub fn into_inner(self) -> Cc<T> {
+        self.cc
+    }
+}
+
+impl<T: ?Sized + Trace + GraphHashable + 'static> Hash for GraphHash<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cc.graph_hash(state, &mut GraphHashContext {
+            depth: self.depth,
+            _phantom: PhantomData,
+        });
+    }
+}
+
+impl<T: ?Sized + Trace + GraphEq + 'static> PartialEq for GraphHash<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        graph_eq(&self.cc, &other.cc)
+    }
+}
+
+impl<T: ?Sized + Trace + GraphEq + 'static> Eq for GraphHash<T> {}
+
+impl<T: ?Sized + Trace + GraphDebug + 'static> Debug for GraphHash<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GraphHash").field(&self.cc.debug_graph()).finish()
+    }
+}
+
+// #################################
+// #       GraphDebug impls        #
+// #################################
+
+impl<T: ?Sized + Trace + GraphDebug + 'static> GraphDebug for Cc<T> {
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        let ptr: NonNull<CcBox<()>> = self.inner_ptr().cast();
+        if ctx.stack.borrow().contains(&ptr) {
+            return write!(f, "<cycle @{:p}>", ptr::addr_of!(**self));
+        }
+
+        ctx.stack.borrow_mut().push(ptr);
+        let res = (**self).fmt_graph(f, ctx);
+        // Not popping the Cc if the formatting panics is fine, since the context cannot be used anymore
+        ctx.stack.borrow_mut().pop();
+        res
+    }
+}
+
+macro_rules! debug_graph_debugs {
+    ($($this:ty),*,) => {
+        $(
+        impl $crate::graph::GraphDebug for $this {
+            #[inline]
+            fn fmt_graph(&self, f: &mut ::core::fmt::Formatter<'_>, _: &$crate::graph::GraphDebugContext) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(self, f)
+            }
+        }
+        )*
+    };
+}
+
+plain_types!(debug_graph_debugs);
+float_types!(debug_graph_debugs);
+atomic_types!(debug_graph_debugs);
+unsized_string_types!(debug_graph_debugs);
+owned_string_types!(debug_graph_debugs);
+
+#[cfg(feature = "weak-ptrs")]
+impl<T: ?Sized + Trace + Debug> GraphDebug for crate::weak::Weak<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, _: &GraphDebugContext) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized> GraphDebug for PhantomData<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, _: &GraphDebugContext) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+// Untraced values and Cells cannot contain any Cc reachable by tracing, so they're formatted using Debug
+impl<T: Debug> GraphDebug for Untraced<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, _: &GraphDebugContext) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl<T: Copy + Debug> GraphDebug for Cell<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, _: &GraphDebugContext) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+// Like Debug, these pointers and wrappers format the pointed value transparently
+macro_rules! deref_graph_debugs {
+    ($($this:ty),*,) => {
+        $(
+        impl<T: ?Sized + GraphDebug> GraphDebug for $this {
+            #[inline]
+            fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+                T::fmt_graph(self, f, ctx)
+            }
+        }
+        )*
+    };
+}
+
+deref_graph_debugs! {
+    &T,
+    Box<T>,
+    Pin<Box<T>>,
+    Rc<T>,
+    Arc<T>,
+}
+
+impl<T: ?Sized + GraphDebug> GraphDebug for ManuallyDrop<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_struct("ManuallyDrop").field("value", &ctx.debug(&**self)).finish()
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for AssertUnwindSafe<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_tuple("AssertUnwindSafe").field(&ctx.debug(&self.0)).finish()
+    }
+}
+
+impl<T: ?Sized + GraphDebug> GraphDebug for RefCell<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        match self.try_borrow() {
+            Ok(borrow) => f.debug_struct("RefCell").field("value", &ctx.debug(&*borrow)).finish(),
+            Err(_) => f.debug_struct("RefCell").field("value", &format_args!("<borrowed>")).finish(),
+        }
+    }
+}
+
+impl<T: ?Sized + GraphDebug> GraphDebug for CcRefCell<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        match self.try_borrow() {
+            Ok(borrow) => f.debug_struct("CcRefCell").field("value", &ctx.debug(&*borrow)).finish(),
+            Err(_) => f.debug_struct("CcRefCell").field("value", &format_args!("<borrowed>")).finish(),
+        }
+    }
+}
+
+impl<T: ?Sized + Trace + GraphDebug + 'static> GraphDebug for CcSlot<T> {
+    /// Unlike the [`Debug`] implementation of [`CcSlot`], the content of the slot is formatted.
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        // Format a clone, since GraphDebug implementations may modify the slot
+        f.debug_struct("CcSlot").field("value", &ctx.debug(&self.get())).finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + GraphDebug> GraphDebug for Mutex<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        let mut d = f.debug_struct("Mutex");
+        match self.try_lock() {
+            Ok(guard) => d.field("data", &ctx.debug(&*guard)),
+            Err(TryLockError::Poisoned(err)) => d.field("data", &ctx.debug(&*err.into_inner())),
+            Err(TryLockError::WouldBlock) => d.field("data", &"<locked>"),
+        };
+        d.field("poisoned", &self.is_poisoned()).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + GraphDebug> GraphDebug for RwLock<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        let mut d = f.debug_struct("RwLock");
+        match self.try_read() {
+            Ok(guard) => d.field("data", &ctx.debug(&*guard)),
+            Err(TryLockError::Poisoned(err)) => d.field("data", &ctx.debug(&*err.into_inner())),
+            Err(TryLockError::WouldBlock) => d.field("data", &"<locked>"),
+        };
+        d.field("poisoned", &self.is_poisoned()).finish_non_exhaustive()
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for OnceCell<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        match self.get() {
+            Some(value) => f.debug_tuple("OnceCell").field(&ctx.debug(value)).finish(),
+            None => f.debug_tuple("OnceCell").field(&format_args!("<uninit>")).finish(),
+        }
+    }
+}
+
+impl<B: ?Sized + GraphDebug + ToOwned> GraphDebug for Cow<'_, B>
+where B::Owned: GraphDebug
+{
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        match self {
+            Cow::Borrowed(borrowed) => borrowed.fmt_graph(f, ctx),
+            Cow::Owned(owned) => owned.fmt_graph(f, ctx),
+        }
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for Option<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        match self {
+            Some(value) => f.debug_tuple("Some").field(&ctx.debug(value)).finish(),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+impl<R: GraphDebug, E: GraphDebug> GraphDebug for Result<R, E> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        match self {
+            Ok(value) => f.debug_tuple("Ok").field(&ctx.debug(value)).finish(),
+            Err(err) => f.debug_tuple("Err").field(&ctx.debug(err)).finish(),
+        }
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for Reverse<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_tuple("Reverse").field(&ctx.debug(&self.0)).finish()
+    }
+}
+
+// Like Debug, Wrapping and Saturating are formatted transparently
+impl<T: GraphDebug> GraphDebug for Wrapping<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        self.0.fmt_graph(f, ctx)
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for Saturating<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        self.0.fmt_graph(f, ctx)
+    }
+}
+
+macro_rules! list_graph_debugs {
+    ($($this:ty),*,) => {
+        $(
+        impl<T: GraphDebug> GraphDebug for $this {
+            #[inline]
+            fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+                f.debug_list().entries(self.iter().map(|value| ctx.debug(value))).finish()
+            }
+        }
+        )*
+    };
+}
+
+sequence_types!(list_graph_debugs);
+
+list_graph_debugs! {
+    [T],
+    BinaryHeap<T>,
+}
+
+impl<T: GraphDebug, const N: usize> GraphDebug for [T; N] {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        self.as_slice().fmt_graph(f, ctx)
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for BTreeSet<T> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_set().entries(self.iter().map(|value| ctx.debug(value))).finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: GraphDebug, S> GraphDebug for HashSet<T, S> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_set().entries(self.iter().map(|value| ctx.debug(value))).finish()
+    }
+}
+
+impl<K: GraphDebug, V: GraphDebug> GraphDebug for BTreeMap<K, V> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_map().entries(self.iter().map(|(key, value)| (ctx.debug(key), ctx.debug(value)))).finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: GraphDebug, V: GraphDebug, S> GraphDebug for HashMap<K, V, S> {
+    #[inline]
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_map().entries(self.iter().map(|(key, value)| (ctx.debug(key), ctx.debug(value)))).finish()
+    }
+}
+
+macro_rules! fn_graph_debug {
+    ($($args:ident),*) => {
+        impl<Ret, $($args),*> $crate::graph::GraphDebug for fn($($args),*) -> Ret {
+            #[inline]
+            fn fmt_graph(&self, f: &mut ::core::fmt::Formatter<'_>, _: &$crate::graph::GraphDebugContext) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(self, f)
+            }
+        }
+
+        impl<Ret, $($args),*> $crate::graph::GraphDebug for unsafe fn($($args),*) -> Ret {
+            #[inline]
+            fn fmt_graph(&self, f: &mut ::core::fmt::Formatter<'_>, _: &$crate::graph::GraphDebugContext) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(self, f)
+            }
+        }
+    }
+}
+
+macro_rules! fn_graph_debugs {
+    ($(($($args:ident),*);)*) => {
+        $(
+            fn_graph_debug!($($args),*);
+        )*
+    }
+}
+
+fn_arities!(fn_graph_debugs);
+
+macro_rules! tuple_graph_debug {
+    ($($args:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($args),*> $crate::graph::GraphDebug for ($($args,)*)
+        where $($args: $crate::graph::GraphDebug),*
+        {
+            #[inline]
+            fn fmt_graph(&self, f: &mut ::core::fmt::Formatter<'_>, ctx: &$crate::graph::GraphDebugContext) -> ::core::fmt::Result {
+                match self {
+                    ($($args,)*) => {
+                        f.debug_tuple("")
+                        $(
+                            .field(&ctx.debug($args))
+                        )*
+                        .finish()
+                    }
+                }
+            }
+        }
+    }
+}
+
+macro_rules! tuple_graph_debugs {
+    ($(($($args:ident $_lower:ident),+);)*) => {
+        $(
+            tuple_graph_debug!($($args),*);
+        )*
+    }
+}
+
+tuple_arities!(tuple_graph_debugs);
+
+// #################################
+// #  GraphEq and GraphHash impls  #
+// #################################
+
+impl<T: ?Sized + Trace + GraphEq + 'static> GraphEq for Cc<T> {
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        let pair: CcPair = (self.inner_ptr().cast(), other.inner_ptr().cast());
+        if !ctx.assumed.insert(pair) {
+            return true;
+        }
+
+        let len = ctx.log.len();
+        ctx.log.push(pair);
+        let eq = (**self).graph_eq(&**other, ctx);
+        if !eq {
+            // Retract the assumptions made while comparing this pair, since they may be wrong
+            for pair in ctx.log.drain(len..) {
+                ctx.assumed.remove(&pair);
+            }
+        }
+        eq
+    }
+}
+
+impl<T: ?Sized + Trace + GraphHashable + 'static> GraphHashable for Cc<T> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        if ctx.depth == 0 {
+            return;
+        }
+
+        ctx.depth -= 1;
+        (**self).graph_hash(state, ctx);
+        ctx.depth += 1;
+    }
+}
+
+macro_rules! plain_graph_eqs {
+    ($($this:ty),*,) => {
+        $(
+        impl $crate::graph::GraphEq for $this {
+            #[inline]
+            fn graph_eq(&self, other: &Self, _: &mut $crate::graph::GraphEqContext) -> bool {
+                self == other
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! plain_graph_hashes {
+    ($($this:ty),*,) => {
+        $(
+        impl $crate::graph::GraphHashable for $this {
+            #[inline]
+            fn graph_hash<H: ::core::hash::Hasher>(&self, state: &mut H, _: &mut $crate::graph::GraphHashContext) {
+                ::core::hash::Hash::hash(self, state);
+            }
+        }
+        )*
+    };
+}
+
+macro_rules! plain_graph_eqs_and_hashes {
+    ($($this:ty),*,) => {
+        plain_graph_eqs! { $($this),*, }
+        plain_graph_hashes! { $($this),*, }
+    };
+}
+
+plain_types!(plain_graph_eqs_and_hashes);
+float_types!(plain_graph_eqs);
+unsized_string_types!(plain_graph_eqs_and_hashes);
+owned_string_types!(plain_graph_eqs_and_hashes);
+
+impl<T: ?Sized> GraphEq for PhantomData<T> {
+    #[inline(always)]
+    fn graph_eq(&self, _: &Self, _: &mut GraphEqContext) -> bool {
+        true
+    }
+}
+
+impl<T: ?Sized> GraphHashable for PhantomData<T> {
+    #[inline(always)]
+    fn graph_hash<H: Hasher>(&self, _: &mut H, _: &mut GraphHashContext) {
+    }
+}
+
+// Untraced values and Cells cannot contain any Cc reachable by tracing, so they're compared and hashed directly
+impl<T: PartialEq> GraphEq for Untraced<T> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, _: &mut GraphEqContext) -> bool {
+        self == other
+    }
+}
+
+impl<T: Hash> GraphHashable for Untraced<T> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, _: &mut GraphHashContext) {
+        self.hash(state);
+    }
+}
+
+impl<T: Copy + PartialEq> GraphEq for Cell<T> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, _: &mut GraphEqContext) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Copy + Hash> GraphHashable for Cell<T> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, _: &mut GraphHashContext) {
+        self.get().hash(state);
+    }
+}
+
+macro_rules! deref_graph_eqs_and_hashes {
+    ($($this:ty),*,) => {
+        $(
+        impl<T: ?Sized + GraphEq> GraphEq for $this {
+            #[inline]
+            fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+                T::graph_eq(self, other, ctx)
+            }
+        }
+
+        impl<T: ?Sized + GraphHashable> GraphHashable for $this {
+            #[inline]
+            fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+                T::graph_hash(self, state, ctx);
+            }
+        }
+        )*
+    };
+}
+
+deref_graph_eqs_and_hashes! {
+    &T,
+    Box<T>,
+    Pin<Box<T>>,
+    ManuallyDrop<T>,
+    Rc<T>,
+    Arc<T>,
+}
+
+impl<B: ?Sized + GraphEq + ToOwned> GraphEq for Cow<'_, B> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        B::graph_eq(self, other, ctx)
+    }
+}
+
+impl<B: ?Sized + GraphHashable + ToOwned> GraphHashable for Cow<'_, B> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        B::graph_hash(self, state, ctx);
+    }
+}
+
+impl<T: ?Sized + GraphEq> GraphEq for RefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if any of the [`RefCell`]s is mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        self.borrow().graph_eq(&other.borrow(), ctx)
+    }
+}
+
+impl<T: ?Sized + GraphHashable> GraphHashable for RefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the [`RefCell`] is mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        self.borrow().graph_hash(state, ctx);
+    }
+}
+
+impl<T: ?Sized + GraphEq> GraphEq for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if any of the [`CcRefCell`]s is mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        self.borrow().graph_eq(&other.borrow(), ctx)
+    }
+}
+
+impl<T: ?Sized + GraphHashable> GraphHashable for CcRefCell<T> {
+    /// # Panics
+    ///
+    /// Panics if the [`CcRefCell`] is mutably borrowed.
+    #[inline]
+    #[track_caller]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        self.borrow().graph_hash(state, ctx);
+    }
+}
+
+// Compare and hash clones, since the implementations may modify the slots
+impl<T: ?Sized + Trace + GraphEq + 'static> GraphEq for CcSlot<T> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        self.get().graph_eq(&other.get(), ctx)
+    }
+}
+
+impl<T: ?Sized + Trace + GraphHashable + 'static> GraphHashable for CcSlot<T> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        self.get().graph_hash(state, ctx);
+    }
+}
+
+impl<T: GraphEq> GraphEq for OnceCell<T> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        match (self.get(), other.get()) {
+            (Some(value), Some(other)) => value.graph_eq(other, ctx),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: GraphEq> GraphEq for Option<T> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        match (self, other) {
+            (Some(value), Some(other)) => value.graph_eq(other, ctx),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: GraphHashable> GraphHashable for Option<T> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        mem::discriminant(self).hash(state);
+        if let Some(value) = self {
+            value.graph_hash(state, ctx);
+        }
+    }
+}
+
+impl<R: GraphEq, E: GraphEq> GraphEq for Result<R, E> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        match (self, other) {
+            (Ok(value), Ok(other)) => value.graph_eq(other, ctx),
+            (Err(err), Err(other)) => err.graph_eq(other, ctx),
+            _ => false,
+        }
+    }
+}
+
+impl<R: GraphHashable, E: GraphHashable> GraphHashable for Result<R, E> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Ok(value) => value.graph_hash(state, ctx),
+            Err(err) => err.graph_hash(state, ctx),
+        }
+    }
+}
+
+macro_rules! newtype_graph_eqs_and_hashes {
+    ($($this:ty),*,) => {
+        $(
+        impl<T: GraphEq> GraphEq for $this {
+            #[inline]
+            fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+                self.0.graph_eq(&other.0, ctx)
+            }
+        }
+
+        impl<T: GraphHashable> GraphHashable for $this {
+            #[inline]
+            fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+                self.0.graph_hash(state, ctx);
+            }
+        }
+        )*
+    };
+}
+
+newtype_types!(newtype_graph_eqs_and_hashes);
+
+// Ordered sets are compared and hashed like sequences, since their elements are always visited in the same order
+macro_rules! list_graph_eqs_and_hashes {
+    ($($this:ty),*,) => {
+        $(
+        impl<T: GraphEq> GraphEq for $this {
+            #[inline]
+            fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+                self.len() == other.len() && self.iter().zip(other.iter()).all(|(value, other)| value.graph_eq(other, ctx))
+            }
+        }
+
+        impl<T: GraphHashable> GraphHashable for $this {
+            #[inline]
+            fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+                state.write_usize(self.len());
+                for value in self.iter() {
+                    value.graph_hash(state, ctx);
+                }
+            }
+        }
+        )*
+    };
+}
+
+sequence_types!(list_graph_eqs_and_hashes);
+
+list_graph_eqs_and_hashes! {
+    [T],
+    BTreeSet<T>,
+}
+
+impl<T: GraphEq, const N: usize> GraphEq for [T; N] {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        self.as_slice().graph_eq(other.as_slice(), ctx)
+    }
+}
+
+impl<T: GraphHashable, const N: usize> GraphHashable for [T; N] {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        self.as_slice().graph_hash(state, ctx);
+    }
+}
+
+impl<K: GraphEq, V: GraphEq> GraphEq for BTreeMap<K, V> {
+    #[inline]
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|((key, value), (other_key, other_value))| {
+            key.graph_eq(other_key, ctx) && value.graph_eq(other_value, ctx)
+        })
+    }
+}
+
+impl<K: GraphHashable, V: GraphHashable> GraphHashable for BTreeMap<K, V> {
+    #[inline]
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        state.write_usize(self.len());
+        for (key, value) in self.iter() {
+            key.graph_hash(state, ctx);
+            value.graph_hash(state, ctx);
+        }
+    }
+}
+
+macro_rules! tuple_graph_eq_and_hash {
+    ($($args:ident $others:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($args),*> $crate::graph::GraphEq for ($($args,)*)
+        where $($args: $crate::graph::GraphEq),*
+        {
+            #[inline]
+            fn graph_eq(&self, other: &Self, ctx: &mut $crate::graph::GraphEqContext) -> bool {
+                let ($($args,)*) = self;
+                let ($($others,)*) = other;
+                true $(&& <$args as $crate::graph::GraphEq>::graph_eq($args, $others, ctx))*
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$($args),*> $crate::graph::GraphHashable for ($($args,)*)
+        where $($args: $crate::graph::GraphHashable),*
+        {
+            #[inline]
+            fn graph_hash<W: ::core::hash::Hasher>(&self, state: &mut W, ctx: &mut $crate::graph::GraphHashContext) {
+                let ($($args,)*) = self;
+                $(
+                    <$args as $crate::graph::GraphHashable>::graph_hash($args, state, ctx);
+                )*
+            }
+        }
+    }
+}
+
+macro_rules! tuple_graph_eqs_and_hashes {
+    ($(($($args:ident $others:ident),+);)*) => {
+        $(
+            tuple_graph_eq_and_hash!($($args $others),*);
+        )*
+    }
+}
+
+tuple_arities!(tuple_graph_eqs_and_hashes);
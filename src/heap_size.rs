@@ -0,0 +1,623 @@
+//! Measurement of the memory retained by objects.
+//!
+//! The [`HeapSize`] trait computes the *deep size* of a value, i.e. the amount of heap memory owned by it.
+//! The memory of every [`Cc`] is counted only once, even if it is reachable through multiple paths (or cycles).
+//!
+//! The [`Cc::retained_size`] method walks the object graph reachable from a [`Cc`] and
+//! returns both the memory exclusively retained by it and the total reachable memory.
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r"# use std::cell::RefCell;
+# use rust_cc::*;
+# use rust_cc::heap_size::*;
+#[derive(Trace, Finalize, HeapSize)]
+struct Node {
+    data: Vec<u8>,
+    next: RefCell<Option<Cc<Node>>>,
+}
+
+let shared = Cc::new(Node {
+    data: vec![0; 1024],
+    next: RefCell::new(None),
+});
+let node = Cc::new(Node {
+    data: vec![0; 16],
+    next: RefCell::new(Some(shared.clone())),
+});
+
+let size = node.retained_size();
+assert!(size.reachable >= 1024 + 16);
+// shared is also owned by someone else, so it isn't exclusively retained by node
+assert!(size.exclusive < 1024);
+```"]
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::cell::{Cell, OnceCell, RefCell};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::panic::AssertUnwindSafe;
+use core::pin::Pin;
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    ffi::OsString,
+    sync::{Mutex, RwLock, TryLockError},
+};
+
+use crate::cc::CcBox;
+use crate::cell::{CcRefCell, CcSlot};
+use crate::state::ExternalMemory;
+use crate::std_types::*;
+use crate::walk::walk;
+use crate::{Cc, Trace, Untraced};
+
+#[cfg(feature = "derive")]
+pub use crate::derives::HeapSize;
+
+/// Trait to compute the amount of heap memory owned by a value.
+///
+/// Implementations should return the number of bytes of heap memory owned by `self`, excluding the size of `self`
+/// itself (i.e. [`size_of_val(self)`][`core::mem::size_of_val`]). For example, the implementation for [`Vec`] returns the size of
+/// its buffer plus the heap size of its elements.
+///
+/// The memory of [`Cc`]s is counted using the provided [`HeapSizeContext`], which makes sure every allocation
+/// is counted only once. Thus, implementations should always call [`heap_size`][`HeapSize::heap_size`] on the contained [`Cc`]s.
+///
+/// This trait is implemented for the same types of the standard library [`Trace`] is implemented for.
+///
+/// # Derive macro
+///
+/// The [`HeapSize`][`macro@crate::heap_size::HeapSize`] derive macro can be used to implement this trait by summing the heap size of every field.
+pub trait HeapSize {
+    /// Returns the number of bytes of heap memory owned by `self`. See [`HeapSize`] for more information.
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize;
+}
+
+/// The context provided to every invocation of [`HeapSize::heap_size`].
+pub struct HeapSizeContext {
+    // The memory of every visited allocation, not including the memory of the nested allocations
+    visited: BTreeMap<NonNull<CcBox<()>>, usize>,
+    // The memory of the allocations found while computing the heap size of the current allocation
+    nested: usize,
+    _phantom: PhantomData<*mut ()>, // Make HeapSizeContext !Send and !Sync
+}
+
+impl HeapSizeContext {
+    #[inline]
+    fn new() -> HeapSizeContext {
+        HeapSizeContext {
+            visited: BTreeMap::new(),
+            nested: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Returns the deep size of `value`, i.e. its size plus the amount of heap memory owned by it.
+///
+/// See [`HeapSize`] for more details.
+#[inline]
+pub fn deep_size_of<T: ?Sized + HeapSize>(value: &T) -> usize {
+    mem::size_of_val(value) + value.heap_size(&mut HeapSizeContext::new())
+}
+
+/// The memory retained by a [`Cc`], as returned by [`Cc::retained_size`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RetainedSize {
+    /// The number of bytes which would be deallocated if the [`Cc`] became unreachable.
+    pub exclusive: usize,
+    /// The number of bytes reachable from the [`Cc`], including the memory which is also reachable from elsewhere.
+    pub reachable: usize,
+}
+
+impl<T: ?Sized + Trace + HeapSize> Cc<T> {
+    /// Returns the memory retained by this [`Cc`].
+    ///
+    /// The object graph reachable from this [`Cc`] is walked by tracing and every allocation is counted once.
+    /// The size of an allocation includes the heap memory owned by its value, as computed by [`HeapSize`].
+    /// Allocations whose value cannot be measured (because they're reached only by tracing, like trait objects not
+    /// implementing [`HeapSize`]) are counted using their size only.
+    ///
+    /// The [*exclusive*][`RetainedSize::exclusive`] size only counts the allocations which are reachable exclusively
+    /// through this [`Cc`], i.e. the ones which would be deallocated if this [`Cc`] became unreachable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a collection.
+    #[track_caller]
+    pub fn retained_size(&self) -> RetainedSize {
+        struct Node {
+            ptr: NonNull<CcBox<()>>,
+            internal_refs: usize,
+            children: Vec<usize>,
+        }
+
+        // Compute the sizes first, since HeapSize implementations may dereference Ccs, which isn't allowed while tracing
+        let mut ctx = HeapSizeContext::new();
+        let _ = self.heap_size(&mut ctx);
+
+        let root: NonNull<CcBox<()>> = self.inner_ptr().cast();
+
+        let (nodes, alive) = walk(|walker| {
+            let mut indexes: BTreeMap<NonNull<CcBox<()>>, usize> = BTreeMap::new();
+            let mut nodes: Vec<Node> = Vec::new();
+
+            indexes.insert(root, 0);
+            nodes.push(Node { ptr: root, internal_refs: 0, children: Vec::new() });
+
+            let mut i = 0;
+            while i < nodes.len() {
+                let mut children = Vec::new();
+                walker.children(nodes[i].ptr, &mut |child| {
+                    let index = *indexes.entry(child).or_insert_with(|| {
+                        nodes.push(Node { ptr: child, internal_refs: 0, children: Vec::new() });
+                        nodes.len() - 1
+                    });
+                    nodes[index].internal_refs += 1;
+                    children.push(index);
+                });
+                nodes[i].children = children;
+                i += 1;
+            }
+
+            // An object is alive (even if this Cc became unreachable) if it is referenced from outside the
+            // walked graph, or if it is reachable from such an object without passing through this Cc
+            let mut alive = vec![false; nodes.len()];
+            let mut stack: Vec<usize> = (1..nodes.len())
+                .filter(|&i| {
+                    let counter = unsafe { nodes[i].ptr.as_ref() }.counter_marker().counter() as usize;
+                    counter > nodes[i].internal_refs
+                })
+                .collect();
+            while let Some(i) = stack.pop() {
+                if i == 0 || alive[i] {
+                    continue;
+                }
+                alive[i] = true;
+                stack.extend_from_slice(&nodes[i].children);
+            }
+
+            (nodes, alive)
+        });
+
+        let mut size = RetainedSize {
+            exclusive: 0,
+            reachable: 0,
+        };
+        for (node, alive) in nodes.iter().zip(alive) {
+            let node_size = match ctx.visited.get(&node.ptr) {
+                Some(&size) => size,
+                None => unsafe { node.ptr.as_ref() }.layout().size(),
+            };
+            size.reachable += node_size;
+            if !alive {
+                size.exclusive += node_size;
+            }
+        }
+        size
+    }
+}
+
+// #################################
+// #        HeapSize impls         #
+// #################################
+
+impl<T: ?Sized + Trace + HeapSize> HeapSize for Cc<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        let ptr: NonNull<CcBox<()>> = self.inner_ptr().cast();
+        if ctx.visited.contains_key(&ptr) {
+            return 0;
+        }
+        // Insert before measuring the value to correctly handle cycles
+        ctx.visited.insert(ptr, 0);
+
+        let outer_nested = mem::replace(&mut ctx.nested, 0);
+        let total = self.inner().layout().size() + (**self).heap_size(ctx);
+        let nested = mem::replace(&mut ctx.nested, outer_nested);
+
+        ctx.visited.insert(ptr, total.saturating_sub(nested));
+        ctx.nested += total;
+        total
+    }
+}
+
+#[cfg(feature = "weak-ptrs")]
+impl<T: ?Sized + Trace> HeapSize for crate::weak::Weak<T> {
+    /// Returns 0, since weak pointers don't own the pointed allocation.
+    #[inline(always)]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        0
+    }
+}
+
+impl HeapSize for ExternalMemory {
+    /// Returns the [reported bytes][`ExternalMemory::bytes`].
+    #[inline]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        self.bytes()
+    }
+}
+
+macro_rules! empty_heap_size {
+    ($($this:ty),*,) => {
+        $(
+        impl $crate::heap_size::HeapSize for $this {
+            #[inline(always)]
+            fn heap_size(&self, _: &mut $crate::heap_size::HeapSizeContext) -> usize {
+                0
+            }
+        }
+        )*
+    };
+}
+
+plain_types!(empty_heap_size);
+float_types!(empty_heap_size);
+atomic_types!(empty_heap_size);
+unsized_string_types!(empty_heap_size);
+
+// Owned strings own the memory of their content
+impl HeapSize for String {
+    #[inline]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for CString {
+    #[inline]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        self.as_bytes_with_nul().len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl HeapSize for PathBuf {
+    #[inline]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        self.capacity()
+    }
+}
+
+#[cfg(feature = "std")]
+impl HeapSize for OsString {
+    #[inline]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: ?Sized> HeapSize for PhantomData<T> {
+    #[inline(always)]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        0
+    }
+}
+
+impl<T: ?Sized + HeapSize> HeapSize for Box<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        mem::size_of_val::<T>(self) + T::heap_size(self, ctx)
+    }
+}
+
+impl<T: ?Sized + HeapSize> HeapSize for ManuallyDrop<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        T::heap_size(self, ctx)
+    }
+}
+
+impl<T: HeapSize> HeapSize for AssertUnwindSafe<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.0.heap_size(ctx)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Untraced<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.0.heap_size(ctx)
+    }
+}
+
+impl<T: ?Sized + HeapSize> HeapSize for CcRefCell<T> {
+    /// Returns 0 if the [`CcRefCell`] is mutably borrowed.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self.try_borrow() {
+            Ok(borrow) => borrow.heap_size(ctx),
+            Err(_) => 0,
+        }
+    }
+}
+
+impl<T: ?Sized + Trace + HeapSize> HeapSize for CcSlot<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        // Measure a clone, since HeapSize implementations may modify the slot
+        self.get().heap_size(ctx)
+    }
+}
+
+impl<T: ?Sized + HeapSize> HeapSize for RefCell<T> {
+    /// Returns 0 if the [`RefCell`] is mutably borrowed.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self.try_borrow() {
+            Ok(borrow) => borrow.heap_size(ctx),
+            Err(_) => 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + HeapSize> HeapSize for Mutex<T> {
+    /// Returns 0 if the [`Mutex`] is locked.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self.try_lock() {
+            Ok(guard) => guard.heap_size(ctx),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().heap_size(ctx),
+            Err(TryLockError::WouldBlock) => 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + HeapSize> HeapSize for RwLock<T> {
+    /// Returns 0 if the [`RwLock`] is write-locked.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self.try_read() {
+            Ok(guard) => guard.heap_size(ctx),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().heap_size(ctx),
+            Err(TryLockError::WouldBlock) => 0,
+        }
+    }
+}
+
+impl<T: Copy + HeapSize> HeapSize for Cell<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.get().heap_size(ctx)
+    }
+}
+
+impl<T: HeapSize> HeapSize for OnceCell<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self.get() {
+            Some(value) => value.heap_size(ctx),
+            None => 0,
+        }
+    }
+}
+
+impl<B: ?Sized + ToOwned> HeapSize for Cow<'_, B>
+where B::Owned: HeapSize
+{
+    /// Returns 0 for the borrowed variant.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self {
+            Cow::Owned(owned) => owned.heap_size(ctx),
+            Cow::Borrowed(_) => 0,
+        }
+    }
+}
+
+impl<T: ?Sized + HeapSize> HeapSize for Pin<Box<T>> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        mem::size_of_val::<T>(self) + T::heap_size(self, ctx)
+    }
+}
+
+macro_rules! newtype_heap_sizes {
+    ($($this:ty),*,) => {
+        $(
+        impl<T: $crate::heap_size::HeapSize> $crate::heap_size::HeapSize for $this {
+            #[inline]
+            fn heap_size(&self, ctx: &mut $crate::heap_size::HeapSizeContext) -> usize {
+                self.0.heap_size(ctx)
+            }
+        }
+        )*
+    }
+}
+
+newtype_types!(newtype_heap_sizes);
+
+impl<T: ?Sized> HeapSize for Rc<T> {
+    /// Always returns 0, since the pointed value is shared with the other [`Rc`]s.
+    #[inline(always)]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        0
+    }
+}
+
+impl<T: ?Sized> HeapSize for Arc<T> {
+    /// Always returns 0, since the pointed value is shared with the other [`Arc`]s.
+    #[inline(always)]
+    fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+        0
+    }
+}
+
+macro_rules! fn_heap_size {
+    ($($args:ident),*) => {
+        impl<Ret, $($args),*> $crate::heap_size::HeapSize for fn($($args),*) -> Ret {
+            #[inline(always)]
+            fn heap_size(&self, _: &mut $crate::heap_size::HeapSizeContext) -> usize {
+                0
+            }
+        }
+
+        impl<Ret, $($args),*> $crate::heap_size::HeapSize for unsafe fn($($args),*) -> Ret {
+            #[inline(always)]
+            fn heap_size(&self, _: &mut $crate::heap_size::HeapSizeContext) -> usize {
+                0
+            }
+        }
+    }
+}
+
+macro_rules! fn_heap_sizes {
+    ($(($($args:ident),*);)*) => {
+        $(
+            fn_heap_size!($($args),*);
+        )*
+    }
+}
+
+fn_arities!(fn_heap_sizes);
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self {
+            Some(inner) => inner.heap_size(ctx),
+            None => 0,
+        }
+    }
+}
+
+impl<R: HeapSize, E: HeapSize> HeapSize for Result<R, E> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        match self {
+            Ok(ok) => ok.heap_size(ctx),
+            Err(err) => err.heap_size(ctx),
+        }
+    }
+}
+
+impl<T: HeapSize, const N: usize> HeapSize for [T; N] {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.as_slice().heap_size(ctx)
+    }
+}
+
+impl<T: HeapSize> HeapSize for [T] {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.iter().map(|elem| elem.heap_size(ctx)).sum()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.as_slice().heap_size(ctx)
+    }
+}
+
+impl<T: HeapSize> HeapSize for VecDeque<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(|elem| elem.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for BinaryHeap<T> {
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(|elem| elem.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for LinkedList<T> {
+    /// Every node is approximated as the element plus two pointers.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        let node_size = mem::size_of::<T>() + 2 * mem::size_of::<usize>();
+        self.len() * node_size + self.iter().map(|elem| elem.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for BTreeSet<T> {
+    /// Approximated as the size of the elements, since the layout of the nodes is unspecified.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.len() * mem::size_of::<T>() + self.iter().map(|elem| elem.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    /// Approximated as the size of the entries, since the layout of the nodes is unspecified.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.len() * mem::size_of::<(K, V)>() + self.iter().map(|(key, value)| key.heap_size(ctx) + value.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: HeapSize, S> HeapSize for HashSet<T, S> {
+    /// Approximated as the size of the allocated buckets, since the layout of the table is unspecified.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(|elem| elem.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+    /// Approximated as the size of the allocated buckets, since the layout of the table is unspecified.
+    #[inline]
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.capacity() * mem::size_of::<(K, V)>() + self.iter().map(|(key, value)| key.heap_size(ctx) + value.heap_size(ctx)).sum::<usize>()
+    }
+}
+
+macro_rules! tuple_heap_size {
+    ($($args:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($args),*> $crate::heap_size::HeapSize for ($($args,)*)
+        where $($args: $crate::heap_size::HeapSize),*
+        {
+            #[inline]
+            fn heap_size(&self, ctx: &mut $crate::heap_size::HeapSizeContext) -> usize {
+                match self {
+                    ($($args,)*) => {
+                        0 $(
+                            + <$args as $crate::heap_size::HeapSize>::heap_size($args, ctx)
+                        )*
+                    }
+                }
+            }
+        }
+    }
+}
+
+macro_rules! tuple_heap_sizes {
+    ($(($($args:ident $_lower:ident),+);)*) => {
+        $(
+            tuple_heap_size!($($args),*);
+        )*
+    }
+}
+
+tuple_arities!(tuple_heap_sizes);
@@ -149,22 +149,34 @@ struct Foo {
 use core::ptr::NonNull;
 use core::ops::{Deref, DerefMut};
 
-use crate::cc::CcBox;
+use crate::cc::{add_to_list, CcBox};
 use crate::counter_marker::Mark;
 use crate::lists::*;
-use crate::state::{replace_state_field, State, try_state};
+use crate::state::{replace_state_field, CollectError, CollectionReport, State, try_state};
 use crate::trace::ContextInner;
 use crate::utils::*;
 
 #[cfg(all(test, feature = "std"))]
 mod tests;
 
+pub mod allocator;
 mod cc;
+pub mod closure;
+pub mod cell;
 mod counter_marker;
+pub mod deep_clone;
+pub mod graph;
+pub mod heap_size;
 mod lists;
+mod order;
+mod pending;
+pub mod roots;
 pub mod state;
+mod std_types;
+mod third_party;
 mod trace;
 mod utils;
+mod walk;
 
 #[cfg(feature = "auto-collect")]
 pub mod config;
@@ -178,31 +190,137 @@ struct Foo {
 #[cfg(feature = "cleaners")]
 pub mod cleaners;
 
+#[cfg(feature = "finalization")]
+pub mod resurrection;
+
+#[cfg(feature = "serde")]
+pub mod serialization;
+
 #[cfg(feature = "derive")]
 pub use derives::{Finalize, Trace};
 
 pub use cc::Cc;
-pub use trace::{Context, Finalize, Trace};
+pub use trace::{Context, Finalize, Trace, Untraced};
+
+/// The default maximum number of rounds executed by a collection (see [`config::Config::finalization_round_limit`]).
+#[cfg(feature = "finalization")]
+pub(crate) const DEFAULT_FINALIZATION_ROUND_LIMIT: core::num::NonZeroUsize = match core::num::NonZeroUsize::new(10) {
+    Some(limit) => limit,
+    None => unreachable!(),
+};
 
 rust_cc_thread_local! {
     pub(crate) static POSSIBLE_CYCLES: PossibleCycles = PossibleCycles::new();
+
+    // The list of frozen objects. Being inside it is what makes an object frozen, see CcBox::is_frozen
+    pub(crate) static FROZEN: FrozenList = FrozenList::new();
 }
 
 /// Immediately executes the cycle collection algorithm and collects garbage cycles.
 ///
 /// Calling this function during a collection won't start a new collection.
+/// See [`try_collect_cycles`] to know whether the collection has been executed.
 pub fn collect_cycles() {
-    let _ = try_state(|state| {
+    let _ = try_collect_cycles();
+}
+
+/// Immediately executes the cycle collection algorithm and collects garbage cycles, returning
+/// the [`CollectionReport`] of the executed collection.
+///
+/// A collection is started also when called while a [`Cc`] is being dropped outside of a collection
+/// (for example, inside a [`Drop`] implementation invoked by dropping the last [`Cc`] to an object).
+///
+/// # Errors
+///
+/// Returns [`CollectError::AlreadyCollecting`] if called during a collection (no new collection is started),
+/// [`CollectError::AccessError`] if the state of the collector couldn't be accessed and [`CollectError::Panicked`]
+/// if a panic happened during the collection and the [panic policy][`crate::config::PanicPolicy`] caught it.
+///
+/// # Panics
+///
+/// Panics if a [`Trace`], [`Finalize`] or [`Drop`] implementation panics during the collection,
+/// unless the [panic policy][`crate::config::PanicPolicy`] says otherwise.
+pub fn try_collect_cycles() -> Result<CollectionReport, CollectError> {
+    try_state(|state| {
         if state.is_collecting() {
-            return;
+            return Err(CollectError::AlreadyCollecting);
         }
 
+        POSSIBLE_CYCLES.try_with(|pc| {
+            let res = collect(state, pc);
+
+            #[cfg(feature = "auto-collect")]
+            adjust_trigger_point(state, pc);
+
+            res
+        }).unwrap_or(Err(CollectError::AccessError))
+    }).unwrap_or(Err(CollectError::AccessError))
+}
+
+/// Freezes every object buffered to be processed in the next collection (see [`Cc::mark_alive`]),
+/// together with every object reachable from them.
+///
+/// Objects which are neither buffered nor reachable from a buffered object are not frozen. To freeze every object
+/// reachable from a given root, use [`Cc::freeze`].
+///
+/// Frozen objects are never buffered and the collector stops tracing when reaching them, reducing the amount of work
+/// done by collections after a large initialization phase. However, garbage cycles containing frozen objects are
+/// never collected until [`unfreeze`] is called. Frozen objects are still deallocated when their last [`Cc`] is dropped.
+///
+/// Use [`state::frozen_objects_count`] and [`state::frozen_bytes`] to know how many objects are frozen.
+///
+/// # Panics
+///
+/// Panics if called during a collection or while a [`Cc`] is being dropped.
+#[track_caller]
+pub fn freeze() {
+    freeze_reachable(|pc, roots| {
+        // Elements are marked NonMarked by remove_first
+        while let Some(ptr) = pc.remove_first() {
+            roots.push(ptr);
+        }
+    });
+}
+
+/// Freezes every object reachable from the roots pushed by `roots`, including the roots themselves.
+#[track_caller]
+pub(crate) fn freeze_reachable(roots: impl FnOnce(&PossibleCycles, &mut alloc::vec::Vec<NonNull<CcBox<()>>>)) {
+    walk::walk(|walker| {
         let _ = POSSIBLE_CYCLES.try_with(|pc| {
-            collect(state, pc);
+            let _ = FROZEN.try_with(|frozen| {
+                let mut to_visit = alloc::vec::Vec::new();
+                roots(pc, &mut to_visit);
+
+                while let Some(ptr) = to_visit.pop() {
+                    let counter_marker = unsafe { ptr.as_ref() }.counter_marker();
+
+                    // Also skips immortal objects
+                    if unsafe { ptr.as_ref() }.is_frozen() {
+                        continue;
+                    }
+
+                    if counter_marker.is_in_possible_cycles() {
+                        counter_marker.mark(Mark::NonMarked);
+                        pc.remove(ptr);
+                    }
+
+                    frozen.add(ptr);
+                    walker.children(ptr, &mut |child| to_visit.push(child));
+                }
+            });
         });
+    });
+}
 
-        #[cfg(feature = "auto-collect")]
-        adjust_trigger_point(state);
+/// Unfreezes every frozen object, buffering it to be processed in the next collection.
+///
+/// See [`freeze`] for more details.
+pub fn unfreeze() {
+    let _ = FROZEN.try_with(|frozen| {
+        // Frozen elements are always marked NonMarked
+        while let Some(ptr) = frozen.remove_first() {
+            add_to_list(ptr);
+        }
     });
 }
 
@@ -215,21 +333,96 @@ pub(crate) fn trigger_collection(state: &State) {
 
     let _ = POSSIBLE_CYCLES.try_with(|pc| {
         if config::config(|config| config.should_collect(state, pc)).unwrap_or(false) {
-            collect(state, pc);
+            let _ = collect(state, pc);
 
-            adjust_trigger_point(state);
+            adjust_trigger_point(state, pc);
         }
     });
 }
 
+/// Forces a collection, regardless of the configuration.
 #[cfg(feature = "auto-collect")]
-fn adjust_trigger_point(state: &State) {
-    let _ = config::config(|config| config.adjust(state));
+pub(crate) fn force_collection(state: &State) {
+    if state.is_collecting() {
+        return;
+    }
+
+    let _ = POSSIBLE_CYCLES.try_with(|pc| {
+        let _ = collect(state, pc);
+
+        adjust_trigger_point(state, pc);
+    });
+}
+
+#[cfg(feature = "auto-collect")]
+fn adjust_trigger_point(state: &State, possible_cycles: &PossibleCycles) {
+    let _ = config::config(|config| config.adjust(state, possible_cycles));
+}
+
+fn collect(state: &State, possible_cycles: &PossibleCycles) -> Result<CollectionReport, CollectError> {
+    // Panics are always propagated when the configuration is not available
+    #[cfg(not(feature = "auto-collect"))]
+    {
+        Ok(collect_inner(state, possible_cycles))
+    }
+
+    #[cfg(feature = "auto-collect")]
+    match config::config(|config| config.panic_policy()).unwrap_or_default() {
+        config::PanicPolicy::Propagate => Ok(collect_inner(state, possible_cycles)),
+        config::PanicPolicy::Abort => {
+            struct AbortGuard;
+
+            impl Drop for AbortGuard {
+                #[inline]
+                fn drop(&mut self) {
+                    // This is executed only while unwinding from a panic
+                    #[cfg(feature = "std")]
+                    std::process::abort();
+
+                    // Panicking while unwinding aborts the process
+                    #[cfg(not(feature = "std"))]
+                    panic!("a panic occurred during a collection");
+                }
+            }
+
+            let abort_guard = AbortGuard;
+            let report = collect_inner(state, possible_cycles);
+            mem::forget(abort_guard);
+            Ok(report)
+        },
+        #[cfg(feature = "std")]
+        config::PanicPolicy::CatchAndLeak => {
+            // The lists used by the collection are local to __collect, so they're dropped while unwinding.
+            // Their destructors remove and unmark every object they contain, leaking them without
+            // leaving them in any list, so they're never accessed again by the collector
+            // (see test_catch_and_leak_panic_policy_finalize)
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                collect_inner(state, possible_cycles)
+            })).map_err(|_| CollectError::Panicked)
+        },
+    }
 }
 
-fn collect(state: &State, possible_cycles: &PossibleCycles) {
+fn collect_inner(state: &State, possible_cycles: &PossibleCycles) -> CollectionReport {
     state.set_collecting(true);
     state.increment_executions_count();
+    state.reset_allocations_count();
+
+    #[cfg(feature = "std")]
+    let start = std::time::Instant::now();
+
+    let mut report = CollectionReport {
+        allocated_bytes_before: state.allocated_bytes(),
+        allocated_bytes_after: 0,
+        buffered_objects_before: possible_cycles.size(),
+        traced_objects: 0,
+        deallocated_objects: 0,
+        skipped_borrowed: 0,
+        rounds: 0,
+        round_limit_reached: false,
+        #[cfg(feature = "std")]
+        duration: core::time::Duration::ZERO,
+    };
 
     struct DropGuard<'a> {
         state: &'a State,
@@ -244,11 +437,16 @@ fn drop(&mut self) {
 
     let _drop_guard = DropGuard { state };
 
+    #[cfg(feature = "auto-collect")]
+    let topological = config::config(|config| config.finalization_order())
+        .is_ok_and(|order| order == config::FinalizationOrder::Topological);
+    #[cfg(not(feature = "auto-collect"))]
+    let topological = false;
+
     #[cfg(feature = "finalization")]
-    for _ in 0..10 {
-        // Limit to 10 executions. A collection usually completes in 2 executions, so passing
-        // 10 and still having objects to clean up and finalize almost surely means that some
-        // finalizer is doing something weird, like the following:
+    {
+        // A collection usually completes in 2 rounds, so reaching the limit and still having objects to clean up and
+        // finalize almost surely means that some finalizer is doing something weird, like the following:
         //
         // thread_local! { static VEC: RefCell<Vec<Cc<MyStruct>>> = ... }
         // #[derive(Trace)]
@@ -258,36 +456,69 @@ fn drop(&mut self) {
         //         let _ = VEC.with(|vec| vec.borrow_mut().pop()); // Popping one at a time
         //     }
         // }
-        // Insert 100 MyStruct into VEC and then drop one -> 100 executions
+        // Insert 100 MyStruct into VEC and then drop one -> 100 rounds
         //
-        // Thus, it is fine to just leave the remaining objects into POSSIBLE_CYCLES for the
+        // Thus, by default it is fine to just leave the remaining objects into POSSIBLE_CYCLES for the
         // next collection execution. The program has already been stopped for too much time.
 
-        if possible_cycles.is_empty() {
-            break;
-        }
+        #[cfg(feature = "auto-collect")]
+        let round_limit = config::config(|config| config.finalization_round_limit())
+            .unwrap_or(Some(DEFAULT_FINALIZATION_ROUND_LIMIT));
+        #[cfg(not(feature = "auto-collect"))]
+        let round_limit = Some(DEFAULT_FINALIZATION_ROUND_LIMIT);
+
+        while !possible_cycles.is_empty() {
+            if round_limit.is_some_and(|limit| report.rounds >= limit.get()) {
+                report.round_limit_reached = true;
+                break;
+            }
 
-        __collect(state, possible_cycles);
+            __collect(state, possible_cycles, &mut report, topological);
+            report.rounds += 1;
+        }
     }
     #[cfg(not(feature = "finalization"))]
     if !possible_cycles.is_empty() {
-        __collect(state, possible_cycles);
+        __collect(state, possible_cycles, &mut report, topological);
+        report.rounds += 1;
     }
 
+    report.allocated_bytes_after = state.allocated_bytes();
+    #[cfg(feature = "std")]
+    {
+        report.duration = start.elapsed();
+    }
+    state.set_last_collection_report(report);
+
+    #[cfg(feature = "finalization")]
+    resurrection::panic_on_resurrections(state);
+
+    report
     // _drop_guard is dropped here, setting state.collecting to false
 }
 
-fn __collect(state: &State, possible_cycles: &PossibleCycles) {
+fn __collect(state: &State, possible_cycles: &PossibleCycles, report: &mut CollectionReport, topological: bool) {
     let mut non_root_list = LinkedList::new();
     {
         let mut root_list = LinkedList::new();
         let mut queue = LinkedQueue::new();
 
-        trace_counting(possible_cycles, &mut root_list, &mut non_root_list, &mut queue);
+        report.traced_objects += trace_counting(
+            possible_cycles,
+            &mut root_list,
+            &mut non_root_list,
+            &mut queue,
+            &mut report.skipped_borrowed,
+        );
         trace_roots(root_list, &mut non_root_list, queue);
     }
 
     if !non_root_list.is_empty() {
+        if topological {
+            // Finalization and deallocation both follow the order of non_root_list
+            order::sort_topologically(&mut non_root_list);
+        }
+
         #[cfg(feature = "pedantic-debug-assertions")]
         non_root_list.iter().for_each(|ptr| {
             let counter_marker = unsafe { ptr.as_ref() }.counter_marker();
@@ -314,7 +545,12 @@ fn __collect(state: &State, possible_cycles: &PossibleCycles) {
                 // _finalizing_guard is dropped here, resetting state.finalizing
             }
 
+            if has_finalized && state.resurrection_detection() != resurrection::DetectionMode::Disabled {
+                resurrection::detect_resurrections(&non_root_list);
+            }
+
             if !has_finalized {
+                report.deallocated_objects += non_root_list_size;
                 deallocate_list(non_root_list, state);
             } else {
                 // Put CcBoxes back into the possible cycles list. They will be re-processed in the
@@ -342,6 +578,7 @@ fn __collect(state: &State, possible_cycles: &PossibleCycles) {
 
         #[cfg(not(feature = "finalization"))]
         {
+            report.deallocated_objects += non_root_list.iter().count();
             deallocate_list(non_root_list, state);
         }
     }
@@ -443,19 +680,26 @@ fn trace_counting(
     root_list: &mut LinkedList,
     non_root_list: &mut LinkedList,
     queue: &mut LinkedQueue,
-) {
+    skipped_borrowed: &mut usize,
+) -> usize {
+    let mut traced_objects = 0usize;
+
     while let Some(ptr) = possible_cycles.remove_first() {
         // The tracing counter has already been reset by add_to_list(...)
-        __trace_counting(ptr, root_list, non_root_list, queue);
+        __trace_counting(ptr, root_list, non_root_list, queue, skipped_borrowed);
+        traced_objects += 1;
     }
 
     while let Some(ptr) = queue.poll() {
         // The tracing counter has already been reset by CcBox::trace when ptr was inserted into the queue
-        __trace_counting(ptr, root_list, non_root_list, queue);
+        __trace_counting(ptr, root_list, non_root_list, queue, skipped_borrowed);
+        traced_objects += 1;
     }
 
     debug_assert!(possible_cycles.is_empty());
     debug_assert!(queue.is_empty());
+
+    traced_objects
 }
 
 fn __trace_counting(
@@ -463,6 +707,7 @@ fn __trace_counting(
     root_list: &mut LinkedList,
     non_root_list: &mut LinkedList,
     queue: &mut LinkedQueue,
+    skipped_borrowed: &mut usize,
 ) {
     let counter_marker = unsafe { ptr.as_ref() }.counter_marker();
 
@@ -476,6 +721,7 @@ fn __trace_counting(
         root_list,
         non_root_list,
         queue,
+        skipped_borrowed,
     });
     CcBox::trace_inner(ptr, &mut ctx);
 
@@ -98,7 +98,7 @@ pub(crate) fn is_empty(&self) -> bool {
     }
 
     #[inline]
-    pub(crate) fn iter(&self) -> Iter {
+    pub(crate) fn iter(&self) -> Iter<'_> {
         self.into_iter()
     }
 }
@@ -341,7 +341,7 @@ pub(crate) unsafe fn swap_list(&self, to_swap: &mut LinkedList, to_swap_size: us
         feature = "finalization",
         all(test, feature = "std") // Unit tests
     ))]
-    pub(crate) fn iter(&self) -> Iter {
+    pub(crate) fn iter(&self) -> Iter<'_> {
         self.into_iter()
     }
 }
@@ -369,6 +369,147 @@ fn into_iter(self) -> Self::IntoIter {
     }
 }
 
+/// A circular doubly-linked list, used to keep track of [frozen][`crate::freeze`] objects.
+///
+/// Differently from the other lists, the `next` and `prev` fields of the elements inside this list are never `None`
+/// (an element alone in the list points to itself). This makes it possible to know whether an object is frozen
+/// without using any bit of its counter (see [`CcBox::is_frozen`][`crate::cc::CcBox::is_frozen`]).
+pub(crate) struct FrozenList {
+    first: Cell<Option<NonNull<CcBox<()>>>>,
+    size: Cell<usize>,
+}
+
+impl FrozenList {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            first: Cell::new(None),
+            size: Cell::new(0),
+        }
+    }
+
+    #[inline]
+    #[cfg(all(test, feature = "std"))] // Only used in unit tests
+    pub(crate) fn reset(&self) {
+        self.first.set(None);
+        self.size.set(0);
+    }
+
+    #[inline]
+    pub(crate) fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    #[inline]
+    pub(crate) fn add(&self, ptr: NonNull<CcBox<()>>) {
+        debug_assert_nones(ptr);
+
+        self.size.set(self.size.get() + 1);
+
+        unsafe {
+            if let Some(first) = self.first.get() {
+                // Insert ptr between the last element and the first one
+                let last = (*first.as_ref().get_prev()).unwrap_unchecked();
+                *ptr.as_ref().get_next() = Some(first);
+                *ptr.as_ref().get_prev() = Some(last);
+                *last.as_ref().get_next() = Some(ptr);
+                *first.as_ref().get_prev() = Some(ptr);
+            } else {
+                // ptr is the only one in the list
+                *ptr.as_ref().get_next() = Some(ptr);
+                *ptr.as_ref().get_prev() = Some(ptr);
+            }
+        }
+
+        self.first.set(Some(ptr));
+    }
+
+    #[inline]
+    pub(crate) fn remove(&self, ptr: NonNull<CcBox<()>>) {
+        self.size.set(self.size.get() - 1);
+
+        unsafe {
+            // Elements inside the list always have both next and prev != None
+            let next = (*ptr.as_ref().get_next()).unwrap_unchecked();
+            let prev = (*ptr.as_ref().get_prev()).unwrap_unchecked();
+
+            if next == ptr {
+                // ptr is the only one in the list
+                self.first.set(None);
+            } else {
+                *next.as_ref().get_prev() = Some(prev);
+                *prev.as_ref().get_next() = Some(next);
+
+                if self.first.get() == Some(ptr) {
+                    self.first.set(Some(next));
+                }
+            }
+
+            *ptr.as_ref().get_next() = None;
+            *ptr.as_ref().get_prev() = None;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn remove_first(&self) -> Option<NonNull<CcBox<()>>> {
+        let first = self.first.get()?;
+        self.remove(first);
+        Some(first)
+    }
+
+    #[inline]
+    pub(crate) fn iter(&self) -> FrozenIter<'_> {
+        self.into_iter()
+    }
+}
+
+impl Drop for FrozenList {
+    #[inline]
+    fn drop(&mut self) {
+        // Remove the remaining elements from the list
+        while self.remove_first().is_some() {
+            // Frozen elements are already marked NonMarked
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a FrozenList {
+    type Item = NonNull<CcBox<()>>;
+    type IntoIter = FrozenIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        FrozenIter {
+            first: self.first.get(),
+            next: self.first.get(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub(crate) struct FrozenIter<'a> {
+    first: Option<NonNull<CcBox<()>>>,
+    next: Option<NonNull<CcBox<()>>>,
+    _phantom: PhantomData<&'a CcBox<()>>,
+}
+
+impl<'a> Iterator for FrozenIter<'a> {
+    type Item = NonNull<CcBox<()>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let ptr = self.next?;
+        unsafe {
+            self.next = *ptr.as_ref().get_next();
+        }
+        // Stop when coming back to the first element
+        if self.next == self.first {
+            self.next = None;
+        }
+        Some(ptr)
+    }
+}
+
 pub(crate) struct LinkedQueue {
     first: Option<NonNull<CcBox<()>>>,
     last: Option<NonNull<CcBox<()>>>,
@@ -0,0 +1,116 @@
+//! Topological ordering of garbage sets.
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+
+use crate::cc::CcBox;
+use crate::lists::LinkedList;
+use crate::trace::{Context, ContextInner};
+
+const UNVISITED: usize = usize::MAX;
+
+/// Reorders `list` so that every object comes before the objects it references, with the objects of the same
+/// strongly connected component sorted in the order they are visited by a depth-first traversal.
+///
+/// Only the references between the objects of `list` are considered. Must be called while collecting.
+pub(crate) fn sort_topologically(list: &mut LinkedList) {
+    let nodes: Vec<NonNull<CcBox<()>>> = list.iter().collect();
+    if nodes.len() < 2 {
+        return;
+    }
+
+    let indices: BTreeMap<usize, usize> = nodes.iter()
+        .enumerate()
+        .map(|(i, ptr)| (ptr.as_ptr() as usize, i))
+        .collect();
+
+    // Trace every object before touching the list, since tracing may panic
+    let edges: Vec<Vec<usize>> = nodes.iter().map(|&ptr| {
+        let mut children = Vec::new();
+        let mut visitor = |child: NonNull<CcBox<()>>| {
+            if let Some(&i) = indices.get(&(child.as_ptr() as usize)) {
+                children.push(i);
+            }
+        };
+        let mut ctx = Context::new(ContextInner::Walking { visitor: &mut visitor });
+        CcBox::trace_inner(ptr, &mut ctx);
+        children
+    }).collect();
+
+    let order = tarjan(&edges);
+
+    // LinkedList::add inserts at the front, so insert in reverse order
+    for &i in order.iter().rev() {
+        list.remove(nodes[i]);
+        list.add(nodes[i]);
+    }
+}
+
+/// Returns the nodes of the graph sorted so that every strongly connected component precedes the components
+/// it has edges to. Inside a component, nodes are sorted by discovery order.
+fn tarjan(edges: &[Vec<usize>]) -> Vec<usize> {
+    let len = edges.len();
+    let mut discovery = vec![UNVISITED; len];
+    let mut lowlink = vec![0usize; len];
+    let mut on_stack = vec![false; len];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut call_stack: Vec<(usize, usize)> = Vec::new(); // (node, index of the next edge to visit)
+    let mut next_discovery = 0usize;
+
+    // Tarjan's algorithm produces the components in reverse topological order
+    let mut components: Vec<Vec<usize>> = Vec::new();
+
+    let mut visit = |node: usize, discovery: &mut [usize], lowlink: &mut [usize], on_stack: &mut [bool], stack: &mut Vec<usize>| {
+        discovery[node] = next_discovery;
+        lowlink[node] = next_discovery;
+        next_discovery += 1;
+        on_stack[node] = true;
+        stack.push(node);
+    };
+
+    for root in 0..len {
+        if discovery[root] != UNVISITED {
+            continue;
+        }
+
+        visit(root, &mut discovery, &mut lowlink, &mut on_stack, &mut stack);
+        call_stack.push((root, 0));
+
+        while let Some((node, next_edge)) = call_stack.last_mut() {
+            let node = *node;
+            if let Some(&child) = edges[node].get(*next_edge) {
+                *next_edge += 1;
+                if discovery[child] == UNVISITED {
+                    visit(child, &mut discovery, &mut lowlink, &mut on_stack, &mut stack);
+                    call_stack.push((child, 0));
+                } else if on_stack[child] {
+                    lowlink[node] = lowlink[node].min(discovery[child]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+
+            if lowlink[node] == discovery[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                // Members are popped in reverse discovery order
+                component.reverse();
+                components.push(component);
+            }
+        }
+    }
+
+    components.into_iter().rev().flatten().collect()
+}
@@ -0,0 +1,111 @@
+use core::cell::{Cell, UnsafeCell};
+use core::mem::{ManuallyDrop, MaybeUninit};
+
+use crate::{Cc, Context, Finalize, Trace};
+
+/// A [`Cc`] whose value is initialized only after its creation, used to recreate cycles.
+///
+/// The `Cc<T>`s returned by [`to_cc`][`PendingCc::to_cc`] can be stored before the value is initialized, but they
+/// **must not** be dereferenced until then.
+///
+/// If a [`PendingCc`] is dropped before its value is initialized (for example, because of a panic), the allocation is
+/// leaked, since the `Cc<T>`s pointing to it would otherwise be able to drop it as an initialized `T`.
+pub(crate) struct PendingCc<T: Trace + 'static> {
+    slot: ManuallyDrop<Cc<PendingSlot<T>>>,
+}
+
+impl<T: Trace> PendingCc<T> {
+    #[inline]
+    #[track_caller]
+    pub(crate) fn new() -> PendingCc<T> {
+        PendingCc {
+            slot: ManuallyDrop::new(Cc::new(PendingSlot {
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+                initialized: Cell::new(false),
+            })),
+        }
+    }
+
+    /// Returns a new `Cc<T>` pointing to the allocation, which must not be dereferenced until the value is initialized.
+    #[inline]
+    pub(crate) fn to_cc(&self) -> Cc<T> {
+        let slot = ManuallyDrop::new((*self.slot).clone());
+        // This cast is correct since the value of a PendingSlot is at the same offset of a T
+        Cc::__new_internal(slot.inner_ptr().cast())
+    }
+
+    /// # Safety
+    /// The value must not be already initialized and no reference to it must exist.
+    #[inline]
+    pub(crate) unsafe fn init(&self, value: T) {
+        debug_assert!(!self.slot.initialized.get());
+        (*self.slot.value.get()).write(value);
+        self.slot.initialized.set(true);
+    }
+}
+
+impl<T: Trace> Clone for PendingCc<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        PendingCc {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<T: Trace> Drop for PendingCc<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.slot.initialized.get() {
+            // SAFETY: the slot is never used again
+            unsafe { ManuallyDrop::drop(&mut self.slot) };
+        }
+    }
+}
+
+/// The value of the allocation of a [`PendingCc`].
+///
+/// The collector traces, finalizes and drops the allocation through the vtable saved by [`Cc::new`], i.e. using the
+/// implementations of [`PendingSlot`] (which check whether the value has been initialized). However, when the last
+/// reference is a `Cc<T>` returned by [`to_cc`][`PendingCc::to_cc`], [`Cc`]'s [`Drop`] implementation calls `T::finalize` and
+/// `drop_in_place::<T>` statically on the cast `Cc<T>`, bypassing [`PendingSlot`]. This is sound only because:
+/// - the `#[repr(C)]` layout places the value at the start of [`PendingSlot`] and a `MaybeUninit<T>` inside an
+///   [`UnsafeCell`] has the same layout of a `T`, so the cast `Cc<T>` points to a valid `T`;
+/// - the [`PendingCc`] keeps the allocation alive until the value is initialized (leaking it otherwise), so a `Cc<T>`
+///   can be the last reference only to an initialized value;
+/// - the layout used for deallocation is always read from the vtable, so the whole [`PendingSlot`] is deallocated.
+#[repr(C)] // value must be the first field, to have the same offset of a T inside a CcBox
+struct PendingSlot<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    initialized: Cell<bool>,
+}
+
+unsafe impl<T: Trace> Trace for PendingSlot<T> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        if self.initialized.get() {
+            // SAFETY: the value is initialized
+            unsafe { (*self.value.get()).assume_init_ref() }.trace(ctx);
+        }
+    }
+}
+
+impl<T: Finalize> Finalize for PendingSlot<T> {
+    #[inline]
+    fn finalize(&self) {
+        if self.initialized.get() {
+            // SAFETY: the value is initialized
+            unsafe { (*self.value.get()).assume_init_ref() }.finalize();
+        }
+    }
+}
+
+impl<T> Drop for PendingSlot<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.initialized.get() {
+            // SAFETY: the value is initialized
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
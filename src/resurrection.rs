@@ -0,0 +1,202 @@
+//! Detection of objects resurrected by finalizers.
+//!
+//! A finalizer may *resurrect* the object being finalized (or other objects of the same garbage cycle) by storing a new
+//! [`Cc`][`crate::Cc`] to it somewhere reachable, for example by cloning a [`Cc`][`crate::Cc`] into a thread-local or by
+//! upgrading a [`Weak`][`crate::weak::Weak`]. The collector handles this correctly, but resurrections are usually
+//! unintended and make collections slower, since every finalized object has to be processed again.
+//!
+//! When detection is enabled using [`set_detection_mode`], the collector compares the reference counter of every object
+//! before and after running the finalizers, reporting every object whose counter has increased. Objects are reported with
+//! their type name and, if they have been allocated while detection was enabled, with the location where they've been allocated.
+//! Note that an object is also reported when a finalizer stores a new reference to it inside another garbage object.
+//!
+//! Reports can be retrieved using [`take_resurrections`]. With [`DetectionMode::Panic`], the collection panics instead
+//! when a resurrection is detected, which is useful in tests.
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r#"# use std::cell::RefCell;
+# use rust_cc::*;
+# use rust_cc::resurrection::*;
+thread_local! {
+    static RESURRECTED: RefCell<Option<Cc<Zombie>>> = const { RefCell::new(None) };
+}
+
+#[derive(Trace)]
+struct Zombie {
+    cyclic: RefCell<Option<Cc<Zombie>>>,
+}
+
+impl Finalize for Zombie {
+    fn finalize(&self) {
+        let cc = self.cyclic.borrow().clone();
+        RESURRECTED.with(|resurrected| *resurrected.borrow_mut() = cc);
+    }
+}
+
+set_detection_mode(DetectionMode::Report).unwrap();
+
+let zombie = Cc::new(Zombie { cyclic: RefCell::new(None) });
+*zombie.cyclic.borrow_mut() = Some(zombie.clone());
+drop(zombie);
+collect_cycles();
+
+let resurrections = take_resurrections();
+assert_eq!(1, resurrections.len());
+assert!(resurrections[0].type_name().ends_with("Zombie"));
+assert!(resurrections[0].allocation_site().is_some());
+# set_detection_mode(DetectionMode::Disabled).unwrap();
+# RESURRECTED.with(|resurrected| resurrected.borrow_mut().take().unwrap().cyclic.borrow_mut().take());
+```"#]
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::{self, Display, Formatter, Write};
+use core::panic::Location;
+use core::ptr::NonNull;
+
+use crate::cc::CcBox;
+use crate::lists::LinkedList;
+use crate::state::{try_state, State, StateAccessError};
+use crate::utils::rust_cc_thread_local;
+
+rust_cc_thread_local! {
+    // The allocation sites of the objects allocated while detection was enabled, indexed by address
+    static ALLOCATION_SITES: RefCell<BTreeMap<usize, &'static Location<'static>>> = const { RefCell::new(BTreeMap::new()) };
+
+    static RESURRECTIONS: RefCell<Vec<Resurrection>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Whether and how resurrections are detected.
+///
+/// See the [module-level documentation][`mod@crate::resurrection`] for more details.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DetectionMode {
+    /// Resurrections are not detected.
+    #[default]
+    Disabled,
+    /// Resurrections are detected and can be retrieved using [`take_resurrections`].
+    Report,
+    /// Resurrections are detected and the collection panics at its end if any is found.
+    Panic,
+}
+
+/// Sets the [`DetectionMode`].
+///
+/// Allocation sites are recorded only for the objects allocated while detection is enabled.
+/// Disabling detection discards every recorded allocation site.
+#[inline]
+pub fn set_detection_mode(mode: DetectionMode) -> Result<(), StateAccessError> {
+    try_state(|state| {
+        state.set_resurrection_detection(mode);
+        if mode == DetectionMode::Disabled {
+            let _ = ALLOCATION_SITES.try_with(|sites| sites.borrow_mut().clear());
+        }
+    })
+}
+
+/// Returns the current [`DetectionMode`].
+#[inline]
+pub fn detection_mode() -> Result<DetectionMode, StateAccessError> {
+    try_state(|state| state.resurrection_detection())
+}
+
+/// Returns the resurrections detected since the last call to this function.
+#[inline]
+pub fn take_resurrections() -> Vec<Resurrection> {
+    RESURRECTIONS.try_with(|resurrections| resurrections.take()).unwrap_or_default()
+}
+
+/// An object resurrected by a finalizer.
+#[derive(Clone, Debug)]
+pub struct Resurrection {
+    type_name: &'static str,
+    allocation_site: Option<&'static Location<'static>>,
+}
+
+impl Resurrection {
+    /// Returns the name of the type of the resurrected object, as returned by [`type_name`][`core::any::type_name`].
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the location where the resurrected object has been allocated,
+    /// or [`None`] if it has been allocated while detection was disabled.
+    #[inline]
+    pub fn allocation_site(&self) -> Option<&'static Location<'static>> {
+        self.allocation_site
+    }
+}
+
+impl Display for Resurrection {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.allocation_site {
+            Some(site) => write!(f, "{} (allocated at {})", self.type_name, site),
+            None => write!(f, "{}", self.type_name),
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn record_allocation_site(ptr: NonNull<CcBox<()>>, site: &'static Location<'static>) {
+    let _ = ALLOCATION_SITES.try_with(|sites| {
+        sites.borrow_mut().insert(ptr.as_ptr() as usize, site);
+    });
+}
+
+#[inline]
+pub(crate) fn remove_allocation_site(ptr: NonNull<CcBox<()>>) {
+    let _ = ALLOCATION_SITES.try_with(|sites| {
+        sites.borrow_mut().remove(&(ptr.as_ptr() as usize));
+    });
+}
+
+/// Records as resurrected every object of `non_root_list` which gained new references during finalization.
+///
+/// The tracing counter of an object in `non_root_list` is equal to its reference counter before finalization.
+pub(crate) fn detect_resurrections(non_root_list: &LinkedList) {
+    let _ = RESURRECTIONS.try_with(|resurrections| {
+        let _ = ALLOCATION_SITES.try_with(|sites| {
+            let sites = sites.borrow();
+            let mut resurrections = resurrections.borrow_mut();
+
+            for ptr in non_root_list {
+                let counter_marker = unsafe { ptr.as_ref() }.counter_marker();
+                if counter_marker.counter() > counter_marker.tracing_counter() {
+                    resurrections.push(Resurrection {
+                        type_name: CcBox::type_name_inner(ptr),
+                        allocation_site: sites.get(&(ptr.as_ptr() as usize)).copied(),
+                    });
+                }
+            }
+        });
+    });
+}
+
+/// Panics if resurrections have been detected and the [`DetectionMode`] is [`DetectionMode::Panic`].
+#[track_caller]
+pub(crate) fn panic_on_resurrections(state: &State) {
+    if state.resurrection_detection() != DetectionMode::Panic {
+        return;
+    }
+
+    let resurrections = take_resurrections();
+    if !resurrections.is_empty() {
+        let mut message = String::from("objects have been resurrected during finalization:");
+        for resurrection in &resurrections {
+            let _ = write!(message, "\n  {}", resurrection);
+        }
+        panic!("{}", message);
+    }
+}
@@ -0,0 +1,420 @@
+//! Explicit roots for embedders.
+//!
+//! Runtimes embedding rust-cc usually keep many references to [`Cc`]s in places like interpreter stacks.
+//! Instead of storing a [`Cc`] in every stack slot, a [`Cc`] can be moved into a [`RootSet`], which returns a lightweight
+//! [`Handle`] to its allocation. [`Handle`]s are [`Copy`] and don't touch any reference count, since their lifetime is bound
+//! to the [`RootSet`].
+//!
+//! A [`RootSet`] doesn't clone the [`Cc`]s moved into it, it takes ownership of their strong references instead.
+//! The collector doesn't know about [`RootSet`]s: the registered allocations (and everything reachable from them) are
+//! kept alive by these strong references, in the same way as by any other [`Cc`] owned outside the object graph.
+//!
+//! The registered allocations can be enumerated using [`RootSet::iter`] and are released in bulk when
+//! the [`RootSet`] is [cleared][`RootSet::clear`] or dropped.
+//!
+//! [`HandleScope`]s are [`RootSet`]s which can be nested, allowing to [escape][`HandleScope::escape`]
+//! a [`Handle`] into the enclosing scope.
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r"# use rust_cc::*;
+# use rust_cc::roots::*;
+#[derive(Trace, Finalize)]
+struct Value(u32);
+
+let scope = HandleScope::new();
+let stack: Vec<Handle<'_, Value>> = (0..3).map(|i| scope.handle(Cc::new(Value(i)))).collect();
+assert_eq!(1, stack[1].0);
+assert_eq!(3, scope.len());
+
+let result = {
+    let inner = scope.nested();
+    let sum = inner.handle(Cc::new(Value(stack.iter().map(|value| value.0).sum())));
+    inner.escape(sum).unwrap()
+    // inner is dropped here, releasing its roots
+};
+assert_eq!(3, result.0);
+// scope is dropped here, releasing every value
+```"]
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::cell::RefCell;
+use core::fmt::{self, Debug, Formatter, Pointer};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::cc::CcBox;
+use crate::{Cc, Trace};
+
+/// A set of explicitly registered roots.
+///
+/// See the [module-level documentation][`mod@crate::roots`] for more details.
+pub struct RootSet {
+    entries: RefCell<Vec<Entry>>,
+    _phantom: PhantomData<Rc<()>>, // Make RootSet !Send and !Sync
+}
+
+#[derive(Copy, Clone)]
+struct Entry {
+    ptr: NonNull<CcBox<()>>,
+    type_id: TypeId,
+    release: unsafe fn(NonNull<CcBox<()>>),
+}
+
+/// # Safety
+/// `ptr` must point to a `CcBox<T>` and own a strong reference to it.
+unsafe fn release<T: Trace + 'static>(ptr: NonNull<CcBox<()>>) {
+    drop(Cc::<T>::__new_internal(ptr.cast()));
+}
+
+impl RootSet {
+    /// Creates a new empty [`RootSet`].
+    #[inline]
+    pub const fn new() -> RootSet {
+        RootSet {
+            entries: RefCell::new(Vec::new()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Registers the allocation pointed by `cc` as a root, returning a [`Handle`] to it.
+    ///
+    /// The strong reference owned by `cc` is moved into the [`RootSet`] without modifying the reference count, and
+    /// keeps the allocation alive until the [`RootSet`] is [cleared][`RootSet::clear`] or dropped.
+    /// To register an allocation while keeping a [`Cc`] to it, pass a clone of the [`Cc`].
+    #[inline]
+    pub fn root<T: Trace + 'static>(&self, cc: Cc<T>) -> Handle<'_, T> {
+        let ptr = ManuallyDrop::new(cc).inner_ptr();
+        self.entries.borrow_mut().push(Entry {
+            ptr: ptr.cast(),
+            type_id: TypeId::of::<T>(),
+            release: release::<T>,
+        });
+        Handle {
+            ptr,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of registered roots.
+    ///
+    /// An allocation registered multiple times is counted multiple times.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    /// Returns `true` if no root is registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    /// Returns an iterator over the registered roots, in registration order.
+    ///
+    /// Roots registered while iterating are also returned.
+    #[inline]
+    pub fn iter(&self) -> Roots<'_> {
+        Roots {
+            roots: self,
+            index: 0,
+        }
+    }
+
+    /// Releases every registered root.
+    ///
+    /// The released allocations are deallocated if they are no longer referenced, or are buffered to be
+    /// processed in the next collection otherwise (like when a [`Cc`] is dropped).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a finalizer or destructor of a released allocation panics. The roots not yet released remain registered.
+    #[inline]
+    pub fn clear(&mut self) {
+        // Release in reverse registration order, one at a time since releasing may panic
+        while let Some(entry) = self.entries.get_mut().pop() {
+            // SAFETY: the entry owns a strong reference to a CcBox of the right type
+            unsafe {
+                (entry.release)(entry.ptr);
+            }
+        }
+    }
+}
+
+impl Default for RootSet {
+    #[inline]
+    fn default() -> Self {
+        RootSet::new()
+    }
+}
+
+impl Drop for RootSet {
+    #[inline]
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl Debug for RootSet {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RootSet")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a RootSet {
+    type Item = Root<'a>;
+    type IntoIter = Roots<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Roots<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the roots registered into a [`RootSet`].
+///
+/// This `struct` is created by [`RootSet::iter`].
+pub struct Roots<'a> {
+    roots: &'a RootSet,
+    index: usize,
+}
+
+impl<'a> Iterator for Roots<'a> {
+    type Item = Root<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Root<'a>> {
+        let entry = *self.roots.entries.borrow().get(self.index)?;
+        self.index += 1;
+        Some(Root {
+            entry,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl Debug for Roots<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Roots").finish_non_exhaustive()
+    }
+}
+
+/// A type-erased root registered into a [`RootSet`], as returned by [`RootSet::iter`].
+#[derive(Copy, Clone)]
+pub struct Root<'a> {
+    entry: Entry,
+    _phantom: PhantomData<&'a RootSet>,
+}
+
+impl<'a> Root<'a> {
+    /// Returns `true` if the root points to a `T`.
+    #[inline]
+    pub fn is<T: Trace + 'static>(&self) -> bool {
+        self.entry.type_id == TypeId::of::<T>()
+    }
+
+    /// Returns a [`Handle`] to the root if it points to a `T`, or [`None`] otherwise.
+    #[inline]
+    pub fn downcast<T: Trace + 'static>(&self) -> Option<Handle<'a, T>> {
+        self.is::<T>().then(|| Handle {
+            ptr: self.entry.ptr.cast(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl Debug for Root<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Root")
+            .field("ptr", &self.entry.ptr)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A lightweight reference to an allocation kept alive by a [`RootSet`] or a [`HandleScope`].
+///
+/// Unlike [`Cc`], copying and dropping a [`Handle`] doesn't modify any reference count.
+pub struct Handle<'a, T: Trace + 'static> {
+    ptr: NonNull<CcBox<T>>,
+    _phantom: PhantomData<&'a T>,
+}
+
+impl<T: Trace> Handle<'_, T> {
+    /// Returns a new [`Cc`] pointing to the same allocation of this [`Handle`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the strong reference count exceeds the maximum supported.
+    #[inline]
+    #[track_caller]
+    pub fn to_cc(&self) -> Cc<T> {
+        let cc = ManuallyDrop::new(Cc::__new_internal(self.ptr));
+        (*cc).clone()
+    }
+
+    /// Returns `true` if the two [`Handle`]s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &Handle<'_, T>, other: &Handle<'_, T>) -> bool {
+        this.ptr == other.ptr
+    }
+}
+
+impl<T: Trace> Copy for Handle<'_, T> {}
+
+impl<T: Trace> Clone for Handle<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Trace> Deref for Handle<'_, T> {
+    type Target = T;
+
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        #[cfg(debug_assertions)]
+        if crate::state::state(|state| state.is_tracing()) {
+            panic!("Cannot deref while tracing!");
+        }
+
+        // SAFETY: the allocation is kept alive by the RootSet for the lifetime of the Handle
+        unsafe { self.ptr.as_ref() }.get_elem()
+    }
+}
+
+impl<T: Trace + Debug> Debug for Handle<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: Trace> Pointer for Handle<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Pointer::fmt(&core::ptr::addr_of!(**self), f)
+    }
+}
+
+/// A [`RootSet`] which can be nested into other scopes.
+///
+/// See the [module-level documentation][`mod@crate::roots`] for more details.
+pub struct HandleScope<'p> {
+    roots: RootSet,
+    parent: Option<&'p HandleScope<'p>>,
+}
+
+impl HandleScope<'static> {
+    /// Creates a new top-level [`HandleScope`].
+    #[inline]
+    pub const fn new() -> HandleScope<'static> {
+        HandleScope {
+            roots: RootSet::new(),
+            parent: None,
+        }
+    }
+}
+
+impl<'p> HandleScope<'p> {
+    /// Creates a new [`HandleScope`] nested into this one.
+    #[inline]
+    pub fn nested(&self) -> HandleScope<'_> {
+        HandleScope {
+            roots: RootSet::new(),
+            parent: Some(self),
+        }
+    }
+
+    /// Registers the allocation pointed by `cc` as a root of this scope, returning a [`Handle`] to it.
+    ///
+    /// See [`RootSet::root`] for more details.
+    #[inline]
+    pub fn handle<T: Trace + 'static>(&self, cc: Cc<T>) -> Handle<'_, T> {
+        self.roots.root(cc)
+    }
+
+    /// Registers the allocation pointed by `handle` as a root of the enclosing scope, returning a [`Handle`]
+    /// valid for the lifetime of the enclosing scope. Returns [`None`] if this scope is a top-level scope.
+    ///
+    /// If the allocation is registered in this scope, its most recent registration is moved into the enclosing scope
+    /// without modifying the reference count. Otherwise, the enclosing scope registers a new strong reference to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation isn't registered in this scope and the strong reference count exceeds the maximum supported.
+    #[inline]
+    #[track_caller]
+    pub fn escape<T: Trace + 'static>(&self, handle: Handle<'_, T>) -> Option<Handle<'p, T>> {
+        let parent = self.parent?;
+        let ptr: NonNull<CcBox<()>> = handle.ptr.cast();
+
+        let mut entries = self.roots.entries.borrow_mut();
+        let index = match entries.iter().rposition(|entry| entry.ptr == ptr) {
+            Some(index) => index,
+            None => {
+                drop(entries);
+                return Some(parent.handle(handle.to_cc()));
+            },
+        };
+
+        // Move the strong reference owned by the entry into the parent, so that the Handles
+        // to the allocation obtained from this scope remain valid until the parent is dropped
+        let entry = entries.remove(index);
+        drop(entries);
+        parent.roots.entries.borrow_mut().push(entry);
+        Some(Handle {
+            ptr: handle.ptr,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the [`RootSet`] containing the roots of this scope.
+    #[inline]
+    pub fn roots(&self) -> &RootSet {
+        &self.roots
+    }
+}
+
+impl Default for HandleScope<'static> {
+    #[inline]
+    fn default() -> Self {
+        HandleScope::new()
+    }
+}
+
+impl Deref for HandleScope<'_> {
+    type Target = RootSet;
+
+    #[inline]
+    fn deref(&self) -> &RootSet {
+        &self.roots
+    }
+}
+
+impl Debug for HandleScope<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandleScope")
+            .field("len", &self.roots.len())
+            .field("nested", &self.parent.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,333 @@
+//! Serialization and deserialization of [`Cc`]s using [serde](https://serde.rs).
+//!
+//! By default, [`Cc`] is serialized like the value it contains and is deserialized by allocating a new [`Cc`],
+//! like serde does for [`Rc`][`alloc::rc::Rc`]. Thus, shared [`Cc`]s are duplicated and serializing a cyclic
+//! structure never terminates.
+//!
+//! The [`serialize_graph`] function (or the [`SerializeGraph`] wrapper) serializes a value in *graph mode*,
+//! where every [`Cc`] is given an id. The first time a [`Cc`] is serialized, its value is serialized together with
+//! its id as `{"$id": id, "value": ...}`. Later occurrences are serialized as `{"$ref": id}`.
+//! Ids are assigned incrementally starting from 0, in the order the [`Cc`]s are first serialized.
+//!
+//! The [`deserialize_graph`] function deserializes data serialized in graph mode, recreating shared [`Cc`]s and cycles.
+//! Since a [`Cc`] is deserialized differently depending on the serialized data, graph mode deserialization
+//! requires a self-describing format (like JSON).
+//!
+//! # Example
+#![cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#![cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#![doc = r##"# use std::cell::RefCell;
+# use rust_cc::*;
+# use rust_cc::serialization::*;
+# use serde::{Deserialize, Serialize};
+#[derive(Trace, Finalize, Serialize, Deserialize)]
+struct Node {
+    value: u32,
+    next: RefCell<Option<Cc<Node>>>,
+}
+
+let node = Cc::new(Node { value: 1, next: RefCell::new(None) });
+*node.next.borrow_mut() = Some(node.clone());
+
+let json = serde_json::to_string(&SerializeGraph(&node)).unwrap();
+assert_eq!(r#"{"$id":0,"value":{"value":1,"next":{"$ref":0}}}"#, json);
+
+// SAFETY: the Deserialize implementations of Node (and its fields) don't access the deserialized Ccs
+let copy: Cc<Node> = unsafe { deserialize_graph(&mut serde_json::Deserializer::from_str(&json)) }.unwrap();
+assert_eq!(1, copy.value);
+assert!(Cc::ptr_eq(&copy, copy.next.borrow().as_ref().unwrap()));
+# node.next.borrow_mut().take();
+# copy.next.borrow_mut().take();
+```"##]
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::cell::RefCell;
+use core::fmt::{self, Formatter};
+use core::marker::PhantomData;
+use core::ptr;
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, Unexpected, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::pending::PendingCc;
+use crate::utils::rust_cc_thread_local;
+use crate::{Cc, Trace};
+
+rust_cc_thread_local! {
+    // The ids of the already serialized Ccs, or None if not serializing in graph mode
+    static SERIALIZING: RefCell<Option<BTreeMap<usize, u64>>> = const { RefCell::new(None) };
+
+    // The already deserialized Ccs, or None if not deserializing in graph mode
+    static DESERIALIZING: RefCell<Option<BTreeMap<u64, Box<dyn Any>>>> = const { RefCell::new(None) };
+}
+
+const STRUCT_NAME: &str = "Cc";
+const ID: &str = "$id";
+const REF: &str = "$ref";
+const VALUE: &str = "value";
+const FIELDS: &[&str] = &[ID, REF, VALUE];
+
+/// Serializes `value` in graph mode.
+///
+/// Can be used with the `#[serde(serialize_with = "...")]` attribute.
+/// See the [module-level documentation][`mod@crate::serialization`] for more details.
+pub fn serialize_graph<T: ?Sized + Serialize, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    struct ResetGuard;
+
+    impl Drop for ResetGuard {
+        #[inline]
+        fn drop(&mut self) {
+            let _ = SERIALIZING.try_with(|serializing| *serializing.borrow_mut() = None);
+        }
+    }
+
+    // Nested calls just reuse the ids of the outermost one
+    let activated = SERIALIZING.try_with(|serializing| {
+        let mut serializing = serializing.borrow_mut();
+        if serializing.is_none() {
+            *serializing = Some(BTreeMap::new());
+            true
+        } else {
+            false
+        }
+    }).unwrap_or(false);
+
+    if activated {
+        let _guard = ResetGuard;
+        value.serialize(serializer)
+    } else {
+        value.serialize(serializer)
+    }
+}
+
+/// A wrapper which serializes the wrapped value in graph mode.
+///
+/// See the [module-level documentation][`mod@crate::serialization`] for more details.
+#[derive(Copy, Clone, Debug)]
+pub struct SerializeGraph<'a, T: ?Sized>(pub &'a T);
+
+impl<T: ?Sized + Serialize> Serialize for SerializeGraph<'_, T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_graph(self.0, serializer)
+    }
+}
+
+/// Deserializes a value serialized in graph mode, recreating shared [`Cc`]s and cycles.
+///
+/// If an error is returned, the [`Cc`]s whose value was still being deserialized are leaked.
+///
+/// See the [module-level documentation][`mod@crate::serialization`] for more details.
+///
+/// # Safety
+///
+/// To recreate cycles, the [`Cc`]s deserialized while their value is still being deserialized point to allocations
+/// which are not yet initialized. Thus, the [`Deserialize`] implementations called during deserialization **must not**
+/// access the value pointed by any deserialized [`Cc`] (for example, by dereferencing it), they can only store it.
+///
+/// This excludes every collection which hashes or compares its elements on insertion, like [`HashSet`], [`BTreeSet`],
+/// [`BinaryHeap`] and the keys of [`HashMap`] and [`BTreeMap`], whenever their elements (or keys) contain a deserialized [`Cc`],
+/// directly or indirectly (for example, a `BTreeSet<Cc<T>>` or a `HashMap<K, V>` where `K` contains a [`Cc`]).
+/// The values of maps are not hashed nor compared, so they may contain deserialized [`Cc`]s.
+///
+/// The implementations generated by serde's derive macro (without attributes like `deserialize_with`, `from`
+/// or `try_from`) satisfy this requirement if the [`Deserialize`] implementations of the types of every field do.
+///
+/// [`HashSet`]: std::collections::HashSet
+/// [`HashMap`]: std::collections::HashMap
+/// [`BTreeSet`]: alloc::collections::BTreeSet
+/// [`BTreeMap`]: alloc::collections::BTreeMap
+/// [`BinaryHeap`]: alloc::collections::BinaryHeap
+pub unsafe fn deserialize_graph<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    struct ResetGuard;
+
+    impl Drop for ResetGuard {
+        #[inline]
+        fn drop(&mut self) {
+            // Take the map before dropping it, since dropping a Cc may deserialize again
+            let _ = DESERIALIZING.try_with(|deserializing| deserializing.borrow_mut().take());
+        }
+    }
+
+    // Nested calls just reuse the ids of the outermost one
+    let activated = DESERIALIZING.try_with(|deserializing| {
+        let mut deserializing = deserializing.borrow_mut();
+        if deserializing.is_none() {
+            *deserializing = Some(BTreeMap::new());
+            true
+        } else {
+            false
+        }
+    }).unwrap_or(false);
+
+    if activated {
+        let _guard = ResetGuard;
+        T::deserialize(deserializer)
+    } else {
+        T::deserialize(deserializer)
+    }
+}
+
+enum Occurrence {
+    Plain,
+    First(u64),
+    Later(u64),
+}
+
+impl<T: ?Sized + Trace + Serialize> Serialize for Cc<T> {
+    /// Serializes the contained value, or the [`Cc`] with its id if serializing in graph mode.
+    ///
+    /// See the [module-level documentation][`mod@crate::serialization`] for more details.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let addr = ptr::addr_of!(**self).cast::<()>() as usize;
+        let occurrence = SERIALIZING.try_with(|serializing| match &mut *serializing.borrow_mut() {
+            Some(ids) => {
+                let next_id = ids.len() as u64;
+                match ids.get(&addr) {
+                    Some(&id) => Occurrence::Later(id),
+                    None => {
+                        // Insert before serializing the value to correctly handle cycles
+                        ids.insert(addr, next_id);
+                        Occurrence::First(next_id)
+                    },
+                }
+            },
+            None => Occurrence::Plain,
+        }).unwrap_or(Occurrence::Plain);
+
+        match occurrence {
+            Occurrence::Plain => (**self).serialize(serializer),
+            Occurrence::First(id) => {
+                let mut state = serializer.serialize_struct(STRUCT_NAME, 2)?;
+                state.serialize_field(ID, &id)?;
+                state.serialize_field(VALUE, &**self)?;
+                state.end()
+            },
+            Occurrence::Later(id) => {
+                let mut state = serializer.serialize_struct(STRUCT_NAME, 1)?;
+                state.serialize_field(REF, &id)?;
+                state.end()
+            },
+        }
+    }
+}
+
+impl<'de, T: Trace + Deserialize<'de>> Deserialize<'de> for Cc<T> {
+    /// Deserializes a new [`Cc`], or recreates the serialized [`Cc`] if deserializing in graph mode.
+    ///
+    /// See the [module-level documentation][`mod@crate::serialization`] for more details.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let graph_mode = DESERIALIZING
+            .try_with(|deserializing| deserializing.borrow().is_some())
+            .unwrap_or(false);
+
+        if graph_mode {
+            deserializer.deserialize_struct(STRUCT_NAME, FIELDS, CcVisitor(PhantomData))
+        } else {
+            T::deserialize(deserializer).map(Cc::new)
+        }
+    }
+}
+
+enum Field {
+    Id,
+    Ref,
+    Value,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                write!(f, "`{ID}`, `{REF}` or `{VALUE}`")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Field, E> {
+                match value {
+                    ID => Ok(Field::Id),
+                    REF => Ok(Field::Ref),
+                    VALUE => Ok(Field::Value),
+                    _ => Err(E::unknown_field(value, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+struct CcVisitor<T: 'static>(PhantomData<T>);
+
+impl<'de, T: Trace + Deserialize<'de> + 'static> Visitor<'de> for CcVisitor<T> {
+    type Value = Cc<T>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "a map with either the `{ID}` and `{VALUE}` keys or the `{REF}` key")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Cc<T>, A::Error> {
+        let cc = match map.next_key::<Field>()? {
+            Some(Field::Ref) => {
+                let id: u64 = map.next_value()?;
+                let cc = DESERIALIZING.try_with(|deserializing| {
+                    deserializing
+                        .borrow()
+                        .as_ref()
+                        .and_then(|ccs| ccs.get(&id))
+                        .and_then(|cc| cc.downcast_ref::<PendingCc<T>>())
+                        .map(PendingCc::to_cc)
+                }).ok().flatten();
+
+                cc.ok_or_else(|| de::Error::invalid_value(Unexpected::Unsigned(id), &"the id of a previously deserialized Cc"))?
+            },
+            Some(Field::Id) => {
+                let id: u64 = map.next_value()?;
+                match map.next_key::<Field>()? {
+                    Some(Field::Value) => {},
+                    _ => return Err(de::Error::missing_field(VALUE)),
+                }
+
+                let unused = DESERIALIZING.try_with(|deserializing| {
+                    deserializing.borrow().as_ref().is_some_and(|ccs| !ccs.contains_key(&id))
+                }).unwrap_or(false);
+
+                if !unused {
+                    return Err(de::Error::invalid_value(Unexpected::Unsigned(id), &"an unused id"));
+                }
+
+                // Insert before deserializing the value to correctly handle cycles
+                let pending = PendingCc::<T>::new();
+                let _ = DESERIALIZING.try_with(|deserializing| {
+                    if let Some(ccs) = &mut *deserializing.borrow_mut() {
+                        ccs.insert(id, Box::new(pending.clone()));
+                    }
+                });
+
+                let value: T = map.next_value()?;
+
+                // SAFETY: the caller of deserialize_graph guarantees that the deserialized Ccs are not accessed
+                unsafe {
+                    pending.init(value);
+                }
+                pending.to_cc()
+            },
+            _ => return Err(de::Error::missing_field(ID)),
+        };
+
+        if map.next_key::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom("unexpected key after the Cc"));
+        }
+        Ok(cc)
+    }
+}
@@ -4,7 +4,11 @@
 use alloc::rc::Rc;
 use core::cell::Cell;
 use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use core::time::Duration;
+use core::fmt::{self, Debug, Formatter};
 use thiserror::Error;
+use crate::trace::{Context, Finalize, Trace};
 use crate::utils;
 
 utils::rust_cc_thread_local! {
@@ -41,7 +45,13 @@ pub(crate) fn reset_state() {
 
         state.dropping.set(false);
         state.allocated_bytes.set(0);
+        state.external_bytes.set(0);
+        state.allocations_counter.set(0);
         state.executions_counter.set(0);
+        state.last_collection.set(None);
+
+        #[cfg(feature = "finalization")]
+        state.resurrection_detection.set(crate::resurrection::DetectionMode::Disabled);
     });
 }
 
@@ -53,7 +63,13 @@ pub(crate) struct State {
 
     dropping: Cell<bool>,
     allocated_bytes: Cell<usize>,
+    external_bytes: Cell<usize>,
+    allocations_counter: Cell<usize>,
     executions_counter: Cell<usize>,
+    last_collection: Cell<Option<CollectionReport>>,
+
+    #[cfg(feature = "finalization")]
+    resurrection_detection: Cell<crate::resurrection::DetectionMode>,
 
     _phantom: PhantomData<Rc<()>>, // Make State !Send and !Sync
 }
@@ -69,7 +85,13 @@ const fn new() -> Self {
 
             dropping: Cell::new(false),
             allocated_bytes: Cell::new(0),
+            external_bytes: Cell::new(0),
+            allocations_counter: Cell::new(0),
             executions_counter: Cell::new(0),
+            last_collection: Cell::new(None),
+
+            #[cfg(feature = "finalization")]
+            resurrection_detection: Cell::new(crate::resurrection::DetectionMode::Disabled),
 
             _phantom: PhantomData,
         }
@@ -83,6 +105,7 @@ pub(crate) fn allocated_bytes(&self) -> usize {
     #[inline]
     pub(crate) fn record_allocation(&self, layout: Layout) {
         self.allocated_bytes.set(self.allocated_bytes.get() + layout.size());
+        self.allocations_counter.set(self.allocations_counter.get().wrapping_add(1));
     }
 
     #[inline]
@@ -90,6 +113,21 @@ pub(crate) fn record_deallocation(&self, layout: Layout) {
         self.allocated_bytes.set(self.allocated_bytes.get() - layout.size());
     }
 
+    #[inline]
+    pub(crate) fn external_bytes(&self) -> usize {
+        self.external_bytes.get()
+    }
+
+    #[inline]
+    fn add_external_bytes(&self, bytes: usize) {
+        self.external_bytes.set(self.external_bytes.get().saturating_add(bytes));
+    }
+
+    #[inline]
+    fn remove_external_bytes(&self, bytes: usize) {
+        self.external_bytes.set(self.external_bytes.get().saturating_sub(bytes));
+    }
+
     #[inline]
     pub(crate) fn executions_count(&self) -> usize {
         self.executions_counter.get()
@@ -100,6 +138,28 @@ pub(super) fn increment_executions_count(&self) {
         self.executions_counter.set(self.executions_counter.get() + 1);
     }
 
+    /// Returns the number of allocations done since the start of the last collection.
+    #[inline]
+    #[cfg_attr(not(feature = "auto-collect"), allow(dead_code))] // Currently used only by collection policies
+    pub(crate) fn allocations_count(&self) -> usize {
+        self.allocations_counter.get()
+    }
+
+    #[inline]
+    pub(super) fn reset_allocations_count(&self) {
+        self.allocations_counter.set(0);
+    }
+
+    #[inline]
+    pub(crate) fn last_collection_report(&self) -> Option<CollectionReport> {
+        self.last_collection.get()
+    }
+
+    #[inline]
+    pub(super) fn set_last_collection_report(&self, report: CollectionReport) {
+        self.last_collection.set(Some(report));
+    }
+
     #[inline]
     pub(crate) fn is_collecting(&self) -> bool {
         self.collecting.get()
@@ -122,6 +182,18 @@ pub(crate) fn set_finalizing(&self, value: bool) {
         self.finalizing.set(value);
     }
 
+    #[cfg(feature = "finalization")]
+    #[inline]
+    pub(crate) fn resurrection_detection(&self) -> crate::resurrection::DetectionMode {
+        self.resurrection_detection.get()
+    }
+
+    #[cfg(feature = "finalization")]
+    #[inline]
+    pub(crate) fn set_resurrection_detection(&self, mode: crate::resurrection::DetectionMode) {
+        self.resurrection_detection.set(mode);
+    }
+
     #[inline]
     pub(crate) fn is_dropping(&self) -> bool {
         self.dropping.get()
@@ -164,18 +236,212 @@ pub fn buffered_objects_count() -> Result<usize, StateAccessError> {
     crate::POSSIBLE_CYCLES.try_with(|pc| Ok(pc.size())).unwrap_or(Err(StateAccessError::AccessError))
 }
 
+/// Returns the number of [frozen][`crate::freeze`] objects.
+#[inline]
+pub fn frozen_objects_count() -> Result<usize, StateAccessError> {
+    // Like buffered_objects_count, the count is kept inside FROZEN
+    crate::FROZEN.try_with(|frozen| Ok(frozen.size())).unwrap_or(Err(StateAccessError::AccessError))
+}
+
+/// Returns the number of bytes allocated by the garbage collector for [frozen][`crate::freeze`] objects.
+///
+/// The returned value is computed by visiting every frozen object.
+#[inline]
+pub fn frozen_bytes() -> Result<usize, StateAccessError> {
+    crate::FROZEN.try_with(|frozen| {
+        // SAFETY: frozen objects are valid to access
+        Ok(frozen.iter().map(|ptr| unsafe { ptr.as_ref() }.layout().size()).sum())
+    }).unwrap_or(Err(StateAccessError::AccessError))
+}
+
 /// Returns the number of allocated bytes managed by the garbage collector.
 #[inline]
 pub fn allocated_bytes() -> Result<usize, StateAccessError> {
     try_state(|state| Ok(state.allocated_bytes()))?
 }
 
+/// Returns the number of bytes of external memory reported using [`add_external_memory`].
+#[inline]
+pub fn external_bytes() -> Result<usize, StateAccessError> {
+    try_state(|state| Ok(state.external_bytes()))?
+}
+
+/// Reports to the garbage collector that `bytes` bytes of memory not allocated by it are being kept alive by [`Cc`][`crate::Cc`]s.
+///
+/// Objects managed by the collector may own memory allocated elsewhere (for example, the buffer of a [`Vec`]),
+/// which isn't counted by [`allocated_bytes`]. Reporting it allows the
+/// [collection policies][`crate::config::CollectionPolicy`] to take it into account when deciding whether to start a collection.
+///
+/// The reported memory should be removed using [`remove_external_memory`] when it is freed.
+/// Consider using an [`ExternalMemory`] instead, which automatically removes the reported memory when dropped.
+///
+/// [`Vec`]: `alloc::vec::Vec`
+#[inline]
+pub fn add_external_memory(bytes: usize) -> Result<(), StateAccessError> {
+    try_state(|state| state.add_external_bytes(bytes))
+}
+
+/// Reports to the garbage collector that `bytes` bytes of memory previously reported using [`add_external_memory`] have been freed.
+#[inline]
+pub fn remove_external_memory(bytes: usize) -> Result<(), StateAccessError> {
+    try_state(|state| state.remove_external_bytes(bytes))
+}
+
+/// An amount of external memory reported to the garbage collector, which is removed when dropped.
+///
+/// See [`add_external_memory`] for more details.
+///
+/// # Example
+#[cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#[cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#[doc = r"# use rust_cc::*;
+# use rust_cc::state::ExternalMemory;
+#[derive(Trace, Finalize)]
+struct Buffer {
+    data: Vec<u8>,
+    external: ExternalMemory,
+}
+
+let data = vec![0u8; 1024 * 1024];
+let buffer = Cc::new(Buffer {
+    external: ExternalMemory::new(data.capacity()),
+    data,
+});
+```"]
+#[must_use = "the reported memory is removed immediately if the ExternalMemory is dropped"]
+pub struct ExternalMemory {
+    bytes: usize,
+    _phantom: PhantomData<Rc<()>>, // Make ExternalMemory !Send and !Sync, since the memory is reported to the current thread
+}
+
+impl ExternalMemory {
+    /// Reports `bytes` bytes of external memory to the garbage collector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state of the garbage collector cannot be accessed.
+    #[inline]
+    #[track_caller]
+    pub fn new(bytes: usize) -> ExternalMemory {
+        state(|state| state.add_external_bytes(bytes));
+        ExternalMemory {
+            bytes,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of reported bytes.
+    #[inline]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Changes the number of reported bytes.
+    #[inline]
+    pub fn resize(&mut self, bytes: usize) {
+        let _ = try_state(|state| {
+            state.remove_external_bytes(self.bytes);
+            state.add_external_bytes(bytes);
+        });
+        self.bytes = bytes;
+    }
+}
+
+impl Drop for ExternalMemory {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = try_state(|state| state.remove_external_bytes(self.bytes));
+    }
+}
+
+impl Debug for ExternalMemory {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalMemory").field("bytes", &self.bytes).finish()
+    }
+}
+
+unsafe impl Trace for ExternalMemory {
+    #[inline(always)]
+    fn trace(&self, _: &mut Context<'_>) {
+    }
+}
+
+impl Finalize for ExternalMemory {
+}
+
 /// Returns the total number of executed collections.
 #[inline]
 pub fn executions_count() -> Result<usize, StateAccessError> {
     try_state(|state| Ok(state.executions_count()))?
 }
 
+/// Returns the [`CollectionReport`] of the last executed collection, or [`None`] if no collection has been executed yet.
+#[inline]
+pub fn last_collection_report() -> Result<Option<CollectionReport>, StateAccessError> {
+    try_state(|state| Ok(state.last_collection_report()))?
+}
+
+/// Information about an executed collection.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CollectionReport {
+    /// The number of bytes allocated by the collector before the collection.
+    pub allocated_bytes_before: usize,
+    /// The number of bytes allocated by the collector after the collection.
+    pub allocated_bytes_after: usize,
+    /// The number of objects buffered to be processed (see [`Cc::mark_alive`][`crate::Cc::mark_alive`]) before the collection.
+    pub buffered_objects_before: usize,
+    /// The number of objects traced by the collection.
+    pub traced_objects: usize,
+    /// The number of objects deallocated by the collection.
+    pub deallocated_objects: usize,
+    /// The number of [`CcRefCell`][`crate::cell::CcRefCell`]s which weren't traced because they were mutably borrowed,
+    /// together with the number of `Mutex`es and `RwLock`s which weren't traced because they were locked.
+    ///
+    /// The objects inside such values are treated as alive, so a non-zero value may explain why some garbage hasn't been collected.
+    pub skipped_borrowed: usize,
+    /// The number of times the collection algorithm has been executed during the collection.
+    ///
+    /// When finalization is enabled, the algorithm is executed again after running finalizers, to check for resurrected objects
+    /// and to collect the garbage created by finalizers. The maximum number of rounds is set by
+    /// [`finalization_round_limit`][`crate::config::Config::finalization_round_limit`].
+    pub rounds: usize,
+    /// `true` if the collection stopped because the [round limit][`crate::config::Config::finalization_round_limit`] has been
+    /// reached while there were still objects to process, `false` otherwise.
+    ///
+    /// The remaining objects are left buffered for the next collection. Reaching the limit usually means that a finalizer
+    /// keeps creating new garbage.
+    pub round_limit_reached: bool,
+    /// The duration of the collection.
+    #[cfg(feature = "std")]
+    pub duration: Duration,
+}
+
+/// An error returned by [`try_collect_cycles`][`crate::try_collect_cycles`].
+#[non_exhaustive]
+#[derive(Error, Debug)]
+pub enum CollectError {
+    /// A collection is already running, so a new one hasn't been started.
+    #[error("a collection is already running")]
+    AlreadyCollecting,
+    /// The garbage collector state couldn't be accessed.
+    #[error("couldn't access the state")]
+    AccessError,
+    /// A [`Trace`], [`Finalize`] or [`Drop`] implementation panicked during the collection and the panic has been caught
+    /// according to the [panic policy][`crate::config::PanicPolicy`].
+    ///
+    /// The objects which were being processed by the collection may have been leaked.
+    #[error("a panic occurred during the collection")]
+    Panicked,
+}
+
 /// Returns `true` if the garbage collector is in a tracing phase, `false` otherwise.
 ///
 /// See [`Trace`][`trait@crate::Trace`] for more details.
@@ -0,0 +1,188 @@
+//! Lists of the types of the standard library supported by the traits of this crate.
+//!
+//! Every macro takes the name of another macro and invokes it with a list of types. The implementations of
+//! [`Trace`][`crate::Trace`], [`HeapSize`][`crate::heap_size::HeapSize`], [`DeepClone`][`crate::deep_clone::DeepClone`]
+//! and of the traits in [`graph`][`crate::graph`] are generated from these lists, so that they cover the same types.
+//!
+//! Types are listed by their full path, since item paths are resolved at the invocation site.
+//! Generic types use `T` as their only type parameter.
+
+/// Sized types which don't own any memory, implementing [`Copy`], [`Debug`][`core::fmt::Debug`], [`Eq`] and [`Hash`][`core::hash::Hash`].
+macro_rules! plain_types {
+    ($mac:ident) => {
+        $mac! {
+            (),
+            bool,
+            isize,
+            usize,
+            i8,
+            u8,
+            i16,
+            u16,
+            i32,
+            u32,
+            i64,
+            u64,
+            i128,
+            u128,
+            char,
+            ::core::num::NonZeroIsize,
+            ::core::num::NonZeroUsize,
+            ::core::num::NonZeroI8,
+            ::core::num::NonZeroU8,
+            ::core::num::NonZeroI16,
+            ::core::num::NonZeroU16,
+            ::core::num::NonZeroI32,
+            ::core::num::NonZeroU32,
+            ::core::num::NonZeroI64,
+            ::core::num::NonZeroU64,
+            ::core::num::NonZeroI128,
+            ::core::num::NonZeroU128,
+            ::core::time::Duration,
+            ::core::cmp::Ordering,
+            ::core::sync::atomic::Ordering,
+            ::core::net::IpAddr,
+            ::core::net::Ipv4Addr,
+            ::core::net::Ipv6Addr,
+            ::core::net::SocketAddr,
+            ::core::net::SocketAddrV4,
+            ::core::net::SocketAddrV6,
+        }
+
+        #[cfg(feature = "std")]
+        $mac! {
+            ::std::time::Instant,
+            ::std::time::SystemTime,
+        }
+    };
+}
+
+/// Floating point types, which implement [`PartialEq`] but not [`Eq`] and [`Hash`][`core::hash::Hash`].
+macro_rules! float_types {
+    ($mac:ident) => {
+        $mac! {
+            f32,
+            f64,
+        }
+    };
+}
+
+/// Atomic types, which implement neither [`Clone`] nor [`PartialEq`].
+macro_rules! atomic_types {
+    ($mac:ident) => {
+        $mac! {
+            ::core::sync::atomic::AtomicBool,
+            ::core::sync::atomic::AtomicIsize,
+            ::core::sync::atomic::AtomicUsize,
+            ::core::sync::atomic::AtomicI8,
+            ::core::sync::atomic::AtomicU8,
+            ::core::sync::atomic::AtomicI16,
+            ::core::sync::atomic::AtomicU16,
+            ::core::sync::atomic::AtomicI32,
+            ::core::sync::atomic::AtomicU32,
+            ::core::sync::atomic::AtomicI64,
+            ::core::sync::atomic::AtomicU64,
+        }
+    };
+}
+
+/// Unsized string types.
+macro_rules! unsized_string_types {
+    ($mac:ident) => {
+        $mac! {
+            str,
+            ::core::ffi::CStr,
+        }
+
+        #[cfg(feature = "std")]
+        $mac! {
+            ::std::path::Path,
+            ::std::ffi::OsStr,
+        }
+    };
+}
+
+/// Owned string types, i.e. the owned counterparts of the types in `unsized_string_types`.
+macro_rules! owned_string_types {
+    ($mac:ident) => {
+        $mac! {
+            ::alloc::string::String,
+            ::alloc::ffi::CString,
+        }
+
+        #[cfg(feature = "std")]
+        $mac! {
+            ::std::path::PathBuf,
+            ::std::ffi::OsString,
+        }
+    };
+}
+
+/// Tuple structs wrapping a single `T`.
+macro_rules! newtype_types {
+    ($mac:ident) => {
+        $mac! {
+            ::core::cmp::Reverse<T>,
+            ::core::num::Wrapping<T>,
+            ::core::num::Saturating<T>,
+        }
+    };
+}
+
+/// Sequences of `T`s, which can be iterated by reference and collected from an iterator.
+macro_rules! sequence_types {
+    ($mac:ident) => {
+        $mac! {
+            ::alloc::vec::Vec<T>,
+            ::alloc::collections::VecDeque<T>,
+            ::alloc::collections::LinkedList<T>,
+        }
+    };
+}
+
+/// The parameters of the supported function pointers.
+macro_rules! fn_arities {
+    ($mac:ident) => {
+        $mac! {
+            ();
+            (A);
+            (A, B);
+            (A, B, C);
+            (A, B, C, D);
+            (A, B, C, D, E);
+            (A, B, C, D, E, F);
+            (A, B, C, D, E, F, G);
+            (A, B, C, D, E, F, G, H);
+            (A, B, C, D, E, F, G, H, I);
+            (A, B, C, D, E, F, G, H, I, J);
+            (A, B, C, D, E, F, G, H, I, J, K);
+            (A, B, C, D, E, F, G, H, I, J, K, L);
+        }
+    };
+}
+
+/// The elements of the supported tuples. Every type parameter is paired with a lowercase identifier,
+/// for the implementations which need to bind two tuples at once.
+macro_rules! tuple_arities {
+    ($mac:ident) => {
+        $mac! {
+            (A a);
+            (A a, B b);
+            (A a, B b, C c);
+            (A a, B b, C c, D d);
+            (A a, B b, C c, D d, E e);
+            (A a, B b, C c, D d, E e, F f);
+            (A a, B b, C c, D d, E e, F f, G g);
+            (A a, B b, C c, D d, E e, F f, G g, H h);
+            (A a, B b, C c, D d, E e, F f, G g, H h, I i);
+            (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j);
+            (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k);
+            (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
+        }
+    };
+}
+
+pub(crate) use {
+    atomic_types, float_types, fn_arities, newtype_types, owned_string_types, plain_types, sequence_types,
+    tuple_arities, unsized_string_types,
+};
@@ -561,3 +561,86 @@ fn finalize(&self) {
     
     FINALIZED.with(|fin| assert!(fin.get()));
 }
+
+#[test]
+fn test_last_collection_report() {
+    reset_state();
+
+    assert_eq!(None, state::last_collection_report().unwrap());
+
+    let cc1 = Cc::new(Circular {
+        cc: Cell::new(None),
+    });
+    let cc2 = Cc::new(Circular {
+        cc: Cell::new(None),
+    });
+    let (droppable, _checker) = Droppable::new(Circular { cc: Cell::new(None) });
+    cc1.cc.set(Some(Cc::new(droppable)));
+    cc1.cc.set(None);
+    drop(cc2.clone());
+
+    let allocated_bytes = state::allocated_bytes().unwrap();
+    collect_cycles();
+
+    let report = state::last_collection_report().unwrap().expect("Missing report");
+    assert_eq!(allocated_bytes, report.allocated_bytes_before);
+    assert_eq!(allocated_bytes, report.allocated_bytes_after);
+    assert_eq!(1, report.buffered_objects_before);
+    assert_eq!(1, report.traced_objects);
+    assert_eq!(0, report.deallocated_objects);
+
+    let (droppable1, _checker1) = Droppable::new(Circular { cc: Cell::new(None) });
+    let (droppable2, _checker2) = Droppable::new(Circular { cc: Cell::new(None) });
+    let circ1 = Cc::new(droppable1);
+    let circ2 = Cc::new(droppable2);
+    circ1.cc.set(Some(circ2.clone()));
+    circ2.cc.set(Some(circ1.clone()));
+    drop(circ1);
+    drop(circ2);
+
+    let allocated_bytes = state::allocated_bytes().unwrap();
+    collect_cycles();
+
+    let report = state::last_collection_report().unwrap().expect("Missing report");
+    assert_eq!(allocated_bytes, report.allocated_bytes_before);
+    assert_eq!(state::allocated_bytes().unwrap(), report.allocated_bytes_after);
+    assert!(report.allocated_bytes_after < report.allocated_bytes_before);
+    assert_eq!(2, report.buffered_objects_before);
+    // With finalization the objects are traced again after having been finalized
+    assert_eq!(if cfg!(feature = "finalization") { 4 } else { 2 }, report.traced_objects);
+    assert_eq!(2, report.deallocated_objects);
+
+    drop(cc1);
+    drop(cc2);
+    collect_cycles();
+    assert_empty();
+}
+
+#[test]
+fn test_external_memory() {
+    reset_state();
+
+    assert_eq!(0, state::external_bytes().unwrap());
+    state::add_external_memory(100).unwrap();
+    assert_eq!(100, state::external_bytes().unwrap());
+    state::remove_external_memory(40).unwrap();
+    assert_eq!(60, state::external_bytes().unwrap());
+    state::remove_external_memory(100).unwrap();
+    assert_eq!(0, state::external_bytes().unwrap());
+
+    let (droppable, checker) = Droppable::new(state::ExternalMemory::new(1024));
+    let cc = Cc::new(droppable);
+    assert_eq!(1024, state::external_bytes().unwrap());
+
+    let mut external = state::ExternalMemory::new(10);
+    assert_eq!(1034, state::external_bytes().unwrap());
+    external.resize(20);
+    assert_eq!(20, external.bytes());
+    assert_eq!(1044, state::external_bytes().unwrap());
+    drop(external);
+
+    drop(cc);
+    checker.assert_dropped();
+    assert_eq!(0, state::external_bytes().unwrap());
+    assert_empty();
+}
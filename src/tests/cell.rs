@@ -0,0 +1,204 @@
+use std::mem;
+
+use super::*;
+use crate::cell::{CcRefCell, CcSlot};
+use crate::collect_cycles;
+
+struct Node {
+    next: CcRefCell<Option<Cc<Droppable<Node>>>>,
+}
+
+unsafe impl Trace for Node {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.next.trace(ctx);
+    }
+}
+
+impl Finalize for Node {}
+
+fn cycle() -> (Cc<Droppable<Node>>, DropChecker) {
+    let (droppable, checker) = Droppable::new(Node {
+        next: CcRefCell::new(None),
+    });
+    let cc = Cc::new(droppable);
+    *cc.next.borrow_mut() = Some(cc.clone());
+    (cc, checker)
+}
+
+#[test]
+fn test_api() {
+    let cell = CcRefCell::new(1);
+    assert_eq!(1, *cell.borrow());
+    *cell.borrow_mut() += 1;
+    assert_eq!(2, cell.replace(3));
+    assert_eq!(3, cell.replace_with(|&mut old| old + 1));
+
+    let other = CcRefCell::new(5);
+    cell.swap(&other);
+    assert_eq!(5, *cell.borrow());
+    assert_eq!(4, other.take());
+    assert_eq!(0, *other.borrow());
+
+    {
+        let _borrow = cell.borrow();
+        assert!(cell.try_borrow().is_ok());
+        assert!(cell.try_borrow_mut().is_err());
+    }
+    {
+        let _borrow = cell.borrow_mut();
+        assert!(cell.try_borrow().is_err());
+        assert_eq!("CcRefCell { value: <borrowed> }", format!("{cell:?}"));
+    }
+
+    assert_eq!("CcRefCell { value: 5 }", format!("{cell:?}"));
+    assert_eq!(CcRefCell::new(5), cell.clone());
+    assert_eq!(5, cell.into_inner());
+}
+
+#[test]
+fn test_traced_while_borrowed() {
+    reset_state();
+
+    let (cc, checker) = cycle();
+
+    // Keep the cell borrowed forever
+    mem::forget(cc.next.borrow());
+    drop(cc);
+    collect_cycles();
+
+    checker.assert_finalized();
+    checker.assert_dropped();
+
+    let report = state::last_collection_report().unwrap().expect("Missing report");
+    assert_eq!(0, report.skipped_borrowed);
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_skipped_while_mutably_borrowed() {
+    reset_state();
+
+    let (cc, checker) = cycle();
+
+    // Keep the cell mutably borrowed forever
+    mem::forget(cc.next.borrow_mut());
+    drop(cc);
+    collect_cycles();
+
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+
+    let report = state::last_collection_report().unwrap().expect("Missing report");
+    assert_eq!(1, report.skipped_borrowed);
+    assert_eq!(0, report.deallocated_objects);
+}
+
+#[test]
+fn test_collect_while_borrowed() {
+    reset_state();
+
+    let (alive, alive_checker) = cycle();
+    let (garbage, garbage_checker) = cycle();
+
+    {
+        let _borrow = alive.next.borrow();
+        drop(alive.clone()); // Make sure alive is processed by the collection
+        drop(garbage);
+        collect_cycles();
+    }
+
+    alive_checker.assert_not_finalized();
+    alive_checker.assert_not_dropped();
+    garbage_checker.assert_finalized();
+    garbage_checker.assert_dropped();
+
+    let report = state::last_collection_report().unwrap().expect("Missing report");
+    assert_eq!(0, report.skipped_borrowed);
+    assert_eq!(1, report.deallocated_objects);
+
+    *alive.next.borrow_mut() = None;
+    drop(alive);
+    alive_checker.assert_dropped();
+}
+
+struct SlotNode {
+    next: CcSlot<Droppable<SlotNode>>,
+}
+
+unsafe impl Trace for SlotNode {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.next.trace(ctx);
+    }
+}
+
+impl Finalize for SlotNode {}
+
+fn slot_node() -> (Cc<Droppable<SlotNode>>, DropChecker) {
+    let (droppable, checker) = Droppable::new(SlotNode {
+        next: CcSlot::empty(),
+    });
+    (Cc::new(droppable), checker)
+}
+
+#[test]
+fn test_slot_api() {
+    reset_state();
+
+    let (cc, checker) = slot_node();
+    let slot = CcSlot::empty();
+    assert!(slot.is_none());
+    assert!(slot.get().is_none());
+
+    slot.set(Some(cc.clone()));
+    assert!(slot.is_some());
+    assert!(slot.get().is_some_and(|got| Cc::ptr_eq(&got, &cc)));
+    assert_eq!("CcSlot { is_some: true }", format!("{slot:?}"));
+
+    let cloned = slot.clone();
+    assert!(cloned.take().is_some_and(|taken| Cc::ptr_eq(&taken, &cc)));
+    assert!(cloned.is_none());
+
+    let (other, other_checker) = slot_node();
+    assert!(slot.replace(Some(other)).is_some_and(|old| Cc::ptr_eq(&old, &cc)));
+
+    // The replaced value is dropped
+    slot.set(None);
+    other_checker.assert_dropped();
+
+    drop(cc);
+    checker.assert_dropped();
+    assert!(slot.into_inner().is_none());
+}
+
+#[test]
+fn test_slot_cycle() {
+    reset_state();
+
+    let (first, first_checker) = slot_node();
+    let (second, second_checker) = slot_node();
+    first.next.set(Some(second.clone()));
+    second.next.set(Some(first.clone()));
+    drop(first);
+    drop(second);
+
+    collect_cycles();
+
+    first_checker.assert_finalized();
+    first_checker.assert_dropped();
+    second_checker.assert_finalized();
+    second_checker.assert_dropped();
+}
+
+#[test]
+fn test_slot_self_cycle_broken() {
+    reset_state();
+
+    let (cc, checker) = slot_node();
+    cc.next.set(Some(cc.clone()));
+
+    // Breaking the cycle by emptying the slot deallocates the object without a collection
+    cc.next.set(None);
+    drop(cc);
+
+    checker.assert_dropped();
+}
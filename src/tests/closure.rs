@@ -0,0 +1,60 @@
+use super::*;
+use crate::closure::TracedFn;
+use crate::{cc_closure, collect_cycles};
+
+struct Widget {
+    on_click: RefCell<Option<TracedFn<(u32, u32), u32>>>,
+}
+
+unsafe impl Trace for Widget {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.on_click.trace(ctx);
+    }
+}
+
+impl Finalize for Widget {}
+
+#[test]
+fn test_call() {
+    reset_state();
+
+    let constant: TracedFn<(), i32> = cc_closure!(|| 42, captures = []);
+    assert_eq!(42, constant.call(()));
+
+    let base = Cc::new(10u32);
+    let other = Cc::new(5u32);
+    let sum = cc_closure!(move |a: u32, b: u32| **base + **other + a + b, captures = [base, other,]);
+    assert_eq!(18, sum.call((1, 2)));
+    assert_eq!("TracedFn { .. }", format!("{sum:?}"));
+
+    let explicit = TracedFn::new(Cc::new(String::from("abc")), |captured, (n,): (usize,)| captured.len() * n);
+    assert_eq!(6, explicit.call((2,)));
+}
+
+#[test]
+fn test_cycle_through_closure() {
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(Widget {
+        on_click: RefCell::new(None),
+    });
+    let widget = Cc::new(droppable);
+
+    let this = widget.clone();
+    let callback = cc_closure!(move |a, b| {
+        assert!(this.on_click.try_borrow_mut().is_err());
+        a + b
+    }, captures = [this]);
+    *widget.on_click.borrow_mut() = Some(callback);
+
+    assert_eq!(3, widget.on_click.borrow().as_ref().unwrap().call((1, 2)));
+
+    drop(widget);
+    checker.assert_not_dropped();
+
+    collect_cycles();
+
+    checker.assert_finalized();
+    checker.assert_dropped();
+}
+
@@ -2,6 +2,7 @@
 
 fn assert_not_marked(counter: &CounterMarker) {
     assert!(counter.is_not_marked());
+    assert!(counter.is_non_marked());
     assert!(!counter.is_in_possible_cycles());
     assert!(!counter.is_in_list());
     assert!(!counter._is_in_queue());
@@ -130,6 +131,25 @@ fn test(already_fin: bool) {
     test(false);
 }
 
+#[test]
+fn test_max() {
+    // Both counters use 14 bits and the value with every bit set to 1 is reserved
+    assert_eq!(MAX, (1u16 << 14) - 2);
+
+    // Don't run this under MIRI, see test_increment_decrement
+    #[cfg(not(miri))]
+    {
+        let counter = CounterMarker::new_with_counter_to_one(false);
+        while counter.counter() < MAX {
+            assert!(counter.increment_counter().is_ok());
+        }
+        assert!(counter.increment_counter().is_err());
+        assert_eq!(counter.counter(), MAX);
+        assert_not_marked(&counter);
+        assert_default_settings(&counter);
+    }
+}
+
 #[test]
 fn test_increment_decrement() {
     fn test(counter: CounterMarker) {
@@ -223,6 +243,7 @@ fn test(counter: CounterMarker) {
         counter.mark(Mark::PossibleCycles);
 
         assert!(counter.is_not_marked());
+        assert!(!counter.is_non_marked());
         assert!(counter.is_in_possible_cycles());
         assert!(!counter.is_in_list());
         assert!(!counter._is_in_queue());
@@ -232,6 +253,7 @@ fn test(counter: CounterMarker) {
         counter.mark(Mark::InList);
 
         assert!(!counter.is_not_marked());
+        assert!(!counter.is_non_marked());
         assert!(!counter.is_in_possible_cycles());
         assert!(counter.is_in_list());
         assert!(!counter._is_in_queue());
@@ -241,6 +263,7 @@ fn test(counter: CounterMarker) {
         counter.mark(Mark::InQueue);
 
         assert!(!counter.is_not_marked());
+        assert!(!counter.is_non_marked());
         assert!(!counter.is_in_possible_cycles());
         assert!(!counter.is_in_list());
         assert!(counter._is_in_queue());
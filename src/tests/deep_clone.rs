@@ -0,0 +1,234 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::*;
+use crate::collect_cycles;
+use crate::deep_clone::*;
+
+unsafe impl<T: DeepClone> DeepClone for GraphNode<T> {
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        GraphNode {
+            value: self.value.deep_clone(ctx),
+            next: self.next.deep_clone(ctx),
+        }
+    }
+}
+
+// Counts the drops of the nodes
+struct Counted {
+    value: u32,
+    drops: Rc<Cell<u32>>,
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+unsafe impl DeepClone for Counted {
+    fn deep_clone(&self, _: &mut DeepCloneContext) -> Self {
+        Counted {
+            value: self.value,
+            drops: self.drops.clone(),
+        }
+    }
+}
+
+type Node = GraphNode<Counted>;
+
+fn node(value: u32, drops: &Rc<Cell<u32>>) -> Cc<Node> {
+    graph_node(Counted {
+        value,
+        drops: drops.clone(),
+    })
+}
+
+fn next(cc: &Cc<Node>, i: usize) -> Cc<Node> {
+    cc.next.borrow()[i].clone()
+}
+
+#[test]
+fn test_deep_clone_cycle() {
+    reset_state();
+
+    let drops = Rc::new(Cell::new(0));
+    let first = node(1, &drops);
+    let second = node(2, &drops);
+    first.next.borrow_mut().push(second.clone());
+    second.next.borrow_mut().push(first.clone());
+    drop(second);
+
+    let copy = first.deep_clone();
+    assert!(!Cc::ptr_eq(&first, &copy));
+    assert_eq!(1, copy.value.value);
+
+    let copy_second = next(&copy, 0);
+    assert!(!Cc::ptr_eq(&next(&first, 0), &copy_second));
+    assert_eq!(2, copy_second.value.value);
+    assert!(Cc::ptr_eq(&copy, &next(&copy_second, 0)));
+    drop(copy_second);
+
+    // The copy is independent from the original
+    first.next.borrow_mut().clear();
+    assert_eq!(1, copy.next.borrow().len());
+
+    collect_cycles();
+    assert_eq!(1, drops.get());
+
+    drop(first);
+    drop(copy);
+    collect_cycles();
+    assert_eq!(4, drops.get());
+}
+
+#[test]
+fn test_deep_clone_shared() {
+    reset_state();
+
+    let drops = Rc::new(Cell::new(0));
+    let root = node(0, &drops);
+    let shared = node(1, &drops);
+    root.next.borrow_mut().push(shared.clone());
+    root.next.borrow_mut().push(node(2, &drops));
+    root.next.borrow_mut().push(shared);
+
+    let copy = root.deep_clone();
+    assert_eq!(3, copy.next.borrow().len());
+    assert!(Cc::ptr_eq(&next(&copy, 0), &next(&copy, 2)));
+    assert!(!Cc::ptr_eq(&next(&copy, 0), &next(&copy, 1)));
+    assert!(!Cc::ptr_eq(&next(&root, 0), &next(&copy, 0)));
+    assert_eq!(2, next(&copy, 1).value.value);
+
+    drop(root);
+    assert_eq!(3, drops.get());
+    drop(copy);
+    assert_eq!(6, drops.get());
+}
+
+#[test]
+fn test_deep_clone_values() {
+    reset_state();
+
+    let drops = Rc::new(Cell::new(0));
+    let shared = node(1, &drops);
+    let value = (vec![Some(shared.clone()), None, Some(shared)], String::from("value"));
+
+    let copy = deep_clone(&value);
+    assert_eq!("value", copy.1);
+    let copy_shared = copy.0[0].as_ref().unwrap();
+    assert!(Cc::ptr_eq(copy_shared, copy.0[2].as_ref().unwrap()));
+    assert!(!Cc::ptr_eq(copy_shared, value.0[0].as_ref().unwrap()));
+    assert!(copy.0[1].is_none());
+}
+
+struct Collecting {
+    next: RefCell<Option<Cc<Collecting>>>,
+}
+
+unsafe impl Trace for Collecting {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.next.trace(ctx);
+    }
+}
+
+impl Finalize for Collecting {}
+
+unsafe impl DeepClone for Collecting {
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        let next = self.next.deep_clone(ctx);
+
+        // Buffer the copy being initialized and collect, which must not trace its value
+        drop(next.clone());
+        collect_cycles();
+
+        Collecting { next }
+    }
+}
+
+#[test]
+fn test_collect_while_deep_cloning() {
+    reset_state();
+
+    let cc = Cc::new(Collecting {
+        next: RefCell::new(None),
+    });
+    *cc.next.borrow_mut() = Some(cc.clone());
+
+    let copy = cc.deep_clone();
+    assert!(Cc::ptr_eq(&copy, copy.next.borrow().as_ref().unwrap()));
+
+    copy.next.borrow_mut().take();
+    cc.next.borrow_mut().take();
+}
+
+struct Panicking {
+    next: RefCell<Option<Cc<Panicking>>>,
+}
+
+unsafe impl Trace for Panicking {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.next.trace(ctx);
+    }
+}
+
+impl Finalize for Panicking {}
+
+unsafe impl DeepClone for Panicking {
+    fn deep_clone(&self, ctx: &mut DeepCloneContext) -> Self {
+        let _next = self.next.deep_clone(ctx);
+        panic!("Expected panic during deep_clone!");
+    }
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_panicking_deep_clone() {
+    reset_state();
+
+    let cc = Cc::new(Panicking {
+        next: RefCell::new(None),
+    });
+    *cc.next.borrow_mut() = Some(cc.clone());
+
+    let res = panic::catch_unwind(AssertUnwindSafe(|| cc.deep_clone()));
+    assert!(res.is_err());
+
+    // The partially copied allocation is leaked, so collecting must not access it
+    collect_cycles();
+    assert_state_not_collecting();
+
+    cc.next.borrow_mut().take();
+}
+
+#[test]
+fn test_deep_clone_std_types() {
+    reset_state();
+
+    let drops = Rc::new(Cell::new(0));
+    let shared = node(1, &drops);
+    let value = (
+        OnceCell::from(shared.clone()),
+        Cow::<[Cc<Node>]>::Owned(vec![shared.clone()]),
+        Mutex::new(shared.clone()),
+        AtomicU32::new(3),
+    );
+
+    let copy = deep_clone(&value);
+    let copy_shared = copy.0.get().unwrap();
+    assert!(!Cc::ptr_eq(copy_shared, &shared));
+    assert!(Cc::ptr_eq(copy_shared, &copy.1[0]));
+    assert!(Cc::ptr_eq(copy_shared, &copy.2.lock().unwrap()));
+    assert_eq!(3, copy.3.load(Ordering::Relaxed));
+
+    // A locked Mutex cannot be copied
+    let guard = value.2.lock().unwrap();
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| deep_clone(&value.2))).is_err());
+    drop(guard);
+
+    drop(copy);
+    assert_eq!(1, drops.get());
+}
@@ -0,0 +1,275 @@
+use super::*;
+use crate::{collect_cycles, freeze, unfreeze};
+
+struct Node {
+    edges: RefCell<Vec<Cc<Droppable<Node>>>>,
+}
+
+unsafe impl Trace for Node {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.edges.trace(ctx);
+    }
+}
+
+impl Finalize for Node {}
+
+fn circular() -> (Cc<Droppable<Node>>, DropChecker) {
+    let (droppable, checker) = Droppable::new(Node {
+        edges: RefCell::new(Vec::new()),
+    });
+    let cc = Cc::new(droppable);
+    cc.edges.borrow_mut().push(cc.clone());
+    (cc, checker)
+}
+
+fn frozen_count() -> usize {
+    state::frozen_objects_count().unwrap()
+}
+
+#[test]
+fn test_freeze() {
+    reset_state();
+
+    let (first, first_checker) = circular();
+    let (second, second_checker) = circular();
+
+    // first is buffered, second is reachable from first
+    first.edges.borrow_mut().push(second.clone());
+    drop(second);
+    let _ = first.clone();
+
+    freeze();
+    assert_eq!(2, frozen_count());
+    assert!(state::frozen_bytes().unwrap() > 0);
+    assert_empty();
+
+    // Dropping a frozen object doesn't buffer it
+    drop(first);
+    assert_empty();
+
+    collect_cycles();
+    first_checker.assert_not_dropped();
+    second_checker.assert_not_dropped();
+
+    unfreeze();
+    assert_eq!(0, frozen_count());
+    assert_eq!(0, state::frozen_bytes().unwrap());
+    assert_eq!(2, state::buffered_objects_count().unwrap());
+
+    collect_cycles();
+    first_checker.assert_finalized();
+    first_checker.assert_dropped();
+    second_checker.assert_finalized();
+    second_checker.assert_dropped();
+}
+
+#[test]
+fn test_freeze_from_root() {
+    reset_state();
+
+    let (root, root_checker) = circular();
+    let (child, child_checker) = circular();
+    root.edges.borrow_mut().push(child.clone());
+
+    // Neither object is buffered, so freeze cannot reach them
+    freeze();
+    assert_eq!(0, frozen_count());
+
+    drop(child);
+    assert_eq!(1, state::buffered_objects_count().unwrap());
+
+    // The buffered child is reachable from root, so it is frozen and removed from the buffer
+    root.freeze();
+    assert_eq!(2, frozen_count());
+    assert!(root.inner().is_frozen());
+    assert_empty();
+
+    // Freezing again does nothing
+    root.freeze();
+    assert_eq!(2, frozen_count());
+
+    drop(root);
+    collect_cycles();
+    root_checker.assert_not_dropped();
+    child_checker.assert_not_dropped();
+
+    unfreeze();
+    collect_cycles();
+    root_checker.assert_dropped();
+    child_checker.assert_dropped();
+}
+
+#[test]
+fn test_deallocate_frozen() {
+    reset_state();
+
+    let mut ccs: Vec<_> = (0..3).map(|_| Cc::new(Droppable::new(Node {
+        edges: RefCell::new(Vec::new()),
+    }).0)).collect();
+    ccs.iter().for_each(|cc| drop(cc.clone()));
+    freeze();
+    assert_eq!(3, frozen_count());
+    assert!(ccs.iter().all(|cc| cc.inner().is_frozen() && !cc.inner().is_immortal()));
+
+    // Remove objects from the middle, the start and the end of the frozen list
+    let size = state::frozen_bytes().unwrap() / 3;
+    drop(ccs.remove(1));
+    assert_eq!(2, frozen_count());
+    assert_eq!(2 * size, state::frozen_bytes().unwrap());
+    drop(ccs.remove(0));
+    assert_eq!(1, frozen_count());
+    assert!(ccs[0].inner().is_frozen());
+    drop(ccs.pop());
+    assert_eq!(0, frozen_count());
+    assert_eq!(0, state::frozen_bytes().unwrap());
+    assert_empty();
+}
+
+#[test]
+fn test_tracing_stops_at_frozen() {
+    reset_state();
+
+    let (frozen, frozen_checker) = circular();
+    let _ = frozen.clone();
+    freeze();
+    assert_eq!(1, frozen_count());
+
+    // A garbage cycle referencing a frozen object
+    let (first, first_checker) = circular();
+    let (second, second_checker) = circular();
+    first.edges.borrow_mut().push(second.clone());
+    second.edges.borrow_mut().push(first.clone());
+    second.edges.borrow_mut().push(frozen.clone());
+    drop(first);
+    drop(second);
+
+    collect_cycles();
+    first_checker.assert_dropped();
+    second_checker.assert_dropped();
+    frozen_checker.assert_not_dropped();
+    // The frozen object is never traced (with finalization the cycle is traced twice, before and after finalizing)
+    let expected = if cfg!(feature = "finalization") { 4 } else { 2 };
+    assert_eq!(expected, state::last_collection_report().unwrap().unwrap().traced_objects);
+    assert_eq!(1, frozen_count());
+
+    // Frozen objects are deallocated when their last Cc is dropped
+    frozen.edges.borrow_mut().clear();
+    drop(frozen);
+    frozen_checker.assert_dropped();
+    assert_eq!(0, frozen_count());
+    assert_empty();
+}
+
+#[test]
+fn test_frozen_object_referencing_cycle() {
+    reset_state();
+
+    let (frozen, frozen_checker) = circular();
+    let _ = frozen.clone();
+    freeze();
+
+    // The cycle is referenced only by a frozen object, so it must not be collected
+    let (cc, checker) = circular();
+    frozen.edges.borrow_mut().push(cc.clone());
+    drop(cc);
+
+    collect_cycles();
+    checker.assert_not_dropped();
+    frozen_checker.assert_not_dropped();
+
+    drop(frozen);
+    unfreeze();
+    collect_cycles();
+    checker.assert_dropped();
+    frozen_checker.assert_dropped();
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_leak() {
+    reset_state();
+
+    let (cc, checker) = circular();
+    let (child, child_checker) = circular();
+    cc.edges.borrow_mut().push(child.clone());
+    drop(child);
+
+    let cloned = cc.clone();
+    drop(cc.clone());
+    let leaked: &'static Droppable<Node> = cc.leak();
+    assert_eq!(0, frozen_count());
+
+    // Immortal objects are never buffered nor collected, and their children are kept alive
+    drop(cloned);
+    assert_eq!(1, state::buffered_objects_count().unwrap()); // Only child is buffered
+    collect_cycles();
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+    child_checker.assert_not_dropped();
+    assert_eq!(2, leaked.edges.borrow().len());
+
+    // Unfreezing doesn't affect immortal objects
+    freeze();
+    unfreeze();
+    collect_cycles();
+    checker.assert_not_dropped();
+    child_checker.assert_not_dropped();
+
+    // Objects removed from an immortal object can be collected
+    leaked.edges.borrow_mut().pop();
+    collect_cycles();
+    child_checker.assert_dropped();
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_leak_frozen() {
+    reset_state();
+
+    let (cc, checker) = circular();
+    let _ = cc.clone();
+    freeze();
+    assert_eq!(1, frozen_count());
+
+    let leaked = cc.clone().leak();
+    assert_eq!(0, frozen_count());
+
+    // Leaking an immortal object again does nothing
+    let _ = cc.clone().leak();
+    assert_eq!(0, frozen_count());
+
+    drop(cc);
+    unfreeze();
+    collect_cycles();
+    checker.assert_not_dropped();
+    assert_eq!(1, leaked.edges.borrow().len());
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_leaked_is_frozen() {
+    reset_state();
+
+    let (cc, checker) = circular();
+    let _ = cc.clone();
+    let cloned = cc.clone();
+    let _ = cc.leak();
+
+    // Leaked objects are frozen, even if they're not inside the frozen list
+    assert!(cloned.inner().is_frozen());
+    assert!(cloned.inner().is_immortal());
+    assert_eq!(0, frozen_count());
+    assert_empty();
+
+    // unfreeze cannot clear the immortal state
+    unfreeze();
+    assert!(cloned.inner().is_frozen());
+    assert!(cloned.inner().is_immortal());
+    assert_empty();
+
+    drop(cloned);
+    assert_empty();
+    collect_cycles();
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+}
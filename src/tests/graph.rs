@@ -0,0 +1,385 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::CString;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::net::Ipv4Addr;
+use std::num::{Saturating, Wrapping};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, RwLock};
+
+use super::*;
+use crate::{collect_cycles, graph};
+use crate::graph::{GraphDebug, GraphDebugContext, GraphEq, GraphEqContext, GraphHash, GraphHashable, GraphHashContext};
+
+type Node = GraphNode<u32>;
+
+impl<T: Debug> Debug for GraphNode<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GraphNode")
+            .field("value", &self.value)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for GraphNode<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.next == other.next
+    }
+}
+
+impl<T: Hash> Hash for GraphNode<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+        self.next.borrow().hash(state);
+    }
+}
+
+impl<T: GraphDebug> GraphDebug for GraphNode<T> {
+    fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+        f.debug_struct("GraphNode")
+            .field("value", &ctx.debug(&self.value))
+            .field("next", &ctx.debug(&self.next))
+            .finish()
+    }
+}
+
+impl<T: GraphEq> GraphEq for GraphNode<T> {
+    fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+        self.value.graph_eq(&other.value, ctx) && self.next.graph_eq(&other.next, ctx)
+    }
+}
+
+impl<T: GraphHashable> GraphHashable for GraphNode<T> {
+    fn graph_hash<H: Hasher>(&self, state: &mut H, ctx: &mut GraphHashContext) {
+        self.value.graph_hash(state, ctx);
+        self.next.graph_hash(state, ctx);
+    }
+}
+
+fn node(value: u32) -> Cc<Node> {
+    graph_node(value)
+}
+
+fn cycle_str(cc: &Cc<Node>) -> String {
+    format!("<cycle @{:p}>", *cc)
+}
+
+#[test]
+fn test_debug_graph() {
+    reset_state();
+
+    let first = node(1);
+    let second = node(2);
+    first.next.borrow_mut().push(second.clone());
+    second.next.borrow_mut().push(first.clone());
+
+    assert_eq!(
+        format!(
+            "GraphNode {{ value: 1, next: RefCell {{ value: [GraphNode {{ value: 2, next: RefCell {{ value: [{}] }} }}] }} }}",
+            cycle_str(&first)
+        ),
+        format!("{:?}", first.debug_graph())
+    );
+
+    // The Debug implementation of Cc just forwards to the value
+    first.next.borrow_mut().clear();
+    assert_eq!("GraphNode { value: 1, next: RefCell { value: [] } }", format!("{:?}", first));
+    assert_eq!(format!("{:?}", first), format!("{:?}", first.debug_graph()));
+    second.next.borrow_mut().clear();
+}
+
+#[test]
+fn test_debug_graph_borrowed() {
+    reset_state();
+
+    let cc = node(1);
+    let _borrow = cc.next.borrow_mut();
+    assert_eq!(
+        "GraphNode { value: 1, next: RefCell { value: <borrowed> } }",
+        format!("{:?}", cc.debug_graph())
+    );
+}
+
+#[test]
+fn test_debug_graph_shared_node() {
+    reset_state();
+
+    // A node reachable through multiple paths without forming a cycle is printed every time
+    let root = node(1);
+    let shared = node(2);
+    root.next.borrow_mut().push(shared.clone());
+    root.next.borrow_mut().push(shared.clone());
+
+    let shared_str = "GraphNode { value: 2, next: RefCell { value: [] } }";
+    assert_eq!(
+        format!("GraphNode {{ value: 1, next: RefCell {{ value: [{shared_str}, {shared_str}] }} }}"),
+        format!("{:?}", root.debug_graph())
+    );
+}
+
+#[test]
+fn test_custom_graph_debug() {
+    reset_state();
+
+    struct Wrapper(Cc<Node>);
+
+    impl GraphDebug for Wrapper {
+        fn fmt_graph(&self, f: &mut Formatter<'_>, ctx: &GraphDebugContext) -> fmt::Result {
+            write!(f, "Wrapper({:?})", ctx.debug(&self.0))
+        }
+    }
+
+    unsafe impl Trace for Wrapper {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.0.trace(ctx);
+        }
+    }
+
+    impl Finalize for Wrapper {}
+
+    let cc = node(1);
+    cc.next.borrow_mut().push(cc.clone());
+    let wrapper = Cc::new(Wrapper(cc.clone()));
+
+    assert_eq!(
+        format!("Wrapper(GraphNode {{ value: 1, next: RefCell {{ value: [{}] }} }})", cycle_str(&cc)),
+        format!("{:?}", wrapper.debug_graph())
+    );
+
+    cc.next.borrow_mut().clear();
+}
+
+#[test]
+fn test_debug_graph_panic() {
+    reset_state();
+
+    struct Panicking;
+
+    impl GraphDebug for Panicking {
+        fn fmt_graph(&self, _: &mut Formatter<'_>, _: &GraphDebugContext) -> fmt::Result {
+            panic!("Panicking in GraphDebug");
+        }
+    }
+
+    unsafe impl Trace for Panicking {
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl Finalize for Panicking {}
+
+    let cc = Cc::new(Panicking);
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| format!("{:?}", cc.debug_graph()))).is_err());
+
+    // The next formatting isn't affected by the panic
+    let cc = self_loop(1);
+    assert_eq!(
+        format!("GraphNode {{ value: 1, next: RefCell {{ value: [{}] }} }}", cycle_str(&cc)),
+        format!("{:?}", cc.debug_graph())
+    );
+    cc.next.borrow_mut().clear();
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn self_loop(value: u32) -> Cc<Node> {
+    let cc = node(value);
+    cc.next.borrow_mut().push(cc.clone());
+    cc
+}
+
+fn two_cycle(first: u32, second: u32) -> Cc<Node> {
+    let a = node(first);
+    let b = node(second);
+    a.next.borrow_mut().push(b.clone());
+    b.next.borrow_mut().push(a.clone());
+    a
+}
+
+fn break_cycles(ccs: &[&Cc<Node>]) {
+    for cc in ccs {
+        cc.next.borrow_mut().clear();
+    }
+    collect_cycles();
+}
+
+#[test]
+fn test_graph_eq() {
+    reset_state();
+
+    let a = self_loop(1);
+    let b = two_cycle(1, 1);
+    let c = two_cycle(1, 2);
+
+    assert!(graph::graph_eq(&a, &a));
+    assert!(graph::graph_eq(&a, &b));
+    assert!(graph::graph_eq(&b, &a));
+    assert!(!graph::graph_eq(&a, &c));
+    assert!(!graph::graph_eq(&c, &b));
+
+    // The PartialEq implementation of Cc just forwards to the value
+    let d = node(1);
+    let e = node(1);
+    assert!(d == e);
+    e.next.borrow_mut().push(node(3));
+    assert!(d != e);
+
+    let b_next = b.next.borrow()[0].clone();
+    let c_next = c.next.borrow()[0].clone();
+    break_cycles(&[&a, &b, &c, &b_next, &c_next]);
+}
+
+#[test]
+fn test_graph_eq_different_shapes() {
+    reset_state();
+
+    // Same values but different numbers of edges
+    let a = self_loop(1);
+    let b = self_loop(1);
+    b.next.borrow_mut().push(b.clone());
+
+    assert!(!graph::graph_eq(&a, &b));
+
+    break_cycles(&[&a, &b]);
+}
+
+#[test]
+fn test_graph_eq_retracts_wrong_assumptions() {
+    reset_state();
+
+    // Equal if any of the two lists is equal
+    struct Either(Vec<Cc<Node>>, Vec<Cc<Node>>);
+
+    impl GraphEq for Either {
+        fn graph_eq(&self, other: &Self, ctx: &mut GraphEqContext) -> bool {
+            self.0.graph_eq(&other.0, ctx) || self.1.graph_eq(&other.1, ctx)
+        }
+    }
+
+    let a = self_loop(1);
+    let b = self_loop(2);
+
+    // The assumption that a and b are equal made while comparing the first lists must not be used for the second ones
+    let first = Either(vec![a.clone()], vec![a.clone()]);
+    let second = Either(vec![b.clone()], vec![b.clone()]);
+    assert!(!graph::graph_eq(&first, &second));
+
+    let third = Either(vec![b.clone()], vec![self_loop(1)]);
+    assert!(graph::graph_eq(&first, &third));
+
+    let third_next = third.1[0].clone();
+    drop((first, second, third));
+    break_cycles(&[&a, &b, &third_next]);
+}
+
+#[test]
+fn test_graph_hash() {
+    reset_state();
+
+    let a = self_loop(1);
+    let b = two_cycle(1, 1);
+    let c = two_cycle(1, 2);
+    let b_next = b.next.borrow()[0].clone();
+    let c_next = c.next.borrow()[0].clone();
+
+    let hash_a = GraphHash::new(a.clone(), 4);
+    let hash_b = GraphHash::new(b.clone(), 4);
+    let hash_c = GraphHash::new(c.clone(), 4);
+
+    assert_eq!(hash_a, hash_b);
+    assert_ne!(hash_a, hash_c);
+    assert_eq!(hash_of(&hash_a), hash_of(&hash_b));
+    assert_ne!(hash_of(&hash_a), hash_of(&hash_c));
+
+    // A depth of 1 only hashes the first node
+    assert_eq!(
+        hash_of(&GraphHash::new(c.clone(), 1)),
+        hash_of(&GraphHash::new(a.clone(), 1)),
+    );
+
+    let mut memo = HashMap::new();
+    memo.insert(hash_a, "a");
+    assert_eq!(Some(&"a"), memo.get(&hash_b));
+    assert_eq!(None, memo.get(&hash_c));
+
+    // A depth of 0 hashes nothing
+    assert_eq!(hash_of(&GraphHash::new(c.clone(), 0)), hash_of(&GraphHash::new(node(3), 0)));
+
+    // The Hash implementation of Cc just forwards to the value
+    let d = node(1);
+    let e = node(1);
+    assert_eq!(hash_of(&d), hash_of(&e));
+    e.next.borrow_mut().push(node(3));
+    assert_ne!(hash_of(&d), hash_of(&e));
+
+    drop(memo);
+    drop((hash_b, hash_c));
+    break_cycles(&[&a, &b, &c, &b_next, &c_next]);
+}
+
+fn assert_debug_like_std<T: Trace + GraphDebug + Debug + 'static>(value: T) {
+    let cc = Cc::new(value);
+    assert_eq!(format!("{:?}", *cc), format!("{:?}", cc.debug_graph()));
+}
+
+#[test]
+fn test_debug_graph_std_types() {
+    reset_state();
+
+    assert_debug_like_std(Mutex::new(Some(1u32)));
+    assert_debug_like_std(RwLock::new(vec![1u32]));
+    assert_debug_like_std(OnceCell::from(1u32));
+    assert_debug_like_std(OnceCell::<u32>::new());
+    assert_debug_like_std(Reverse(1u32));
+    assert_debug_like_std(Wrapping(1u32));
+    assert_debug_like_std(Saturating(1u32));
+    assert_debug_like_std(BTreeSet::from([1u32, 2]));
+    assert_debug_like_std(BinaryHeap::from([1u32]));
+    assert_debug_like_std(Cow::<'static, str>::Borrowed("str"));
+    assert_debug_like_std(CString::new("str").unwrap());
+    assert_debug_like_std(Ipv4Addr::LOCALHOST);
+
+    let mutex = Cc::new(Mutex::new(1u32));
+    let _guard = mutex.lock().unwrap();
+    assert_eq!(format!("{:?}", *mutex), format!("{:?}", mutex.debug_graph()));
+
+    let cc = self_loop(1);
+    let map = Cc::new(BTreeMap::from([(1u32, cc.clone())]));
+    assert_eq!(
+        format!("{{1: GraphNode {{ value: 1, next: RefCell {{ value: [{}] }} }}}}", cycle_str(&cc)),
+        format!("{:?}", map.debug_graph())
+    );
+
+    drop(map);
+    break_cycles(&[&cc]);
+}
+
+#[test]
+fn test_graph_eq_std_types() {
+    reset_state();
+
+    let a = self_loop(1);
+    let b = two_cycle(1, 1);
+    let c = two_cycle(1, 2);
+
+    assert!(graph::graph_eq(&BTreeMap::from([(1u32, a.clone())]), &BTreeMap::from([(1u32, b.clone())])));
+    assert!(!graph::graph_eq(&BTreeMap::from([(1u32, a.clone())]), &BTreeMap::from([(2u32, b.clone())])));
+    assert!(graph::graph_eq(&OnceCell::from(a.clone()), &OnceCell::from(b.clone())));
+    assert!(!graph::graph_eq(&OnceCell::from(a.clone()), &OnceCell::new()));
+    assert!(!graph::graph_eq(
+        &Cow::<[Cc<Node>]>::Owned(vec![a.clone()]),
+        &Cow::<[Cc<Node>]>::Owned(vec![c.clone()])
+    ));
+
+    let b_next = b.next.borrow()[0].clone();
+    let c_next = c.next.borrow()[0].clone();
+    break_cycles(&[&a, &b, &c, &b_next, &c_next]);
+}
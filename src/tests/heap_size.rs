@@ -0,0 +1,130 @@
+use std::mem::size_of;
+
+use super::*;
+use crate::*;
+use crate::heap_size::*;
+
+impl<T: HeapSize> HeapSize for GraphNode<T> {
+    fn heap_size(&self, ctx: &mut HeapSizeContext) -> usize {
+        self.value.heap_size(ctx) + self.next.heap_size(ctx)
+    }
+}
+
+fn node(bytes: usize) -> Cc<GraphNode<Vec<u8>>> {
+    graph_node(Vec::with_capacity(bytes))
+}
+
+fn cc_size(bytes: usize) -> usize {
+    size_of::<CcBox<GraphNode<Vec<u8>>>>() + bytes
+}
+
+#[test]
+fn test_deep_size_of() {
+    reset_state();
+
+    assert_eq!(size_of::<u64>(), deep_size_of(&0u64));
+    assert_eq!(size_of::<String>() + 10, deep_size_of(&String::with_capacity(10)));
+    assert_eq!(size_of::<Vec<u32>>() + 3 * size_of::<u32>(), deep_size_of(&Vec::<u32>::with_capacity(3)));
+    assert_eq!(size_of::<Box<[u8]>>() + 7, deep_size_of(&vec![0u8; 7].into_boxed_slice()));
+    assert_eq!(
+        size_of::<Option<Box<String>>>() + size_of::<String>() + 4,
+        deep_size_of(&Some(Box::new(String::with_capacity(4))))
+    );
+
+    let cc = node(16);
+    let size = size_of::<Cc<GraphNode<Vec<u8>>>>() + cc_size(16);
+    assert_eq!(size, deep_size_of(&cc));
+
+    // The same allocation is counted only once
+    let vec = vec![cc.clone(), cc.clone(), cc];
+    assert_eq!(
+        size_of::<Vec<Cc<GraphNode<Vec<u8>>>>>() + 3 * size_of::<Cc<GraphNode<Vec<u8>>>>() + cc_size(16),
+        deep_size_of(&vec)
+    );
+}
+
+#[test]
+fn test_retained_size() {
+    reset_state();
+
+    // a -> b -> c, c is also owned by another Cc
+    let a = node(1);
+    let b = node(10);
+    let c = node(100);
+    b.next.borrow_mut().push(c.clone());
+    a.next.borrow_mut().push(b.clone());
+    drop(b);
+
+    let size = a.retained_size();
+    let reachable = cc_size(1) + cc_size(10) + 8 + cc_size(100); // b's Vec has capacity 4, so 4 * 8 bytes are counted
+    assert_eq!(size.reachable, size.exclusive + cc_size(100));
+    assert!(size.reachable >= reachable);
+
+    // Now c is exclusively retained by a
+    drop(c);
+    let new_size = a.retained_size();
+    assert_eq!(size.reachable, new_size.reachable);
+    assert_eq!(new_size.reachable, new_size.exclusive);
+
+    drop(a);
+    collect_cycles();
+    assert_empty();
+}
+
+#[test]
+fn test_retained_size_cycles() {
+    reset_state();
+
+    // a <-> b, plus a self-loop on b
+    let a = node(1);
+    let b = node(2);
+    a.next.borrow_mut().push(b.clone());
+    b.next.borrow_mut().push(a.clone());
+    b.next.borrow_mut().push(b.clone());
+
+    // Both a and b are owned from outside the cycle, so each one retains only itself
+    let a_size = a.retained_size();
+    let b_size = b.retained_size();
+    assert_eq!(a_size.reachable, b_size.reachable);
+    assert_eq!(a_size.reachable, a_size.exclusive + b_size.exclusive);
+    assert!(a_size.exclusive >= cc_size(1));
+    assert!(b_size.exclusive >= cc_size(2));
+
+    // Now b is owned only by a
+    drop(b);
+    let size = a.retained_size();
+    assert_eq!(a_size.reachable, size.reachable);
+    assert_eq!(size.reachable, size.exclusive);
+
+    drop(a);
+    collect_cycles();
+    assert_empty();
+}
+
+#[test]
+fn test_retained_size_panicking_trace() {
+    reset_state();
+
+    struct Panicking;
+
+    unsafe impl Trace for Panicking {
+        fn trace(&self, _: &mut Context<'_>) {
+            panic!("Test panic");
+        }
+    }
+
+    impl Finalize for Panicking {}
+
+    impl HeapSize for Panicking {
+        fn heap_size(&self, _: &mut HeapSizeContext) -> usize {
+            0
+        }
+    }
+
+    let cc = Cc::new(Panicking);
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cc.retained_size()));
+    assert!(res.is_err());
+    assert!(!state(|state| state.is_collecting()));
+
+    std::mem::forget(cc); // Don't trace again
+}
@@ -185,6 +185,7 @@ fn test_remove_first(mut list: impl ListMethods) {
 }
 
 #[test]
+#[allow(clippy::never_loop)] // The closure panics on purpose
 fn test_for_each_clearing_panic() {
     let mut list = LinkedList::new();
     let mut vec = new_collection(&[0, 1, 2, 3], &mut list);
@@ -387,7 +388,7 @@ trait ListMethods: CommonMethods {
 
     fn is_empty(&self) -> bool;
 
-    fn iter(&self) -> Iter;
+    fn iter(&self) -> Iter<'_>;
 
     fn assert_size(&self, expected_size: usize);
 }
@@ -415,7 +416,7 @@ fn is_empty(&self) -> bool {
         self.is_empty()
     }
 
-    fn iter(&self) -> Iter {
+    fn iter(&self) -> Iter<'_> {
         self.iter()
     }
 
@@ -447,7 +448,7 @@ fn is_empty(&self) -> bool {
         self.is_empty()
     }
 
-    fn iter(&self) -> Iter {
+    fn iter(&self) -> Iter<'_> {
         self.iter()
     }
 
@@ -5,14 +5,22 @@
 use std::ops::{Deref, DerefMut};
 
 use crate::trace::Trace;
-use crate::{state, Cc, Context, Finalize, POSSIBLE_CYCLES};
+use crate::{state, Cc, Context, Finalize, FROZEN, POSSIBLE_CYCLES};
 use crate::state::state;
 
 mod bench_code;
 mod cc;
+mod cell;
+mod closure;
 mod lists;
 mod panicking;
+mod roots;
 mod counter_marker;
+mod deep_clone;
+mod freeze;
+mod graph;
+mod heap_size;
+mod trace;
 
 #[cfg(feature = "weak-ptrs")]
 mod weak;
@@ -20,8 +28,15 @@
 #[cfg(feature = "cleaners")]
 mod cleaners;
 
+#[cfg(feature = "finalization")]
+mod resurrection;
+
+#[cfg(feature = "auto-collect")]
+mod order;
+
 pub(crate) fn reset_state() {
     POSSIBLE_CYCLES.with(|pc| pc.reset());
+    FROZEN.with(|frozen| frozen.reset());
     state::reset_state();
 
     #[cfg(feature = "auto-collect")]
@@ -122,6 +137,27 @@ pub(crate) fn assert_not_dropped(&self) {
     }
 }
 
+/// A node of an object graph, used to test the utilities which walk whole graphs.
+pub(crate) struct GraphNode<T: 'static> {
+    pub(crate) value: T,
+    pub(crate) next: RefCell<Vec<Cc<GraphNode<T>>>>,
+}
+
+unsafe impl<T: 'static> Trace for GraphNode<T> {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.next.trace(ctx);
+    }
+}
+
+impl<T: 'static> Finalize for GraphNode<T> {}
+
+pub(crate) fn graph_node<T: 'static>(value: T) -> Cc<GraphNode<T>> {
+    Cc::new(GraphNode {
+        value,
+        next: RefCell::new(Vec::new()),
+    })
+}
+
 pub(crate) fn assert_empty() {
     assert!(POSSIBLE_CYCLES.with(|pc| pc.is_empty()));
 }
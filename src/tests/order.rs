@@ -0,0 +1,120 @@
+use super::*;
+use crate::collect_cycles;
+use crate::config::{config, FinalizationOrder};
+
+type Log = Rc<RefCell<Vec<&'static str>>>;
+
+struct Node {
+    name: &'static str,
+    edges: RefCell<Vec<Cc<Node>>>,
+    #[allow(unused)]
+    finalized: Log,
+    dropped: Log,
+}
+
+unsafe impl Trace for Node {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.edges.trace(ctx);
+    }
+}
+
+impl Finalize for Node {
+    #[cfg(feature = "finalization")]
+    fn finalize(&self) {
+        self.finalized.borrow_mut().push(self.name);
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.borrow_mut().push(self.name);
+    }
+}
+
+fn link(from: &Cc<Node>, to: &Cc<Node>) {
+    from.edges.borrow_mut().push(to.clone());
+}
+
+/// Builds a -> {b <-> c} -> d, where a and d reference themselves, allocating the nodes in the given order.
+fn build(allocation_order: [&'static str; 4], finalized: &Log, dropped: &Log) {
+    let nodes: Vec<Cc<Node>> = allocation_order.iter().map(|&name| Cc::new(Node {
+        name,
+        edges: RefCell::new(Vec::new()),
+        finalized: finalized.clone(),
+        dropped: dropped.clone(),
+    })).collect();
+    let node = |name| nodes.iter().find(|node| node.name == name).unwrap();
+
+    link(node("a"), node("a"));
+    link(node("a"), node("b"));
+    link(node("b"), node("c"));
+    link(node("c"), node("b"));
+    link(node("c"), node("d"));
+    link(node("d"), node("d"));
+}
+
+fn assert_ordered(log: &[&'static str]) {
+    assert_eq!(4, log.len());
+    assert_eq!("a", log[0]);
+    assert!(log[1..3].contains(&"b") && log[1..3].contains(&"c"));
+    assert_eq!("d", log[3]);
+}
+
+#[test]
+fn test_topological_order() {
+    let orders = [
+        ["a", "b", "c", "d"],
+        ["d", "c", "b", "a"],
+        ["c", "a", "d", "b"],
+        ["b", "d", "a", "c"],
+    ];
+
+    for allocation_order in orders {
+        reset_state();
+        config(|config| config.set_finalization_order(FinalizationOrder::Topological)).unwrap();
+
+        let finalized: Log = Rc::new(RefCell::new(Vec::new()));
+        let dropped: Log = Rc::new(RefCell::new(Vec::new()));
+        build(allocation_order, &finalized, &dropped);
+
+        collect_cycles();
+
+        #[cfg(feature = "finalization")]
+        assert_ordered(&finalized.borrow());
+        assert_ordered(&dropped.borrow());
+        assert_empty();
+    }
+}
+
+#[test]
+fn test_topological_order_is_deterministic() {
+    let run = || {
+        reset_state();
+        config(|config| config.set_finalization_order(FinalizationOrder::Topological)).unwrap();
+
+        let finalized: Log = Rc::new(RefCell::new(Vec::new()));
+        let dropped: Log = Rc::new(RefCell::new(Vec::new()));
+        build(["c", "a", "d", "b"], &finalized, &dropped);
+        collect_cycles();
+
+        let finalized = finalized.borrow().clone();
+        let dropped = dropped.borrow().clone();
+        (finalized, dropped)
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn test_unordered_by_default() {
+    reset_state();
+    assert_eq!(FinalizationOrder::Unordered, config(|config| config.finalization_order()).unwrap());
+
+    let finalized: Log = Rc::new(RefCell::new(Vec::new()));
+    let dropped: Log = Rc::new(RefCell::new(Vec::new()));
+    build(["a", "b", "c", "d"], &finalized, &dropped);
+
+    collect_cycles();
+    assert_eq!(4, dropped.borrow().len());
+    assert_empty();
+}
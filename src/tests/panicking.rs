@@ -2,7 +2,7 @@
 use std::mem;
 
 use crate::tests::{assert_state_not_collecting, reset_state};
-use crate::{collect_cycles, Cc, Context, Finalize, Trace};
+use crate::{collect_cycles, try_collect_cycles, Cc, Context, Finalize, Trace};
 
 fn register_panicking<T: Trace>(#[allow(unused_variables)] cc: &Cc<T>) { // The unused_variables warning is triggered when not running on Miri
     #[cfg(miri)]
@@ -318,3 +318,186 @@ fn finalize(&self) {
 
     panicking_collect_cycles(assert_state_not_collecting);
 }
+
+#[test]
+fn test_try_collect_cycles() {
+    reset_state();
+
+    {
+        let cc = Cc::new(Panicking {
+            trace_counter: Cell::new(usize::MAX),
+            panic_on_trace: Cell::new(false),
+            panic_on_finalize: Cell::new(false),
+            panic_on_drop: Cell::new(false),
+            cc: RefCell::new(None),
+        });
+        *cc.cc.borrow_mut() = Some(cc.clone());
+    }
+
+    let report = try_collect_cycles().unwrap();
+    assert_eq!(1, report.buffered_objects_before);
+    assert_eq!(1, report.deallocated_objects);
+    assert_state_not_collecting();
+}
+
+#[cfg(feature = "finalization")]
+#[test]
+fn test_try_collect_cycles_while_collecting() {
+    use std::rc::Rc;
+    use crate::state::CollectError;
+
+    reset_state();
+
+    struct Collecting {
+        result: Rc<Cell<Option<Result<(), CollectError>>>>,
+        cyclic: RefCell<Option<Cc<Collecting>>>,
+    }
+
+    unsafe impl Trace for Collecting {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.cyclic.trace(ctx);
+        }
+    }
+
+    impl Finalize for Collecting {
+        fn finalize(&self) {
+            self.result.set(Some(try_collect_cycles().map(|_| ())));
+        }
+    }
+
+    let result = Rc::new(Cell::new(None));
+    {
+        let cc = Cc::new(Collecting {
+            result: result.clone(),
+            cyclic: RefCell::new(None),
+        });
+        *cc.cyclic.borrow_mut() = Some(cc.clone());
+    }
+    collect_cycles();
+
+    assert!(matches!(result.take(), Some(Err(CollectError::AlreadyCollecting))));
+    assert_state_not_collecting();
+}
+
+#[test]
+fn test_try_collect_cycles_while_dropping() {
+    use std::rc::Rc;
+
+    reset_state();
+
+    struct Dropping {
+        result: Rc<Cell<Option<usize>>>,
+    }
+
+    unsafe impl Trace for Dropping {
+        fn trace(&self, _: &mut Context<'_>) {
+        }
+    }
+
+    impl Finalize for Dropping {}
+
+    impl Drop for Dropping {
+        fn drop(&mut self) {
+            self.result.set(try_collect_cycles().ok().map(|report| report.deallocated_objects));
+        }
+    }
+
+    // A garbage cycle to be collected while dropping
+    {
+        let cc = Cc::new(Panicking {
+            trace_counter: Cell::new(usize::MAX),
+            panic_on_trace: Cell::new(false),
+            panic_on_finalize: Cell::new(false),
+            panic_on_drop: Cell::new(false),
+            cc: RefCell::new(None),
+        });
+        *cc.cc.borrow_mut() = Some(cc.clone());
+    }
+
+    let result = Rc::new(Cell::new(None));
+    // Dropping the only Cc deallocates the object outside of a collection, which doesn't prevent starting a new one
+    drop(Cc::new(Dropping {
+        result: result.clone(),
+    }));
+
+    assert_eq!(Some(1), result.take());
+    assert_state_not_collecting();
+}
+
+#[cfg(feature = "auto-collect")]
+#[test]
+fn test_catch_and_leak_panic_policy() {
+    use crate::config::{config, PanicPolicy};
+    use crate::state::CollectError;
+
+    reset_state();
+
+    config(|config| config.set_panic_policy(PanicPolicy::CatchAndLeak)).unwrap();
+
+    {
+        let cc = Cc::new(Panicking {
+            trace_counter: Cell::new(0),
+            panic_on_trace: Cell::new(true),
+            panic_on_finalize: Cell::new(false),
+            panic_on_drop: Cell::new(false),
+            cc: RefCell::new(None),
+        });
+        *cc.cc.borrow_mut() = Some(cc.clone());
+        register_panicking(&cc);
+    }
+
+    assert!(matches!(try_collect_cycles(), Err(CollectError::Panicked)));
+    assert_state_not_collecting();
+
+    // The collector is still usable after the panic
+    collect_cycles();
+    assert!(try_collect_cycles().is_ok());
+}
+
+#[cfg(all(feature = "auto-collect", feature = "finalization"))]
+#[test]
+fn test_catch_and_leak_panic_policy_finalize() {
+    use crate::config::{config, PanicPolicy};
+    use crate::state::{self, CollectError};
+
+    reset_state();
+
+    config(|config| config.set_panic_policy(PanicPolicy::CatchAndLeak)).unwrap();
+
+    {
+        let cc = Cc::new(Panicking {
+            trace_counter: Cell::new(0),
+            panic_on_trace: Cell::new(false),
+            panic_on_finalize: Cell::new(true),
+            panic_on_drop: Cell::new(false),
+            cc: RefCell::new(None),
+        });
+        *cc.cc.borrow_mut() = Some(cc.clone());
+        register_panicking(&cc);
+    }
+
+    assert!(matches!(try_collect_cycles(), Err(CollectError::Panicked)));
+    assert_state_not_collecting();
+
+    // The object being finalized has been leaked and removed from every list
+    assert_eq!(0, state::buffered_objects_count().unwrap());
+
+    // Another collection doesn't access the leaked object
+    {
+        let cc = Cc::new(Panicking {
+            trace_counter: Cell::new(0),
+            panic_on_trace: Cell::new(false),
+            panic_on_finalize: Cell::new(false),
+            panic_on_drop: Cell::new(false),
+            cc: RefCell::new(None),
+        });
+        *cc.cc.borrow_mut() = Some(cc.clone());
+    }
+
+    let report = try_collect_cycles().unwrap();
+    assert_eq!(1, report.buffered_objects_before);
+    // The new cycle is traced before and after being finalized, while the leaked object is never traced
+    assert_eq!(2, report.traced_objects);
+    assert_eq!(1, report.deallocated_objects);
+    assert_state_not_collecting();
+}
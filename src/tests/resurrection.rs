@@ -0,0 +1,107 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use super::*;
+use crate::collect_cycles;
+use crate::resurrection::*;
+
+thread_local! {
+    static RESURRECTED: RefCell<Vec<Cc<Zombie>>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Zombie {
+    resurrect: bool,
+    cyclic: RefCell<Option<Cc<Zombie>>>,
+}
+
+unsafe impl Trace for Zombie {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.cyclic.trace(ctx);
+    }
+}
+
+impl Finalize for Zombie {
+    fn finalize(&self) {
+        if self.resurrect {
+            if let Some(cc) = &*self.cyclic.borrow() {
+                RESURRECTED.with(|resurrected| resurrected.borrow_mut().push(cc.clone()));
+            }
+        }
+    }
+}
+
+fn zombie(resurrect: bool) -> Cc<Zombie> {
+    let cc = Cc::new(Zombie {
+        resurrect,
+        cyclic: RefCell::new(None),
+    });
+    *cc.cyclic.borrow_mut() = Some(cc.clone());
+    cc
+}
+
+fn bury_zombies() {
+    let zombies = RESURRECTED.with(|resurrected| resurrected.take());
+    for zombie in zombies {
+        zombie.cyclic.borrow_mut().take();
+    }
+}
+
+#[test]
+fn test_detect_resurrections() {
+    reset_state();
+
+    set_detection_mode(DetectionMode::Report).unwrap();
+    assert_eq!(DetectionMode::Report, detection_mode().unwrap());
+
+    drop(zombie(true));
+    drop(zombie(false));
+    collect_cycles();
+
+    let resurrections = take_resurrections();
+    assert_eq!(1, resurrections.len());
+    assert!(resurrections[0].type_name().ends_with("Zombie"));
+    assert_eq!(file!(), resurrections[0].allocation_site().unwrap().file());
+    assert!(resurrections[0].to_string().contains(file!()));
+    assert!(take_resurrections().is_empty());
+
+    bury_zombies();
+    set_detection_mode(DetectionMode::Disabled).unwrap();
+}
+
+#[test]
+fn test_detection_disabled() {
+    reset_state();
+
+    // Allocated while detection is disabled, so the allocation site is unknown
+    let cc = zombie(true);
+    set_detection_mode(DetectionMode::Report).unwrap();
+    drop(cc);
+    collect_cycles();
+
+    let resurrections = take_resurrections();
+    assert_eq!(1, resurrections.len());
+    assert!(resurrections[0].allocation_site().is_none());
+    bury_zombies();
+
+    set_detection_mode(DetectionMode::Disabled).unwrap();
+    drop(zombie(true));
+    collect_cycles();
+    assert!(take_resurrections().is_empty());
+    bury_zombies();
+}
+
+#[test]
+fn test_panic_on_resurrection() {
+    reset_state();
+
+    set_detection_mode(DetectionMode::Panic).unwrap();
+    drop(zombie(true));
+
+    let res = panic::catch_unwind(AssertUnwindSafe(collect_cycles));
+    let message = *res.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("resurrected during finalization"));
+    assert!(message.contains("Zombie"));
+    assert_state_not_collecting();
+
+    bury_zombies();
+    set_detection_mode(DetectionMode::Disabled).unwrap();
+}
@@ -0,0 +1,158 @@
+use super::*;
+use crate::collect_cycles;
+use crate::roots::*;
+
+struct Circular {
+    next: RefCell<Option<Cc<Droppable<Circular>>>>,
+}
+
+unsafe impl Trace for Circular {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.next.trace(ctx);
+    }
+}
+
+impl Finalize for Circular {}
+
+fn circular() -> (Cc<Droppable<Circular>>, DropChecker) {
+    let (droppable, checker) = Droppable::new(Circular {
+        next: RefCell::new(None),
+    });
+    let cc = Cc::new(droppable);
+    *cc.next.borrow_mut() = Some(cc.clone());
+    (cc, checker)
+}
+
+#[test]
+fn test_root_set() {
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(5u32);
+    let mut roots = RootSet::new();
+    let handle = roots.root(Cc::new(droppable));
+    let copy = handle;
+    assert_eq!(5, **copy);
+    assert!(Handle::ptr_eq(&handle, &copy));
+    assert_eq!(2, handle.to_cc().strong_count());
+    assert_eq!(1, roots.len());
+
+    collect_cycles();
+    checker.assert_not_dropped();
+
+    roots.clear();
+    assert!(roots.is_empty());
+    checker.assert_finalized();
+    checker.assert_dropped();
+    assert_empty();
+}
+
+#[test]
+fn test_root_moves_reference() {
+    reset_state();
+
+    let cc = Cc::new(1u32);
+    let other = cc.clone();
+    assert_eq!(2, other.strong_count());
+
+    // Rooting doesn't modify the reference count, neither does copying handles
+    let roots = RootSet::new();
+    let handle = roots.root(cc);
+    let _copies = [handle; 8];
+    assert_eq!(2, other.strong_count());
+
+    drop(roots);
+    assert_eq!(1, other.strong_count());
+}
+
+#[test]
+fn test_rooted_cycle() {
+    reset_state();
+
+    let (cc, checker) = circular();
+    let roots = RootSet::new();
+    let handle = roots.root(cc);
+
+    collect_cycles();
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+    assert!(handle.next.borrow().is_some());
+
+    drop(roots);
+    checker.assert_not_dropped();
+
+    collect_cycles();
+    checker.assert_finalized();
+    checker.assert_dropped();
+    assert_empty();
+}
+
+#[test]
+fn test_enumerate_roots() {
+    reset_state();
+
+    let roots = RootSet::new();
+    roots.root(Cc::new(1u32));
+    roots.root(Cc::new(String::from("two")));
+    let three = roots.root(Cc::new(3u32));
+
+    let numbers: Vec<u32> = roots.iter().filter_map(|root| root.downcast::<u32>()).map(|handle| *handle).collect();
+    assert_eq!(vec![1, 3], numbers);
+    assert!(roots.iter().nth(1).unwrap().is::<String>());
+    assert!(Handle::ptr_eq(&three, &roots.iter().last().unwrap().downcast().unwrap()));
+
+    // Roots registered while iterating are returned too
+    let mut count = 0;
+    for root in &roots {
+        if root.is::<String>() {
+            roots.root(Cc::new(4u32));
+        }
+        count += 1;
+    }
+    assert_eq!(4, count);
+}
+
+#[test]
+fn test_handle_scopes() {
+    reset_state();
+
+    let (droppable, outer_checker) = Droppable::new(1u32);
+    let scope = HandleScope::new();
+    let outer = scope.handle(Cc::new(droppable));
+
+    let (droppable, escaped_checker) = Droppable::new(2u32);
+    let (droppable2, inner_checker) = Droppable::new(3u32);
+    let escaped = {
+        let inner = scope.nested();
+        let handle = inner.handle(Cc::new(droppable));
+        inner.handle(Cc::new(droppable2));
+        inner.handle(outer.to_cc());
+        assert_eq!(3, inner.len());
+        let escaped = inner.escape(handle).unwrap();
+
+        // The root is moved into the enclosing scope instead of being cloned
+        assert_eq!(2, inner.len());
+        assert_eq!(2, scope.len());
+        assert!(Handle::ptr_eq(&handle, &escaped));
+        assert_eq!(2, escaped.to_cc().strong_count()); // The moved root and the returned Cc
+
+        // Escaping an allocation registered elsewhere registers a new strong reference
+        let other = RootSet::new();
+        let other_handle = other.root(Cc::new(4u32));
+        let escaped_other = inner.escape(other_handle).unwrap();
+        assert_eq!(2, inner.len());
+        assert_eq!(3, scope.len());
+        assert!(Handle::ptr_eq(&other_handle, &escaped_other));
+        assert_eq!(3, escaped_other.to_cc().strong_count());
+        escaped
+    };
+    inner_checker.assert_dropped();
+    escaped_checker.assert_not_dropped();
+    outer_checker.assert_not_dropped();
+    assert_eq!(2, **escaped);
+    assert_eq!(3, scope.len());
+    assert!(scope.escape(outer).is_none());
+
+    drop(scope);
+    escaped_checker.assert_dropped();
+    outer_checker.assert_dropped();
+}
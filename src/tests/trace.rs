@@ -0,0 +1,283 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::mem;
+use std::num::Wrapping;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, RwLock};
+
+use super::*;
+use crate::{collect_cycles, Untraced};
+
+type Node = Droppable<RefCell<Option<Box<dyn Trace>>>>;
+
+// A key comparing and hashing only by id, pointing to a Node
+struct Key(u32, Cc<Node>);
+
+unsafe impl Trace for Key {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.1.trace(ctx);
+    }
+}
+
+impl Finalize for Key {}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+#[derive(Default)]
+struct SimpleHasher(u64);
+
+impl Hasher for SimpleHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = self.0.wrapping_mul(31).wrapping_add(*byte as u64);
+        }
+    }
+}
+
+/// Creates a cycle passing through the container returned by `make` and checks that it is collected
+fn test_cycle<C: Trace + 'static>(make: impl FnOnce(Cc<Node>) -> C) {
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(RefCell::new(None));
+    {
+        let cc: Cc<Node> = Cc::new(droppable);
+        let container = make(cc.clone());
+        *cc.borrow_mut() = Some(Box::new(container));
+    }
+
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+
+    collect_cycles();
+
+    checker.assert_finalized();
+    checker.assert_dropped();
+    assert_empty();
+}
+
+#[test]
+fn test_vec_deque() {
+    test_cycle(|cc| VecDeque::from([cc.clone(), cc]));
+}
+
+#[test]
+fn test_linked_list() {
+    test_cycle(|cc| LinkedList::from([cc.clone(), cc]));
+}
+
+#[test]
+fn test_binary_heap() {
+    test_cycle(|cc| BinaryHeap::from([Key(0, cc.clone()), Key(1, cc)]));
+}
+
+#[test]
+fn test_btree_set() {
+    test_cycle(|cc| BTreeSet::from([Key(0, cc.clone()), Key(1, cc)]));
+}
+
+#[test]
+fn test_btree_map() {
+    test_cycle(|cc| BTreeMap::from([(Key(0, cc.clone()), cc.clone()), (Key(1, cc.clone()), cc)]));
+}
+
+#[test]
+fn test_hash_set() {
+    test_cycle(|cc| HashSet::from([Key(0, cc.clone()), Key(1, cc)]));
+}
+
+#[test]
+fn test_hash_map() {
+    test_cycle(|cc| HashMap::from([(Key(0, cc.clone()), cc.clone()), (Key(1, cc.clone()), cc)]));
+}
+
+#[test]
+fn test_hash_map_custom_hasher() {
+    test_cycle(|cc| {
+        let mut map: HashMap<Key, Cc<Node>, BuildHasherDefault<SimpleHasher>> = HashMap::default();
+        map.insert(Key(0, cc.clone()), cc.clone());
+        map.insert(Key(1, cc.clone()), cc);
+        map
+    });
+}
+
+#[test]
+fn test_not_collected_while_reachable() {
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(RefCell::new(None));
+    let cc: Cc<Node> = Cc::new(droppable);
+    *cc.borrow_mut() = Some(Box::new(BTreeMap::from([(0u32, VecDeque::from([cc.clone()]))])));
+
+    collect_cycles();
+
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+
+    drop(cc);
+    collect_cycles();
+
+    checker.assert_finalized();
+    checker.assert_dropped();
+}
+
+#[test]
+fn test_once_cell() {
+    test_cycle(|cc| {
+        let cell = OnceCell::new();
+        assert!(cell.set(cc).is_ok());
+        cell
+    });
+}
+
+#[test]
+fn test_mutex() {
+    test_cycle(|cc| Mutex::new(vec![cc]));
+}
+
+#[test]
+fn test_rw_lock() {
+    test_cycle(|cc| RwLock::new(Some(cc)));
+}
+
+#[test]
+fn test_poisoned_mutex() {
+    test_cycle(|cc| {
+        let mutex = Mutex::new(cc);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = mutex.lock().unwrap();
+            panic!("Poisoning the mutex");
+        }));
+        assert!(res.is_err());
+        assert!(mutex.is_poisoned());
+        mutex
+    });
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_skipped_while_locked() {
+    struct Locked {
+        mutex: Mutex<Option<Cc<Droppable<Locked>>>>,
+        rw_lock: RwLock<Option<Cc<Droppable<Locked>>>>,
+    }
+
+    unsafe impl Trace for Locked {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.mutex.trace(ctx);
+            self.rw_lock.trace(ctx);
+        }
+    }
+
+    impl Finalize for Locked {}
+
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(Locked {
+        mutex: Mutex::new(None),
+        rw_lock: RwLock::new(None),
+    });
+    let cc = Cc::new(droppable);
+    *cc.mutex.lock().unwrap() = Some(cc.clone());
+    *cc.rw_lock.write().unwrap() = Some(cc.clone());
+
+    // Keep both locks locked forever
+    mem::forget(cc.mutex.lock().unwrap());
+    mem::forget(cc.rw_lock.read().unwrap());
+    drop(cc);
+    collect_cycles();
+
+    checker.assert_not_dropped();
+
+    let report = state::last_collection_report().unwrap().expect("Missing report");
+    assert_eq!(2, report.skipped_borrowed);
+    assert_eq!(0, report.deallocated_objects);
+}
+
+#[test]
+fn test_wrappers() {
+    test_cycle(|cc| (Reverse(cc.clone()), Wrapping(cc.clone()), Box::pin(cc)));
+}
+
+#[test]
+fn test_cow() {
+    #[derive(Clone)]
+    struct Owned(Cc<Node>);
+
+    unsafe impl Trace for Owned {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.0.trace(ctx);
+        }
+    }
+
+    impl Finalize for Owned {}
+
+    test_cycle(|cc| Cow::<'static, Owned>::Owned(Owned(cc)));
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_rc_is_not_traced() {
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(RefCell::new(None));
+    let cc: Cc<Node> = Cc::new(droppable);
+    *cc.borrow_mut() = Some(Box::new(Rc::new(Mutex::new(cc.clone()))));
+
+    // Ccs inside Rcs are never traced, so the cycle is leaked
+    drop(cc);
+    collect_cycles();
+
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+}
+
+#[cfg(not(miri))] // Don't run on Miri due to leaks
+#[test]
+fn test_untraced() {
+    reset_state();
+
+    let (droppable, checker) = Droppable::new(RefCell::new(None));
+    let cc: Cc<Node> = Cc::new(droppable);
+    let mut untraced = Untraced::new(vec![cc.clone()]);
+    untraced.push(cc.clone());
+    assert_eq!(2, untraced.len());
+    *cc.borrow_mut() = Some(Box::new(untraced));
+
+    // The cycle passes through an Untraced, so it is leaked
+    drop(cc);
+    collect_cycles();
+
+    checker.assert_not_finalized();
+    checker.assert_not_dropped();
+}
@@ -0,0 +1,235 @@
+//! Implementations of [`Trace`] and [`Finalize`] for types of third-party crates, enabled by the homonymous features.
+
+#[cfg(feature = "hashbrown")]
+mod hashbrown_impls {
+    use hashbrown::{HashMap, HashSet, HashTable};
+    use crate::trace::{Context, Finalize, Trace};
+
+    unsafe impl<K: Trace, V: Trace, S> Trace for HashMap<K, V, S> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for (key, value) in self {
+                key.trace(ctx);
+                value.trace(ctx);
+            }
+        }
+    }
+
+    impl<K: Finalize, V: Finalize, S> Finalize for HashMap<K, V, S> {
+        #[inline]
+        fn finalize(&self) {
+            for (key, value) in self {
+                key.finalize();
+                value.finalize();
+            }
+        }
+    }
+
+    unsafe impl<T: Trace, S> Trace for HashSet<T, S> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for elem in self {
+                elem.trace(ctx);
+            }
+        }
+    }
+
+    impl<T: Finalize, S> Finalize for HashSet<T, S> {
+        #[inline]
+        fn finalize(&self) {
+            for elem in self {
+                elem.finalize();
+            }
+        }
+    }
+
+    unsafe impl<T: Trace> Trace for HashTable<T> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for elem in self {
+                elem.trace(ctx);
+            }
+        }
+    }
+
+    impl<T: Finalize> Finalize for HashTable<T> {
+        #[inline]
+        fn finalize(&self) {
+            for elem in self {
+                elem.finalize();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "indexmap")]
+mod indexmap_impls {
+    use indexmap::{IndexMap, IndexSet};
+    use crate::trace::{Context, Finalize, Trace};
+
+    unsafe impl<K: Trace, V: Trace, S> Trace for IndexMap<K, V, S> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for (key, value) in self {
+                key.trace(ctx);
+                value.trace(ctx);
+            }
+        }
+    }
+
+    impl<K: Finalize, V: Finalize, S> Finalize for IndexMap<K, V, S> {
+        #[inline]
+        fn finalize(&self) {
+            for (key, value) in self {
+                key.finalize();
+                value.finalize();
+            }
+        }
+    }
+
+    unsafe impl<T: Trace, S> Trace for IndexSet<T, S> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for elem in self {
+                elem.trace(ctx);
+            }
+        }
+    }
+
+    impl<T: Finalize, S> Finalize for IndexSet<T, S> {
+        #[inline]
+        fn finalize(&self) {
+            for elem in self {
+                elem.finalize();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "smallvec")]
+mod smallvec_impls {
+    use smallvec::{Array, SmallVec};
+    use crate::trace::{Context, Finalize, Trace};
+
+    unsafe impl<A: Array> Trace for SmallVec<A>
+    where A::Item: Trace
+    {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for elem in self {
+                elem.trace(ctx);
+            }
+        }
+    }
+
+    impl<A: Array> Finalize for SmallVec<A>
+    where A::Item: Finalize
+    {
+        #[inline]
+        fn finalize(&self) {
+            for elem in self {
+                elem.finalize();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+mod arrayvec_impls {
+    use arrayvec::{ArrayString, ArrayVec};
+    use crate::trace::{Context, Finalize, Trace};
+
+    unsafe impl<T: Trace, const CAP: usize> Trace for ArrayVec<T, CAP> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            for elem in self {
+                elem.trace(ctx);
+            }
+        }
+    }
+
+    impl<T: Finalize, const CAP: usize> Finalize for ArrayVec<T, CAP> {
+        #[inline]
+        fn finalize(&self) {
+            for elem in self {
+                elem.finalize();
+            }
+        }
+    }
+
+    unsafe impl<const CAP: usize> Trace for ArrayString<CAP> {
+        #[inline(always)]
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl<const CAP: usize> Finalize for ArrayString<CAP> {}
+}
+
+#[cfg(feature = "either")]
+mod either_impls {
+    use either::Either;
+    use crate::trace::{Context, Finalize, Trace};
+
+    unsafe impl<L: Trace, R: Trace> Trace for Either<L, R> {
+        #[inline]
+        fn trace(&self, ctx: &mut Context<'_>) {
+            match self {
+                Either::Left(left) => left.trace(ctx),
+                Either::Right(right) => right.trace(ctx),
+            }
+        }
+    }
+
+    impl<L: Finalize, R: Finalize> Finalize for Either<L, R> {
+        #[inline]
+        fn finalize(&self) {
+            match self {
+                Either::Left(left) => left.finalize(),
+                Either::Right(right) => right.finalize(),
+            }
+        }
+    }
+}
+
+// The collections of im share their nodes between clones (like Rc and Arc do), so the contained Ccs
+// are not owned exclusively and cannot be traced. Thus, cycles passing through them are never collected
+#[cfg(feature = "im")]
+mod im_impls {
+    use im::{HashMap, HashSet, OrdMap, OrdSet, Vector};
+    use crate::trace::{Context, Finalize, Trace};
+
+    unsafe impl<K, V, S> Trace for HashMap<K, V, S> {
+        #[inline(always)]
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl<K, V, S> Finalize for HashMap<K, V, S> {}
+
+    unsafe impl<T, S> Trace for HashSet<T, S> {
+        #[inline(always)]
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl<T, S> Finalize for HashSet<T, S> {}
+
+    unsafe impl<K, V> Trace for OrdMap<K, V> {
+        #[inline(always)]
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl<K, V> Finalize for OrdMap<K, V> {}
+
+    unsafe impl<T> Trace for OrdSet<T> {
+        #[inline(always)]
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl<T> Finalize for OrdSet<T> {}
+
+    unsafe impl<T: Clone> Trace for Vector<T> {
+        #[inline(always)]
+        fn trace(&self, _: &mut Context<'_>) {}
+    }
+
+    impl<T: Clone> Finalize for Vector<T> {}
+}
@@ -1,27 +1,24 @@
-use core::cell::RefCell;
-use core::ffi::CStr;
+use core::cell::{Cell, OnceCell, RefCell};
 use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
-use core::num::{
-    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
-    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
-};
+use core::ops::{Deref, DerefMut};
 use core::panic::AssertUnwindSafe;
-use core::sync::atomic::{
-    AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32,
-    AtomicU64, AtomicU8, AtomicUsize,
-};
+use core::pin::Pin;
+use core::ptr::NonNull;
+use alloc::borrow::{Cow, ToOwned};
 use alloc::boxed::Box;
-use alloc::vec::Vec;
-use alloc::ffi::CString;
-use alloc::string::String;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use alloc::rc::Rc;
+use alloc::sync::Arc;
 #[cfg(feature = "std")]
 use std::{
-    path::{Path, PathBuf},
-    ffi::{OsStr, OsString}
+    collections::{HashMap, HashSet},
+    sync::{Mutex, RwLock, TryLockError},
 };
 
+use crate::cc::CcBox;
 use crate::lists::{LinkedList, LinkedQueue};
+use crate::std_types::*;
 
 /// Trait to finalize objects before freeing them.
 ///
@@ -141,11 +138,15 @@ pub(crate) enum ContextInner<'a> {
         root_list: &'a mut LinkedList,
         non_root_list: &'a mut LinkedList,
         queue: &'a mut LinkedQueue,
+        skipped_borrowed: &'a mut usize,
     },
     RootTracing {
         non_root_list: &'a mut LinkedList,
         queue: &'a mut LinkedQueue,
     },
+    Walking {
+        visitor: &'a mut dyn FnMut(NonNull<CcBox<()>>),
+    },
 }
 
 impl<'b> Context<'b> {
@@ -165,80 +166,61 @@ pub(crate) fn inner<'a>(&'a mut self) -> &'a mut ContextInner<'b>
     {
         &mut self.inner
     }
+
+    /// Records that a value hasn't been traced because it was mutably borrowed or locked.
+    /// Only the skips happening during the counting phase are recorded, to avoid counting them twice.
+    #[inline]
+    pub(crate) fn record_skipped_borrow(&mut self) {
+        if let ContextInner::Counting { skipped_borrowed, .. } = &mut self.inner {
+            **skipped_borrowed += 1;
+        }
+    }
 }
 
 // #################################
 // #          Trace impls          #
 // #################################
 
+/// Implements [`Trace`] with an empty [`trace`] method and [`Finalize`] with an empty finalizer for the provided types.
+///
+/// This is useful for leaf types, i.e. types which don't contain any [`Cc`].
+/// Implementing [`Trace`] this way is always safe, since never tracing is always safe. However, any [`Cc`] contained in
+/// such types would never be traced, possibly leaking memory.
+///
+/// # Example
+/// ```rust
+///# use rust_cc::*;
+/// struct Id(u64);
+/// struct Name(String);
+///
+/// empty_trace!(Id, Name);
+///
+/// let cc = Cc::new(Id(0));
+///# let _ = cc;
+/// ```
+///
+/// [`trace`]: crate::Trace::trace
+/// [`Cc`]: crate::Cc
+#[macro_export]
 macro_rules! empty_trace {
-    ($($this:ty),*,) => {
+    ($($this:ty),* $(,)?) => {
         $(
-        unsafe impl $crate::trace::Trace for $this {
+        unsafe impl $crate::Trace for $this {
             #[inline(always)]
-            fn trace(&self, _: &mut $crate::trace::Context<'_>) {}
+            fn trace(&self, _: &mut $crate::Context<'_>) {}
         }
 
-        impl $crate::trace::Finalize for $this {
+        impl $crate::Finalize for $this {
         }
         )*
     };
 }
 
-empty_trace! {
-    (),
-    bool,
-    isize,
-    usize,
-    i8,
-    u8,
-    i16,
-    u16,
-    i32,
-    u32,
-    i64,
-    u64,
-    i128,
-    u128,
-    f32,
-    f64,
-    char,
-    str,
-    CStr,
-    String,
-    CString,
-    NonZeroIsize,
-    NonZeroUsize,
-    NonZeroI8,
-    NonZeroU8,
-    NonZeroI16,
-    NonZeroU16,
-    NonZeroI32,
-    NonZeroU32,
-    NonZeroI64,
-    NonZeroU64,
-    NonZeroI128,
-    NonZeroU128,
-    AtomicBool,
-    AtomicIsize,
-    AtomicUsize,
-    AtomicI8,
-    AtomicU8,
-    AtomicI16,
-    AtomicU16,
-    AtomicI32,
-    AtomicU32,
-    AtomicI64,
-    AtomicU64,
-}
-
-#[cfg(feature = "std")]
-empty_trace! {
-    Path,
-    OsStr,
-    PathBuf,
-    OsString,
-}
+plain_types!(empty_trace);
+float_types!(empty_trace);
+atomic_types!(empty_trace);
+unsized_string_types!(empty_trace);
+owned_string_types!(empty_trace);
 
 // Removed since these impls are error-prone. Making a Cc<MaybeUninit<T>> and then casting it to Cc<T>
 // doesn't make T traced during tracing, since the impls for MaybeUninit are empty and the vtable is saved when calling Cc::new
@@ -254,6 +236,89 @@ impl<T> Finalize for MaybeUninit<T> {
     fn finalize(&self) {}
 }*/
 
+/// A wrapper whose content is never traced nor finalized.
+///
+/// This is useful to embed values not implementing [`Trace`] inside traced types, without having to
+/// remember to skip them in handwritten [`Trace`] implementations (or using `#[rust_cc(ignore)]` with the derive macros).
+///
+/// Never tracing is always safe. However, any [`Cc`] contained inside an [`Untraced`] is never traced, so cycles
+/// passing through it are never collected.
+///
+/// # Example
+#[cfg_attr(
+    feature = "derive",
+    doc = r"```rust"
+)]
+#[cfg_attr(
+    not(feature = "derive"),
+    doc = r"```rust,ignore"
+)]
+#[doc = r"# use std::cell::RefCell;
+# use rust_cc::*;
+struct NotTrace(u32);
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: RefCell<Option<Cc<Node>>>,
+    data: Untraced<NotTrace>,
+}
+
+let node = Cc::new(Node {
+    next: RefCell::new(None),
+    data: Untraced(NotTrace(0)),
+});
+assert_eq!(0, node.data.0.0);
+```"]
+///
+/// [`Cc`]: crate::Cc
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Untraced<T>(pub T);
+
+impl<T> Untraced<T> {
+    /// Creates a new [`Untraced`] containing the provided value.
+    #[inline]
+    pub const fn new(value: T) -> Untraced<T> {
+        Untraced(value)
+    }
+
+    /// Consumes the [`Untraced`], returning the contained value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Untraced<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Untraced(value)
+    }
+}
+
+impl<T> Deref for Untraced<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Untraced<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+unsafe impl<T> Trace for Untraced<T> {
+    #[inline(always)]
+    fn trace(&self, _: &mut Context<'_>) {}
+}
+
+impl<T> Finalize for Untraced<T> {}
+
 unsafe impl<T: ?Sized> Trace for PhantomData<T> {
     #[inline(always)]
     fn trace(&self, _: &mut Context<'_>) {}
@@ -308,6 +373,130 @@ macro_rules! deref_traces_sized {
     AssertUnwindSafe,
 }
 
+// Pinning doesn't matter, since tracing only needs a shared reference to the pointed value
+deref_trace!{T; Pin<Box<T>>; ?::core::marker::Sized +}
+
+macro_rules! newtype_traces {
+    ($($this:ty),*,) => {
+        $(
+        unsafe impl<T: $crate::trace::Trace> $crate::trace::Trace for $this {
+            #[inline]
+            fn trace(&self, ctx: &mut $crate::trace::Context<'_>) {
+                self.0.trace(ctx);
+            }
+        }
+
+        impl<T: $crate::trace::Finalize> $crate::trace::Finalize for $this {
+            #[inline]
+            fn finalize(&self) {
+                self.0.finalize();
+            }
+        }
+        )*
+    }
+}
+
+newtype_types!(newtype_traces);
+
+// A Cell<T> with T: Copy + 'static cannot own any Cc, since Cc isn't Copy.
+// It may only contain a reference to a Cc, which doesn't need to be traced
+unsafe impl<T: Copy> Trace for Cell<T> {
+    #[inline(always)]
+    fn trace(&self, _: &mut Context<'_>) {}
+}
+
+impl<T: Copy> Finalize for Cell<T> {}
+
+// Once initialized, the value of a OnceCell cannot be changed or moved out without a mutable
+// reference, so every trace call during the same tracing phase traces the same Ccs
+unsafe impl<T: Trace> Trace for OnceCell<T> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        if let Some(value) = self.get() {
+            value.trace(ctx);
+        }
+    }
+}
+
+impl<T: Finalize> Finalize for OnceCell<T> {
+    #[inline]
+    fn finalize(&self) {
+        if let Some(value) = self.get() {
+            value.finalize();
+        }
+    }
+}
+
+// Trace isn't implemented for LazyCell, since its value cannot be accessed without forcing its initialization,
+// which would run arbitrary code during tracing
+
+// Only the owned variant owns its value, a borrowed Cow only contains a reference
+unsafe impl<B: ?Sized + ToOwned> Trace for Cow<'_, B>
+where B::Owned: Trace
+{
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        if let Cow::Owned(owned) = self {
+            owned.trace(ctx);
+        }
+    }
+}
+
+impl<B: ?Sized + ToOwned> Finalize for Cow<'_, B>
+where B::Owned: Finalize
+{
+    #[inline]
+    fn finalize(&self) {
+        if let Cow::Owned(owned) = self {
+            owned.finalize();
+        }
+    }
+}
+
+// The contained Ccs are shared with the other Rcs and Arcs, so they're not owned exclusively and cannot be traced
+unsafe impl<T: ?Sized> Trace for Rc<T> {
+    #[inline(always)]
+    fn trace(&self, _: &mut Context<'_>) {}
+}
+
+impl<T: ?Sized> Finalize for Rc<T> {}
+
+unsafe impl<T: ?Sized> Trace for Arc<T> {
+    #[inline(always)]
+    fn trace(&self, _: &mut Context<'_>) {}
+}
+
+impl<T: ?Sized> Finalize for Arc<T> {}
+
+// Function pointers don't own anything
+macro_rules! fn_trace {
+    ($($args:ident),*) => {
+        unsafe impl<Ret, $($args),*> $crate::trace::Trace for fn($($args),*) -> Ret {
+            #[inline(always)]
+            fn trace(&self, _: &mut $crate::trace::Context<'_>) {}
+        }
+
+        impl<Ret, $($args),*> $crate::trace::Finalize for fn($($args),*) -> Ret {}
+
+        unsafe impl<Ret, $($args),*> $crate::trace::Trace for unsafe fn($($args),*) -> Ret {
+            #[inline(always)]
+            fn trace(&self, _: &mut $crate::trace::Context<'_>) {}
+        }
+
+        impl<Ret, $($args),*> $crate::trace::Finalize for unsafe fn($($args),*) -> Ret {}
+    }
+}
+
+macro_rules! fn_traces {
+    ($(($($args:ident),*);)*) => {
+        $(
+            fn_trace!($($args),*);
+        )*
+    }
+}
+
+fn_arities!(fn_traces);
+
 unsafe impl<T: ?Sized + Trace> Trace for RefCell<T> {
     #[inline]
     fn trace(&self, ctx: &mut Context<'_>) {
@@ -326,6 +515,57 @@ fn finalize(&self) {
     }
 }
 
+// A Mutex or RwLock containing a Cc is never Sync (since Cc isn't Send), so it can only be locked by the current thread.
+// Like for CcRefCell, a locked value isn't traced (and the skip is recorded), which is safe. A poisoned lock is traced anyway,
+// since the Ccs inside it are still owned by it
+#[cfg(feature = "std")]
+unsafe impl<T: ?Sized + Trace> Trace for Mutex<T> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        match self.try_lock() {
+            Ok(guard) => guard.trace(ctx),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().trace(ctx),
+            Err(TryLockError::WouldBlock) => ctx.record_skipped_borrow(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + Finalize> Finalize for Mutex<T> {
+    #[inline]
+    fn finalize(&self) {
+        match self.try_lock() {
+            Ok(guard) => guard.finalize(),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().finalize(),
+            Err(TryLockError::WouldBlock) => {},
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe impl<T: ?Sized + Trace> Trace for RwLock<T> {
+    #[inline]
+    fn trace(&self, ctx: &mut Context<'_>) {
+        match self.try_write() {
+            Ok(guard) => guard.trace(ctx),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().trace(ctx),
+            Err(TryLockError::WouldBlock) => ctx.record_skipped_borrow(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: ?Sized + Finalize> Finalize for RwLock<T> {
+    #[inline]
+    fn finalize(&self) {
+        match self.try_read() {
+            Ok(guard) => guard.finalize(),
+            Err(TryLockError::Poisoned(err)) => err.into_inner().finalize(),
+            Err(TryLockError::WouldBlock) => {},
+        }
+    }
+}
+
 unsafe impl<T: Trace> Trace for Option<T> {
     #[inline]
     fn trace(&self, ctx: &mut Context<'_>) {
@@ -400,24 +640,80 @@ fn finalize(&self) {
     }
 }
 
-unsafe impl<T: Trace> Trace for Vec<T> {
-    #[inline]
-    fn trace(&self, ctx: &mut Context<'_>) {
-        for elem in self {
-            elem.trace(ctx);
+macro_rules! iter_trace {
+    ($($generic:ident),*; $this:ty) => {
+        unsafe impl<$($generic),*> $crate::trace::Trace for $this
+        where T: $crate::trace::Trace
+        {
+            #[inline]
+            fn trace(&self, ctx: &mut $crate::trace::Context<'_>) {
+                for elem in self {
+                    elem.trace(ctx);
+                }
+            }
+        }
+
+        impl<$($generic),*> $crate::trace::Finalize for $this
+        where T: $crate::trace::Finalize
+        {
+            #[inline]
+            fn finalize(&self) {
+                for elem in self {
+                    elem.finalize();
+                }
+            }
         }
     }
 }
 
-impl<T: Finalize> Finalize for Vec<T> {
-    #[inline]
-    fn finalize(&self) {
-        for elem in self {
-            elem.finalize();
+macro_rules! iter_traces {
+    ($($this:ty),*,) => {
+        $(
+            iter_trace!(T; $this);
+        )*
+    }
+}
+
+sequence_types!(iter_traces);
+iter_trace!(T; BinaryHeap<T>);
+iter_trace!(T; BTreeSet<T>);
+
+#[cfg(feature = "std")]
+iter_trace!(T, S; HashSet<T, S>);
+
+macro_rules! map_trace {
+    ($($generic:ident),*; $this:ty) => {
+        unsafe impl<$($generic),*> $crate::trace::Trace for $this
+        where K: $crate::trace::Trace, V: $crate::trace::Trace
+        {
+            #[inline]
+            fn trace(&self, ctx: &mut $crate::trace::Context<'_>) {
+                for (key, value) in self {
+                    key.trace(ctx);
+                    value.trace(ctx);
+                }
+            }
+        }
+
+        impl<$($generic),*> $crate::trace::Finalize for $this
+        where K: $crate::trace::Finalize, V: $crate::trace::Finalize
+        {
+            #[inline]
+            fn finalize(&self) {
+                for (key, value) in self {
+                    key.finalize();
+                    value.finalize();
+                }
+            }
         }
     }
 }
 
+map_trace!(K, V; BTreeMap<K, V>);
+
+#[cfg(feature = "std")]
+map_trace!(K, V, S; HashMap<K, V, S>);
+
 macro_rules! tuple_finalize_trace {
     ($($args:ident),+) => {
         #[allow(non_snake_case)]
@@ -455,24 +751,11 @@ fn finalize(&self) {
 }
 
 macro_rules! tuple_finalize_traces {
-    ($(($($args:ident),+);)*) => {
+    ($(($($args:ident $_lower:ident),+);)*) => {
         $(
             tuple_finalize_trace!($($args),*);
         )*
     }
 }
 
-tuple_finalize_traces! {
-    (A);
-    (A, B);
-    (A, B, C);
-    (A, B, C, D);
-    (A, B, C, D, E);
-    (A, B, C, D, E, F);
-    (A, B, C, D, E, F, G);
-    (A, B, C, D, E, F, G, H);
-    (A, B, C, D, E, F, G, H, I);
-    (A, B, C, D, E, F, G, H, I, J);
-    (A, B, C, D, E, F, G, H, I, J, K);
-    (A, B, C, D, E, F, G, H, I, J, K, L);
-}
+tuple_arities!(tuple_finalize_traces);
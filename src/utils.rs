@@ -6,14 +6,25 @@
 use crate::state::State;
 
 #[inline]
-pub(crate) unsafe fn cc_alloc<T: Trace + 'static>(layout: Layout, state: &State) -> NonNull<CcBox<T>> {
+pub(crate) unsafe fn cc_alloc<T: Trace + 'static>(layout: Layout, state: &State) -> Result<NonNull<CcBox<T>>, CcAllocError> {
+    #[cfg(feature = "auto-collect")]
+    crate::config::check_memory_limit(layout, state)?;
+
     state.record_allocation(layout);
     match NonNull::new(alloc(layout) as *mut CcBox<T>) {
-        Some(ptr) => ptr,
+        Some(ptr) => Ok(ptr),
         None => handle_alloc_error(layout),
     }
 }
 
+/// The error returned by [`cc_alloc`].
+#[cfg(feature = "auto-collect")]
+pub(crate) type CcAllocError = crate::config::MemoryLimitError;
+
+/// The error returned by [`cc_alloc`].
+#[cfg(not(feature = "auto-collect"))]
+pub(crate) type CcAllocError = core::convert::Infallible;
+
 #[inline]
 pub(crate) unsafe fn cc_dealloc<T: ?Sized + Trace + 'static>(
     ptr: NonNull<CcBox<T>>,
@@ -21,6 +32,12 @@ pub(crate) unsafe fn cc_dealloc<T: ?Sized + Trace + 'static>(
     state: &State
 ) {
     state.record_deallocation(layout);
+
+    #[cfg(feature = "finalization")]
+    if state.resurrection_detection() != crate::resurrection::DetectionMode::Disabled {
+        crate::resurrection::remove_allocation_site(ptr.cast());
+    }
+
     dealloc(ptr.cast().as_ptr(), layout);
 }
 
@@ -87,11 +104,14 @@ macro_rules! rust_cc_thread_local {
     );
 
     ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => (
-        #[allow(clippy::declare_interior_mutable_const)]
-        const INIT: $t = $init;
-
         #[thread_local]
-        $(#[$attr])* $vis static $name: $crate::utils::NoStdLocalKey<$t> = $crate::utils::NoStdLocalKey::new(INIT);
+        $(#[$attr])* $vis static $name: $crate::utils::NoStdLocalKey<$t> = {
+            // Use a block to allow declaring multiple thread locals in the same module
+            #[allow(clippy::declare_interior_mutable_const)]
+            const INIT: $t = $init;
+
+            $crate::utils::NoStdLocalKey::new(INIT)
+        };
     );
 }
 
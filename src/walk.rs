@@ -0,0 +1,55 @@
+//! Read-only walks of the object graph.
+
+use core::ptr::NonNull;
+
+use crate::cc::CcBox;
+use crate::state::{state, State};
+use crate::trace::{Context, ContextInner};
+
+/// A graph walk in progress. While it exists, the collector is in a tracing phase.
+pub(crate) struct Walker<'s> {
+    state: &'s State,
+}
+
+impl Walker<'_> {
+    /// Traces the object pointed by `ptr`, calling `visitor` for every [`Cc`][`crate::Cc`] traced by it.
+    ///
+    /// The traced objects are not modified in any way.
+    #[inline]
+    pub(crate) fn children(&mut self, ptr: NonNull<CcBox<()>>, visitor: &mut dyn FnMut(NonNull<CcBox<()>>)) {
+        let mut ctx = Context::new(ContextInner::Walking { visitor });
+        CcBox::trace_inner(ptr, &mut ctx);
+    }
+}
+
+impl Drop for Walker<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.state.set_collecting(false);
+    }
+}
+
+/// Executes `f` inside a tracing phase, allowing it to walk the object graph.
+///
+/// # Panics
+///
+/// Panics if called during a collection or while a [`Cc`][`crate::Cc`] is being dropped.
+#[track_caller]
+pub(crate) fn walk<R>(f: impl FnOnce(&mut Walker<'_>) -> R) -> R {
+    state(|state| {
+        #[cfg(feature = "finalization")]
+        let finalizing = state.is_finalizing();
+        #[cfg(not(feature = "finalization"))]
+        let finalizing = false;
+
+        // Like in Cc::finalize_again, is_finalizing and is_dropping are checked since Cc::drop doesn't set is_collecting
+        assert!(
+            !state.is_collecting() && !finalizing && !state.is_dropping(),
+            "the object graph cannot be walked while collecting"
+        );
+
+        state.set_collecting(true);
+        let mut walker = Walker { state }; // Resets collecting when dropped, also during a panic
+        f(&mut walker)
+    })
+}
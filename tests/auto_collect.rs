@@ -1,11 +1,16 @@
 #![cfg(feature = "auto-collect")]
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
 use std::num::NonZeroUsize;
 
 use rust_cc::{Cc, collect_cycles, Context, Finalize, Trace};
-use rust_cc::config::config;
-use rust_cc::state::executions_count;
+use rust_cc::config::{
+    config, override_config, pause_auto_collect, with_config, AllocationCountPolicy, CollectionPolicy,
+    ConfigGuard, FixedThresholdPolicy, PauseTimePolicy, PolicyContext,
+};
+use rust_cc::state::{allocated_bytes, executions_count, ExternalMemory};
 
 struct Traceable {
     inner: RefCell<Option<Cc<Traceable>>>,
@@ -158,3 +163,512 @@ fn new<T: Default>() -> Cc<Cyclic<T>> {
     assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
     collect_cycles(); // Make sure to don't leak test's memory
 }
+
+#[test]
+fn test_pause_auto_collect() {
+    {
+        let _guard = pause_auto_collect().expect("Couldn't pause auto-collect");
+        assert!(!config(|config| config.auto_collect()).unwrap());
+
+        let executions_counter = executions_count().unwrap();
+        let traceable = Traceable::new();
+        drop(traceable);
+
+        let _ = Cc::new(Traceable {
+            inner: RefCell::new(None),
+            _big: Default::default(),
+        });
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+    }
+    assert!(config(|config| config.auto_collect()).unwrap());
+    collect_cycles(); // Make sure to don't leak test's memory
+}
+
+#[test]
+fn test_nested_config_guards() {
+    let adjustment_percent = config(|config| config.adjustment_percent()).unwrap();
+
+    let outer = pause_auto_collect().unwrap();
+    {
+        let _inner = pause_auto_collect().unwrap();
+        let _percent = override_config(|config| config.set_adjustment_percent(0.5)).unwrap();
+        assert!(!config(|config| config.auto_collect()).unwrap());
+        assert_eq!(0.5, config(|config| config.adjustment_percent()).unwrap());
+    }
+    // The inner guards must not re-enable auto-collect
+    assert!(!config(|config| config.auto_collect()).unwrap());
+    assert_eq!(adjustment_percent, config(|config| config.adjustment_percent()).unwrap());
+
+    drop(outer);
+    assert!(config(|config| config.auto_collect()).unwrap());
+}
+
+#[test]
+fn test_config_guards_dropped_out_of_order() {
+    let adjustment_percent = config(|config| config.adjustment_percent()).unwrap();
+
+    let outer = override_config(|config| config.set_adjustment_percent(0.5)).unwrap();
+    let inner = override_config(|config| config.set_adjustment_percent(0.25)).unwrap();
+    let pause = pause_auto_collect().unwrap();
+    let nested_pause = pause_auto_collect().unwrap();
+
+    // The value overridden by inner must be kept until inner is dropped
+    drop(outer);
+    assert_eq!(0.25, config(|config| config.adjustment_percent()).unwrap());
+    drop(inner);
+    assert_eq!(adjustment_percent, config(|config| config.adjustment_percent()).unwrap());
+
+    // Collections are paused until every pause guard has been dropped
+    drop(pause);
+    assert!(!config(|config| config.auto_collect()).unwrap());
+    drop(nested_pause);
+    assert!(config(|config| config.auto_collect()).unwrap());
+}
+
+#[test]
+fn test_config_guard_only_restores_overridden_values() {
+    let guard = pause_auto_collect().unwrap();
+
+    // Values which are not overridden by the guard must not be restored
+    let threshold = NonZeroUsize::new(10);
+    config(|config| config.set_buffered_objects_threshold(threshold)).unwrap();
+    drop(guard);
+
+    assert!(config(|config| config.auto_collect()).unwrap());
+    assert_eq!(threshold, config(|config| config.buffered_objects_threshold()).unwrap());
+    config(|config| config.set_buffered_objects_threshold(None)).unwrap();
+}
+
+#[test]
+fn test_config_guard_dropped_while_accessing_config() {
+    let guard = pause_auto_collect().unwrap();
+
+    config(|config| {
+        drop(guard);
+        // The restoration is postponed until the end of the access
+        assert!(!config.auto_collect());
+    }).unwrap();
+
+    assert!(config(|config| config.auto_collect()).unwrap());
+}
+
+#[test]
+fn test_config_guard_dropped_by_collection_policy() {
+    struct DroppingPolicy {
+        guard: Rc<RefCell<Option<ConfigGuard>>>,
+    }
+
+    impl CollectionPolicy for DroppingPolicy {
+        fn should_collect(&mut self, _ctx: &PolicyContext) -> bool {
+            // The guard is dropped while the collector is accessing the configuration
+            self.guard.borrow_mut().take().is_some()
+        }
+    }
+
+    struct Probe {
+        cyclic: RefCell<Option<Cc<Probe>>>,
+        seen_limit: Rc<Cell<Option<Option<usize>>>>,
+    }
+
+    unsafe impl Trace for Probe {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.cyclic.trace(ctx);
+        }
+    }
+
+    impl Finalize for Probe {}
+
+    impl Drop for Probe {
+        fn drop(&mut self) {
+            self.seen_limit.set(config(|config| config.memory_limit()).ok());
+        }
+    }
+
+    let guard = Rc::new(RefCell::new(None));
+    let policy = DroppingPolicy { guard: guard.clone() };
+
+    with_config(|config| config.set_collection_policy(policy), || {
+        let seen_limit = Rc::new(Cell::new(None));
+        let probe = Cc::new(Probe {
+            cyclic: RefCell::new(None),
+            seen_limit: seen_limit.clone(),
+        });
+        *probe.cyclic.borrow_mut() = Some(probe.clone());
+        drop(probe);
+
+        *guard.borrow_mut() = Some(override_config(|config| config.set_memory_limit(Some(usize::MAX))).unwrap());
+
+        let executions_counter = executions_count().unwrap();
+        let _ = Cc::new(());
+        assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+
+        // The restoration has been applied before the collection started
+        assert_eq!(Some(None), seen_limit.get());
+        assert_eq!(None, config(|config| config.memory_limit()).unwrap());
+    }).unwrap();
+}
+
+#[test]
+fn test_with_config_restores_on_panic() {
+    let res = std::panic::catch_unwind(|| {
+        with_config(|config| config.set_auto_collect(false), || {
+            assert!(!config(|config| config.auto_collect()).unwrap());
+            panic!("Test panic");
+        })
+    });
+    assert!(res.is_err());
+    assert!(config(|config| config.auto_collect()).unwrap());
+
+    let res = with_config(|config| config.set_auto_collect(false), || {
+        config(|config| config.auto_collect()).unwrap()
+    }).unwrap();
+    assert!(!res);
+    assert!(config(|config| config.auto_collect()).unwrap());
+}
+
+#[test]
+fn test_custom_collection_policy() {
+    struct CountingPolicy {
+        should_collect_calls: Rc<Cell<usize>>,
+        adjust_calls: Rc<Cell<usize>>,
+        collect: Rc<Cell<bool>>,
+    }
+
+    impl CollectionPolicy for CountingPolicy {
+        fn should_collect(&mut self, _ctx: &PolicyContext) -> bool {
+            self.should_collect_calls.set(self.should_collect_calls.get() + 1);
+            self.collect.get()
+        }
+
+        fn adjust(&mut self, ctx: &PolicyContext) {
+            self.adjust_calls.set(self.adjust_calls.get() + 1);
+            assert!(ctx.last_collection.is_some());
+        }
+    }
+
+    let should_collect_calls = Rc::new(Cell::new(0));
+    let adjust_calls = Rc::new(Cell::new(0));
+    let collect = Rc::new(Cell::new(false));
+
+    let policy = CountingPolicy {
+        should_collect_calls: should_collect_calls.clone(),
+        adjust_calls: adjust_calls.clone(),
+        collect: collect.clone(),
+    };
+
+    with_config(|config| config.set_collection_policy(policy), || {
+        assert!(config(|config| config.has_custom_collection_policy()).unwrap());
+
+        let executions_counter = executions_count().unwrap();
+
+        // A big allocation doesn't start a collection, since the custom policy doesn't want to
+        let traceable = Traceable::new();
+        drop(traceable);
+        assert!(should_collect_calls.get() > 0);
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+
+        collect.set(true);
+        let _ = Cc::new(());
+        assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+        assert_eq!(1, adjust_calls.get());
+
+        collect.set(false);
+        collect_cycles();
+        assert_eq!(2, adjust_calls.get());
+    }).unwrap();
+
+    assert!(!config(|config| config.has_custom_collection_policy()).unwrap());
+}
+
+#[test]
+fn test_fixed_threshold_policy() {
+    let policy = FixedThresholdPolicy::new(None);
+
+    with_config(|config| config.set_collection_policy(policy), || {
+        let executions_counter = executions_count().unwrap();
+
+        let traceable = Traceable::new();
+        drop(traceable);
+        let _ = Cc::new(Traceable {
+            inner: RefCell::new(None),
+            _big: Default::default(),
+        });
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+        collect_cycles();
+    }).unwrap();
+
+    let policy = FixedThresholdPolicy::new(NonZeroUsize::new(1));
+
+    with_config(|config| config.set_collection_policy(policy), || {
+        collect_cycles();
+        let executions_counter = executions_count().unwrap();
+
+        // Collections are started before allocating
+        let _first = Cc::new(());
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+        let _second = Cc::new(());
+        assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+    }).unwrap();
+}
+
+#[test]
+fn test_allocation_count_policy() {
+    const ALLOCATIONS: usize = 3;
+
+    let policy = AllocationCountPolicy::new(NonZeroUsize::new(ALLOCATIONS).unwrap());
+
+    with_config(|config| config.set_collection_policy(policy), || {
+        collect_cycles();
+        let executions_counter = executions_count().unwrap();
+
+        let ccs: Vec<_> = (0..ALLOCATIONS).map(|_| Cc::new(())).collect();
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+
+        let _ = Cc::new(());
+        assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+
+        drop(ccs);
+    }).unwrap();
+}
+
+#[test]
+fn test_pause_time_policy() {
+    struct Cyclic {
+        cyclic: RefCell<Option<Cc<Cyclic>>>,
+    }
+
+    unsafe impl Trace for Cyclic {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.cyclic.trace(ctx);
+        }
+    }
+
+    impl Finalize for Cyclic {}
+
+    fn new() -> Cc<Cyclic> {
+        let cc = Cc::new(Cyclic {
+            cyclic: RefCell::new(None),
+        });
+        *cc.cyclic.borrow_mut() = Some(cc.clone());
+        cc
+    }
+
+    let mut policy = PauseTimePolicy::new(Duration::from_secs(60));
+    assert_eq!(None, policy.buffered_objects_threshold());
+
+    // An impossibly small target pause results in a collection every buffered object
+    policy.set_target_pause(Duration::ZERO);
+    let policy_with_threshold = with_config(|config| config.set_collection_policy(policy.clone()), || {
+        collect_cycles();
+        let _ = new();
+        collect_cycles();
+
+        let report = rust_cc::state::last_collection_report().unwrap().unwrap();
+        assert_eq!(1, report.buffered_objects_before);
+        // With finalization the object is traced again after having been finalized
+        assert_eq!(if cfg!(feature = "finalization") { 2 } else { 1 }, report.traced_objects);
+        assert_eq!(1, report.deallocated_objects);
+
+        // Collections are started before allocating, so the third allocation sees two buffered objects
+        let executions_counter = executions_count().unwrap();
+        let _ = new();
+        let _ = new();
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+        let _ = new();
+        assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+    });
+    assert!(policy_with_threshold.is_ok());
+    collect_cycles();
+
+    // A long target pause doesn't start collections (the bytes threshold is raised and fixed to avoid interferences)
+    policy.set_target_pause(Duration::from_secs(60));
+    policy.adaptive_policy_mut().set_bytes_threshold(NonZeroUsize::new(1 << 20).unwrap());
+    policy.adaptive_policy_mut().set_adjustment_percent(0.0);
+    with_config(|config| config.set_collection_policy(policy), || {
+        collect_cycles();
+        let _ = new();
+        collect_cycles();
+
+        let executions_counter = executions_count().unwrap();
+        for _ in 0..10 {
+            let _ = new();
+        }
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+        collect_cycles();
+    }).unwrap();
+}
+
+fn big() -> Traceable {
+    Traceable {
+        inner: RefCell::new(None),
+        _big: Default::default(),
+    }
+}
+
+#[test]
+fn test_memory_limit() {
+    collect_cycles();
+
+    with_config(|config| config.set_memory_limit(Some(allocated_bytes().unwrap() + 1024)), || {
+        let small = Cc::try_new(5u32).expect("Couldn't allocate");
+
+        let err = Cc::try_new(big()).err().expect("Allocated over the memory limit");
+        assert_eq!(allocated_bytes().unwrap(), err.allocated_bytes);
+        assert!(err.requested_bytes > err.limit - err.allocated_bytes);
+
+        let res = std::panic::catch_unwind(|| {
+            let _ = Cc::new(big());
+        });
+        assert!(res.is_err(), "Allocated over the memory limit");
+
+        drop(small);
+    }).unwrap();
+
+    assert_eq!(None, config(|config| config.memory_limit()).unwrap());
+    let _ = Cc::new(big());
+}
+
+#[test]
+fn test_memory_limit_forces_collection() {
+    collect_cycles();
+
+    with_config(|config| config.set_auto_collect(false), || {
+        let traceable = Traceable::new();
+        let cycle_bytes = allocated_bytes().unwrap();
+        drop(traceable);
+
+        with_config(|config| config.set_memory_limit(Some(cycle_bytes)), || {
+            let executions_counter = executions_count().unwrap();
+
+            // The garbage cycle is collected to make room for the new allocation, even if auto-collect is disabled
+            let _ = Cc::try_new(big()).expect("Couldn't allocate");
+            assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+        }).unwrap();
+    }).unwrap();
+}
+
+#[test]
+fn test_memory_limit_handler() {
+    collect_cycles();
+
+    let calls = Rc::new(Cell::new(0));
+    let calls_clone = calls.clone();
+
+    let limit = allocated_bytes().unwrap() + 1024;
+    with_config(|cfg| {
+        cfg.set_memory_limit(Some(limit));
+        cfg.set_memory_limit_handler(move |err| {
+            calls_clone.set(calls_clone.get() + 1);
+            // The configuration can be accessed by the handler
+            assert_eq!(Some(err.limit), config(|config| config.memory_limit()).unwrap());
+            // Raise the limit only once
+            (calls_clone.get() == 1).then_some(err.allocated_bytes + err.requested_bytes)
+        });
+    }, || {
+        let first = Cc::try_new(big()).expect("Couldn't allocate");
+        assert_eq!(1, calls.get());
+        let new_limit = config(|config| config.memory_limit()).unwrap().unwrap();
+        assert_eq!(allocated_bytes().unwrap(), new_limit);
+
+        assert!(Cc::try_new(big()).is_err());
+        assert_eq!(2, calls.get());
+        assert_eq!(Some(new_limit), config(|config| config.memory_limit()).unwrap());
+
+        drop(first);
+    }).unwrap();
+
+    assert_eq!(None, config(|config| config.memory_limit()).unwrap());
+}
+
+#[test]
+fn test_external_memory_starts_collections() {
+    struct Node {
+        next: RefCell<Option<Cc<Node>>>,
+        external: RefCell<ExternalMemory>,
+    }
+
+    unsafe impl Trace for Node {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.next.trace(ctx);
+        }
+    }
+
+    impl Finalize for Node {}
+
+    collect_cycles();
+
+    // A small node which keeps a lot of external memory alive
+    let node = Cc::new(Node {
+        next: RefCell::new(None),
+        external: RefCell::new(ExternalMemory::new(0)),
+    });
+    *node.next.borrow_mut() = Some(node.clone());
+    // Resize after the allocation, since Cc::new may start a collection which would adjust the threshold
+    node.external.borrow_mut().resize(1 << 30);
+
+    let executions_counter = executions_count().unwrap();
+    drop(node);
+    assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+
+    let _ = Cc::new(());
+    assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+    assert_eq!(0, rust_cc::state::external_bytes().unwrap());
+}
+
+#[cfg(feature = "finalization")]
+#[test]
+fn test_finalization_round_limit() {
+    use rust_cc::state::{buffered_objects_count, last_collection_report};
+
+    thread_local! {
+        static CHAIN: RefCell<Vec<Cc<Chained>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    struct Chained {
+        cyclic: RefCell<Option<Cc<Chained>>>,
+    }
+
+    unsafe impl Trace for Chained {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.cyclic.trace(ctx);
+        }
+    }
+
+    impl Finalize for Chained {
+        fn finalize(&self) {
+            // Every finalizer creates new garbage, requiring another round
+            let popped = CHAIN.with(|chain| chain.borrow_mut().pop());
+            drop(popped);
+        }
+    }
+
+    fn chained() -> Cc<Chained> {
+        let cc = Cc::new(Chained {
+            cyclic: RefCell::new(None),
+        });
+        *cc.cyclic.borrow_mut() = Some(cc.clone());
+        cc
+    }
+
+    let _pause = pause_auto_collect().unwrap();
+    assert_eq!(Some(NonZeroUsize::new(10).unwrap()), config(|config| config.finalization_round_limit()).unwrap());
+
+    config(|config| config.set_finalization_round_limit(NonZeroUsize::new(3))).unwrap();
+    CHAIN.with(|chain| chain.borrow_mut().extend((0..5).map(|_| chained())));
+    drop(chained());
+
+    collect_cycles();
+    let report = last_collection_report().unwrap().unwrap();
+    assert_eq!(3, report.rounds);
+    assert!(report.round_limit_reached);
+    assert!(buffered_objects_count().unwrap() > 0);
+
+    config(|config| config.set_finalization_round_limit(None)).unwrap();
+    collect_cycles();
+    let report = last_collection_report().unwrap().unwrap();
+    assert!(!report.round_limit_reached);
+    assert_eq!(0, buffered_objects_count().unwrap());
+    assert!(CHAIN.with(|chain| chain.borrow().is_empty()));
+
+    config(|config| config.set_finalization_round_limit(NonZeroUsize::new(10))).unwrap();
+}
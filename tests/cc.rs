@@ -451,3 +451,53 @@ fn use_struct() {
 
     define_structs!(1, 2, 4, 8, 16, 32, 64, 128);
 }
+
+#[test]
+fn test_empty_trace_macro() {
+    struct Leaf(u32);
+    struct OtherLeaf;
+
+    empty_trace!(Leaf, OtherLeaf,);
+
+    struct Node {
+        leaf: Leaf,
+        _other: OtherLeaf,
+        next: RefCell<Option<Cc<Node>>>,
+    }
+
+    unsafe impl Trace for Node {
+        fn trace(&self, ctx: &mut Context<'_>) {
+            self.leaf.trace(ctx);
+            self.next.trace(ctx);
+        }
+    }
+
+    impl Finalize for Node {}
+
+    let cc = Cc::new(Node {
+        leaf: Leaf(1),
+        _other: OtherLeaf,
+        next: RefCell::new(None),
+    });
+    *cc.next.borrow_mut() = Some(cc.clone());
+    assert_eq!(1, cc.leaf.0);
+    drop(cc);
+    collect_cycles();
+}
+
+// The memory limit is part of the configuration, which is provided by the auto-collect feature.
+// Without it, allocations are never limited and never force a collection
+#[cfg(not(feature = "auto-collect"))]
+#[test]
+fn test_no_memory_limit_without_auto_collect() {
+    collect_cycles();
+    let executions = state::executions_count().unwrap();
+    let allocated = state::allocated_bytes().unwrap();
+
+    let ccs: Vec<Cc<[u64; 512]>> = (0..1024).map(|_| Cc::new([0; 512])).collect();
+    assert!(state::allocated_bytes().unwrap() >= allocated + 1024 * 512 * 8);
+    assert_eq!(executions, state::executions_count().unwrap());
+
+    drop(ccs);
+    assert_eq!(allocated, state::allocated_bytes().unwrap());
+}
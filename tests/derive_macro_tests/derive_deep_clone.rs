@@ -0,0 +1,59 @@
+use std::cell::{Cell, RefCell};
+use rust_cc::*;
+use rust_cc::deep_clone::*;
+
+#[derive(Trace, Finalize, DeepClone)]
+struct MyStruct {
+    cyclic: RefCell<Option<Cc<MyStruct>>>,
+    data: Vec<u64>,
+    #[rust_cc(ignore)]
+    shared: Cc<u32>,
+}
+
+#[derive(DeepClone)]
+enum MyEnum {
+    A(Vec<u8>),
+    #[rust_cc(ignore)]
+    #[allow(dead_code)]
+    B(Cell<*const u8>), // Doesn't implement DeepClone
+    C {
+        a: Box<u32>,
+        b: String,
+    },
+}
+
+#[derive(DeepClone)]
+struct Generic<T: Clone, U> {
+    t: Cell<Option<T>>,
+    #[rust_cc(ignore)]
+    u: U,
+}
+
+fn main() {
+    let my_struct = Cc::new(MyStruct {
+        cyclic: RefCell::new(None),
+        data: vec![1, 2, 3],
+        shared: Cc::new(4),
+    });
+    *my_struct.cyclic.borrow_mut() = Some(my_struct.clone());
+
+    let copy = my_struct.deep_clone();
+    assert!(!Cc::ptr_eq(&my_struct, &copy));
+    assert!(Cc::ptr_eq(&copy, copy.cyclic.borrow().as_ref().unwrap()));
+    assert!(Cc::ptr_eq(&my_struct.shared, &copy.shared));
+    assert_eq!(vec![1, 2, 3], copy.data);
+
+    let MyEnum::C { a, b } = deep_clone(&MyEnum::C { a: Box::new(5), b: String::from("b") }) else {
+        panic!("Expected MyEnum::C");
+    };
+    assert_eq!(5, *a);
+    assert_eq!("b", b);
+    let _ = deep_clone(&MyEnum::A(Vec::new()));
+
+    let generic = deep_clone(&Generic { t: Cell::new(Some(6u8)), u: String::from("u") });
+    assert_eq!(Some(6), generic.t.get());
+    assert_eq!("u", generic.u);
+
+    my_struct.cyclic.borrow_mut().take();
+    copy.cyclic.borrow_mut().take();
+}
@@ -0,0 +1,53 @@
+use std::cell::{Cell, RefCell};
+use rust_cc::*;
+use rust_cc::graph::*;
+
+#[derive(Trace, Finalize, GraphDebug)]
+struct MyStruct {
+    cyclic: RefCell<Option<Cc<MyStruct>>>,
+    data: Vec<u64>,
+    #[rust_cc(ignore)]
+    ignored: Cell<*const u8>, // Doesn't implement GraphDebug
+}
+
+#[derive(Trace, Finalize, GraphDebug)]
+enum MyEnum {
+    A(Vec<u8>),
+    #[rust_cc(ignore)]
+    B(Cell<*const u8>),
+    C {
+        a: Box<u32>,
+        r#type: String,
+    },
+    D,
+}
+
+#[derive(Trace, Finalize, GraphDebug)]
+struct Generic<T, U>(Option<T>, #[rust_cc(ignore)] U);
+
+fn main() {
+    let my_struct = Cc::new(MyStruct {
+        cyclic: RefCell::new(None),
+        data: vec![1, 2, 3],
+        ignored: Cell::new(std::ptr::null()),
+    });
+    *my_struct.cyclic.borrow_mut() = Some(my_struct.clone());
+
+    let formatted = format!("{:?}", my_struct.debug_graph());
+    assert!(formatted.starts_with("MyStruct { cyclic: RefCell { value: Some(<cycle @"));
+    assert!(formatted.ends_with(">) }, data: [1, 2, 3], ignored: Cell { value: 0x0 } }"));
+
+    let cc = Cc::new((
+        MyEnum::A(vec![4]),
+        MyEnum::C { a: Box::new(5), r#type: String::from("b") },
+        MyEnum::D,
+        Generic(Some(6u8), Cell::new(7u8)),
+    ));
+    assert_eq!(
+        r#"(A([4]), C { a: 5, type: "b" }, D, Generic(Some(6), Cell { value: 7 }))"#,
+        format!("{:?}", cc.debug_graph())
+    );
+    let _ = format!("{:?}", Cc::new(MyEnum::B(Cell::new(std::ptr::null()))).debug_graph());
+
+    my_struct.cyclic.borrow_mut().take();
+}
@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::time::Instant;
+use rust_cc::*;
+use rust_cc::graph::*;
+
+#[derive(Trace, Finalize, GraphEq, GraphHashable)]
+struct MyStruct {
+    cyclic: RefCell<Option<Cc<MyStruct>>>,
+    data: Vec<u64>,
+    #[rust_cc(ignore)]
+    ignored: Instant, // Doesn't implement GraphEq and GraphHashable
+}
+
+#[derive(Trace, Finalize, GraphEq, GraphHashable)]
+enum MyEnum {
+    A(Vec<u8>),
+    #[rust_cc(ignore)]
+    B(std::time::Duration),
+    C {
+        a: Box<u32>,
+        b: String,
+    },
+    D,
+}
+
+#[derive(Trace, Finalize, GraphEq, GraphHashable)]
+struct Generic<T, U>(Option<T>, #[rust_cc(ignore)] U);
+
+fn main() {
+    let now = Instant::now();
+    let new = |value| {
+        let cc = Cc::new(MyStruct {
+            cyclic: RefCell::new(None),
+            data: vec![value],
+            ignored: now,
+        });
+        *cc.cyclic.borrow_mut() = Some(cc.clone());
+        cc
+    };
+
+    let a = new(1);
+    let b = new(1);
+    let c = new(2);
+    assert!(graph_eq(&a, &b));
+    assert!(!graph_eq(&a, &c));
+
+    assert!(graph_eq(&MyEnum::A(vec![1]), &MyEnum::A(vec![1])));
+    assert!(!graph_eq(&MyEnum::A(vec![1]), &MyEnum::A(vec![2])));
+    assert!(!graph_eq(&MyEnum::A(vec![]), &MyEnum::D));
+    assert!(graph_eq(&MyEnum::D, &MyEnum::D));
+    assert!(graph_eq(
+        &MyEnum::C { a: Box::new(3), b: String::from("b") },
+        &MyEnum::C { a: Box::new(3), b: String::from("b") },
+    ));
+    assert!(graph_eq(&MyEnum::B(std::time::Duration::ZERO), &MyEnum::B(std::time::Duration::ZERO)));
+    assert!(graph_eq(&Generic(Some(4u8), 5u8), &Generic(Some(4u8), 5u8)));
+    assert!(!graph_eq(&Generic(Some(4u8), 5u8), &Generic(Some(4u8), 6u8)));
+
+    #[derive(Trace, Finalize, GraphEq, GraphHashable)]
+    struct Hashable(RefCell<Vec<Cc<Hashable>>>, MyEnum, Generic<u8, u8>);
+
+    let hashable = Cc::new(Hashable(RefCell::new(Vec::new()), MyEnum::D, Generic(None, 7)));
+    hashable.0.borrow_mut().push(hashable.clone());
+    let mut set = HashSet::new();
+    assert!(set.insert(GraphHash::new(hashable.clone(), 4)));
+    assert!(!set.insert(GraphHash::new(hashable.clone(), 4)));
+
+    drop(set);
+    a.cyclic.borrow_mut().take();
+    b.cyclic.borrow_mut().take();
+    c.cyclic.borrow_mut().take();
+    hashable.0.borrow_mut().clear();
+}
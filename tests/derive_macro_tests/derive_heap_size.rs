@@ -0,0 +1,53 @@
+use std::cell::{Cell, RefCell};
+use std::mem::size_of;
+use rust_cc::*;
+use rust_cc::heap_size::*;
+
+#[derive(Trace, Finalize, HeapSize)]
+struct MyStruct {
+    cyclic: RefCell<Option<Cc<MyStruct>>>,
+    data: Vec<u64>,
+    #[rust_cc(ignore)]
+    #[allow(dead_code)]
+    ignored: Cell<*const u8>, // Doesn't implement HeapSize
+}
+
+#[derive(HeapSize)]
+enum MyEnum {
+    A(Vec<u8>),
+    #[rust_cc(ignore)]
+    #[allow(dead_code)]
+    B(Cell<*const u8>),
+    C {
+        a: Box<u32>,
+        b: String,
+    },
+}
+
+#[derive(HeapSize)]
+struct Generic<T: HeapSize> {
+    t: T,
+}
+
+fn main() {
+    let my_struct = Cc::new(MyStruct {
+        cyclic: RefCell::new(None),
+        data: Vec::with_capacity(4),
+        ignored: Cell::new(std::ptr::null()),
+    });
+    *my_struct.cyclic.borrow_mut() = Some(my_struct.clone());
+
+    let size = my_struct.retained_size();
+    assert!(size.reachable >= 4 * size_of::<u64>() + size_of::<MyStruct>());
+    assert_eq!(size.reachable, size.exclusive);
+
+    assert_eq!(size_of::<MyEnum>() + 10, deep_size_of(&MyEnum::A(Vec::with_capacity(10))));
+    assert_eq!(size_of::<MyEnum>(), deep_size_of(&MyEnum::B(Cell::new(std::ptr::null()))));
+    assert_eq!(size_of::<MyEnum>() + size_of::<u32>() + 3, deep_size_of(&MyEnum::C {
+        a: Box::new(0),
+        b: String::with_capacity(3),
+    }));
+    assert_eq!(size_of::<Generic<Vec<u8>>>() + 5, deep_size_of(&Generic { t: Vec::<u8>::with_capacity(5) }));
+
+    my_struct.cyclic.borrow_mut().take();
+}
@@ -0,0 +1,21 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use rust_cc::*;
+use rust_cc::deep_clone::*;
+
+// Copying the set would compare the copied Ccs while their values are still being copied
+#[derive(Trace, Finalize, DeepClone, PartialEq, Eq, PartialOrd, Ord)]
+struct Node {
+    value: u32,
+    set: RefCell<BTreeSet<Cc<Node>>>,
+}
+
+fn main() {
+    let node = Cc::new(Node {
+        value: 1,
+        set: RefCell::new(BTreeSet::new()),
+    });
+    node.set.borrow_mut().insert(node.clone());
+
+    let _ = node.deep_clone();
+}
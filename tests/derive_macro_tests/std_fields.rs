@@ -0,0 +1,58 @@
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::cmp::{Ordering, Reverse};
+use std::net::{IpAddr, SocketAddr};
+use std::num::{Saturating, Wrapping};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use rust_cc::*;
+
+#[derive(Trace, Finalize)]
+struct MyStruct {
+    cell: Cell<u32>,
+    once_cell: OnceCell<Cc<MyStruct>>,
+    cow: Cow<'static, str>,
+    pinned: Pin<Box<RefCell<Option<Cc<MyStruct>>>>>,
+    reverse: Reverse<u8>,
+    wrapping: Wrapping<u8>,
+    saturating: Saturating<u8>,
+    mutex: Mutex<Vec<u8>>,
+    rw_lock: RwLock<String>,
+    duration: Duration,
+    instant: Instant,
+    system_time: SystemTime,
+    ordering: Ordering,
+    ip: IpAddr,
+    socket: SocketAddr,
+    function: fn(u32) -> u32,
+    rc: Rc<str>,
+    arc: Arc<u8>,
+}
+
+fn main() {
+    fn test<T: Trace>(_t: T) {
+    }
+
+    test(MyStruct {
+        cell: Cell::new(0),
+        once_cell: OnceCell::new(),
+        cow: Cow::Borrowed("cow"),
+        pinned: Box::pin(RefCell::new(None)),
+        reverse: Reverse(0),
+        wrapping: Wrapping(0),
+        saturating: Saturating(0),
+        mutex: Mutex::new(Vec::new()),
+        rw_lock: RwLock::new(String::new()),
+        duration: Duration::ZERO,
+        instant: Instant::now(),
+        system_time: SystemTime::now(),
+        ordering: Ordering::Equal,
+        ip: IpAddr::from([127, 0, 0, 1]),
+        socket: SocketAddr::from(([127, 0, 0, 1], 8080)),
+        function: |x| x,
+        rc: Rc::from("rc"),
+        arc: Arc::new(0),
+    });
+}
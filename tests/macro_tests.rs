@@ -12,9 +12,15 @@ fn macro_tests() {
     t.pass("tests/derive_macro_tests/ignored_variant.rs");
     t.pass("tests/derive_macro_tests/no_drop.rs");
     t.pass("tests/derive_macro_tests/empty_attribute.rs");
+    t.pass("tests/derive_macro_tests/derive_heap_size.rs");
+    t.pass("tests/derive_macro_tests/std_fields.rs");
+    t.pass("tests/derive_macro_tests/derive_deep_clone.rs");
+    t.pass("tests/derive_macro_tests/derive_graph_debug.rs");
+    t.pass("tests/derive_macro_tests/derive_graph_eq.rs");
     t.compile_fail("tests/derive_macro_tests/invalid_attributes.rs");
     t.compile_fail("tests/derive_macro_tests/invalid_ignore_attribute.rs");
     t.compile_fail("tests/derive_macro_tests/invalid_no_drop_attribute.rs");
     t.compile_fail("tests/derive_macro_tests/invalid_drop_impl.rs");
     t.compile_fail("tests/derive_macro_tests/invalid_field_bounds.rs");
+    t.compile_fail("tests/derive_macro_tests/invalid_deep_clone_set.rs");
 }
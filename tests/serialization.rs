@@ -0,0 +1,158 @@
+#![cfg(all(feature = "serde", feature = "derive"))]
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use rust_cc::*;
+use rust_cc::serialization::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Trace, Finalize, Serialize, Deserialize)]
+struct Node {
+    value: u32,
+    next: RefCell<Vec<Cc<Node>>>,
+}
+
+fn node(value: u32) -> Cc<Node> {
+    Cc::new(Node {
+        value,
+        next: RefCell::new(Vec::new()),
+    })
+}
+
+fn next(cc: &Cc<Node>, i: usize) -> Cc<Node> {
+    cc.next.borrow()[i].clone()
+}
+
+fn from_graph_json<T: for<'de> Deserialize<'de>>(json: &str) -> serde_json::Result<T> {
+    // SAFETY: the Deserialize implementations used in these tests don't access the deserialized Ccs
+    unsafe { deserialize_graph(&mut serde_json::Deserializer::from_str(json)) }
+}
+
+#[test]
+fn test_plain_serde() {
+    let shared = node(1);
+    let root = node(0);
+    root.next.borrow_mut().push(shared.clone());
+    root.next.borrow_mut().push(shared);
+
+    // Like Rc, shared Ccs are duplicated
+    let json = serde_json::to_string(&root).unwrap();
+    assert_eq!(r#"{"value":0,"next":[{"value":1,"next":[]},{"value":1,"next":[]}]}"#, json);
+
+    let copy: Cc<Node> = serde_json::from_str(&json).unwrap();
+    assert_eq!(2, copy.next.borrow().len());
+    assert!(!Cc::ptr_eq(&next(&copy, 0), &next(&copy, 1)));
+    assert_eq!(1, next(&copy, 1).value);
+}
+
+#[test]
+fn test_graph_serde_cycle() {
+    let first = node(1);
+    let second = node(2);
+    first.next.borrow_mut().push(second.clone());
+    second.next.borrow_mut().push(first.clone());
+
+    let json = serde_json::to_string(&SerializeGraph(&first)).unwrap();
+    assert_eq!(
+        r#"{"$id":0,"value":{"value":1,"next":[{"$id":1,"value":{"value":2,"next":[{"$ref":0}]}}]}}"#,
+        json
+    );
+
+    let copy: Cc<Node> = from_graph_json(&json).unwrap();
+    assert_eq!(1, copy.value);
+    let copy_second = next(&copy, 0);
+    assert_eq!(2, copy_second.value);
+    assert!(Cc::ptr_eq(&copy, &next(&copy_second, 0)));
+
+    // Graph mode is reset afterwards
+    first.next.borrow_mut().clear();
+    assert_eq!(r#"{"value":1,"next":[]}"#, serde_json::to_string(&first).unwrap());
+
+    second.next.borrow_mut().clear();
+    copy_second.next.borrow_mut().clear();
+    drop(copy_second);
+    drop(copy);
+    collect_cycles();
+}
+
+#[test]
+fn test_graph_serde_shared() {
+    let shared = node(1);
+    let value = vec![shared.clone(), node(2), shared];
+
+    let json = serde_json::to_string(&SerializeGraph(&value)).unwrap();
+    assert_eq!(
+        r#"[{"$id":0,"value":{"value":1,"next":[]}},{"$id":1,"value":{"value":2,"next":[]}},{"$ref":0}]"#,
+        json
+    );
+
+    let copy: Vec<Cc<Node>> = from_graph_json(&json).unwrap();
+    assert_eq!(3, copy.len());
+    assert!(Cc::ptr_eq(&copy[0], &copy[2]));
+    assert!(!Cc::ptr_eq(&copy[0], &copy[1]));
+    assert_eq!(2, copy[1].value);
+}
+
+#[derive(Serialize)]
+struct Wrapper {
+    #[serde(serialize_with = "serialize_graph")]
+    graph: Cc<Node>,
+    plain: Cc<Node>,
+}
+
+#[test]
+fn test_serialize_with() {
+    let shared = node(1);
+    let wrapper = Wrapper {
+        graph: shared.clone(),
+        plain: shared,
+    };
+
+    // Only the graph field is serialized in graph mode
+    assert_eq!(
+        r#"{"graph":{"$id":0,"value":{"value":1,"next":[]}},"plain":{"value":1,"next":[]}}"#,
+        serde_json::to_string(&wrapper).unwrap()
+    );
+}
+
+#[test]
+fn test_graph_deserialize_errors() {
+    assert!(from_graph_json::<Cc<Node>>(r#"{"$ref":0}"#).is_err());
+    assert!(from_graph_json::<Cc<Node>>(r#"{"$id":0}"#).is_err());
+    assert!(from_graph_json::<Cc<Node>>(r#"{"value":{"value":1,"next":[]}}"#).is_err());
+    assert!(from_graph_json::<Cc<Node>>(r#"{"value":1,"next":[]}"#).is_err());
+    assert!(from_graph_json::<Vec<Cc<Node>>>(
+        r#"[{"$id":0,"value":{"value":1,"next":[]}},{"$id":0,"value":{"value":2,"next":[]}}]"#
+    ).is_err());
+
+    // Deserialization still works after an error
+    let copy: Vec<Cc<Node>> = from_graph_json(r#"[{"$id":0,"value":{"value":1,"next":[]}},{"$ref":0}]"#).unwrap();
+    assert!(Cc::ptr_eq(&copy[0], &copy[1]));
+}
+
+#[derive(Trace, Finalize, Serialize, Deserialize)]
+struct MapNode {
+    value: u32,
+    children: RefCell<BTreeMap<String, Cc<MapNode>>>,
+}
+
+#[test]
+fn test_graph_serde_map_values() {
+    // The values of maps are never compared, so they can contain back-references
+    let root = Cc::new(MapNode {
+        value: 1,
+        children: RefCell::new(BTreeMap::new()),
+    });
+    root.children.borrow_mut().insert(String::from("self"), root.clone());
+
+    let json = serde_json::to_string(&SerializeGraph(&root)).unwrap();
+    assert_eq!(r#"{"$id":0,"value":{"value":1,"children":{"self":{"$ref":0}}}}"#, json);
+
+    let copy: Cc<MapNode> = from_graph_json(&json).unwrap();
+    assert_eq!(1, copy.value);
+    assert!(Cc::ptr_eq(&copy, &copy.children.borrow()["self"]));
+
+    root.children.borrow_mut().clear();
+    copy.children.borrow_mut().clear();
+}
@@ -0,0 +1,149 @@
+#![cfg(any(feature = "hashbrown", feature = "indexmap", feature = "smallvec", feature = "arrayvec", feature = "either", feature = "im"))]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use rust_cc::*;
+
+struct Node {
+    edge: RefCell<Option<Box<dyn Trace>>>,
+    dropped: Rc<Cell<bool>>,
+}
+
+unsafe impl Trace for Node {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.edge.trace(ctx);
+    }
+}
+
+impl Finalize for Node {}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.dropped.set(true);
+    }
+}
+
+/// Creates a cycle passing through the container returned by `make` and returns whether it has been collected
+fn is_cycle_collected<C: Trace + 'static>(make: impl FnOnce(Cc<Node>) -> C) -> bool {
+    let dropped = Rc::new(Cell::new(false));
+    {
+        let cc = Cc::new(Node {
+            edge: RefCell::new(None),
+            dropped: dropped.clone(),
+        });
+        let container = make(cc.clone());
+        *cc.edge.borrow_mut() = Some(Box::new(container));
+    }
+    assert!(!dropped.get());
+    collect_cycles();
+    dropped.get()
+}
+
+#[cfg(feature = "hashbrown")]
+#[test]
+fn test_hashbrown() {
+    use hashbrown::{HashMap, HashSet, HashTable};
+    use std::hash::RandomState;
+
+    assert!(is_cycle_collected(|cc| {
+        let mut map = HashMap::with_hasher(RandomState::new());
+        map.insert(0u32, cc);
+        map
+    }));
+    assert!(is_cycle_collected(|cc| {
+        let mut set = HashSet::with_hasher(RandomState::new());
+        set.insert(Key(cc));
+        set
+    }));
+    assert!(is_cycle_collected(|cc| {
+        let mut table = HashTable::new();
+        table.insert_unique(0, cc, |_| 0);
+        table
+    }));
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn test_indexmap() {
+    use indexmap::{IndexMap, IndexSet};
+    use std::hash::RandomState;
+
+    assert!(is_cycle_collected(|cc| {
+        let mut map = IndexMap::with_hasher(RandomState::new());
+        map.insert(0u32, cc);
+        map
+    }));
+    assert!(is_cycle_collected(|cc| {
+        let mut set = IndexSet::with_hasher(RandomState::new());
+        set.insert(Key(cc));
+        set
+    }));
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn test_smallvec() {
+    use smallvec::SmallVec;
+
+    // Both inline and spilled
+    assert!(is_cycle_collected(|cc| SmallVec::<[Cc<Node>; 2]>::from_vec(vec![cc])));
+    assert!(is_cycle_collected(|cc| SmallVec::<[Cc<Node>; 1]>::from_vec(vec![cc.clone(), cc])));
+}
+
+#[cfg(feature = "arrayvec")]
+#[test]
+fn test_arrayvec() {
+    use arrayvec::ArrayVec;
+
+    assert!(is_cycle_collected(|cc| {
+        let mut vec = ArrayVec::<Cc<Node>, 2>::new();
+        vec.push(cc.clone());
+        vec.push(cc);
+        vec
+    }));
+}
+
+#[cfg(feature = "either")]
+#[test]
+fn test_either() {
+    use either::Either;
+
+    assert!(is_cycle_collected(Either::<Cc<Node>, ()>::Left));
+    assert!(is_cycle_collected(Either::<(), Cc<Node>>::Right));
+}
+
+#[cfg(all(feature = "im", not(miri)))] // Don't run on Miri due to leaks
+#[test]
+fn test_im_is_not_traced() {
+    use im::Vector;
+
+    // The nodes of im collections are shared between clones, so they're never traced
+    assert!(!is_cycle_collected(Vector::unit));
+}
+
+// A key hashing and comparing by pointer
+#[allow(dead_code)]
+struct Key(Cc<Node>);
+
+unsafe impl Trace for Key {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.0.trace(ctx);
+    }
+}
+
+impl Finalize for Key {}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        Cc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Key {}
+
+impl std::hash::Hash for Key {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::ptr::hash(&*self.0, state);
+    }
+}
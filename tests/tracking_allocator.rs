@@ -0,0 +1,120 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::RefCell;
+
+use rust_cc::allocator::{heap_bytes, CcTrackingAllocator};
+use rust_cc::{collect_cycles, Cc, Context, Finalize, Trace};
+
+#[global_allocator]
+static GLOBAL: CcTrackingAllocator<System> = CcTrackingAllocator::new(System);
+
+struct Buffer {
+    data: Vec<u8>,
+    cyclic: RefCell<Option<Cc<Buffer>>>,
+}
+
+unsafe impl Trace for Buffer {
+    fn trace(&self, ctx: &mut Context<'_>) {
+        self.cyclic.trace(ctx);
+    }
+}
+
+impl Finalize for Buffer {}
+
+fn buffer(bytes: usize) -> Cc<Buffer> {
+    let cc = Cc::new(Buffer {
+        data: vec![0; bytes],
+        cyclic: RefCell::new(None),
+    });
+    *cc.cyclic.borrow_mut() = Some(cc.clone());
+    cc
+}
+
+#[test]
+fn test_heap_bytes() {
+    GLOBAL.install();
+    let before = heap_bytes().expect("The allocator isn't installed");
+
+    let vec: Vec<u8> = Vec::with_capacity(4096);
+    assert!(heap_bytes().unwrap() >= before + 4096);
+
+    let mut vec = vec;
+    vec.reserve_exact(8192);
+    assert!(heap_bytes().unwrap() >= before + 8192);
+
+    drop(vec);
+    assert!(heap_bytes().unwrap() < before + 4096);
+}
+
+#[test]
+fn test_heap_bytes_with_cc() {
+    GLOBAL.install();
+    collect_cycles();
+    let before = heap_bytes().unwrap();
+
+    let cc = buffer(1 << 16);
+    assert_eq!(1 << 16, cc.data.len());
+    assert!(heap_bytes().unwrap() >= before + (1 << 16));
+    drop(cc);
+
+    collect_cycles();
+    assert!(heap_bytes().unwrap() < before + (1 << 16));
+}
+
+#[test]
+fn test_not_installed_allocator() {
+    GLOBAL.install();
+
+    static OTHER: CcTrackingAllocator<System> = CcTrackingAllocator::new(System);
+
+    let before = heap_bytes().unwrap();
+    let layout = Layout::from_size_align(1 << 16, 8).unwrap();
+    unsafe {
+        let ptr = OTHER.alloc(layout);
+        assert!(!ptr.is_null());
+        assert!(heap_bytes().unwrap() < before + (1 << 16));
+        OTHER.dealloc(ptr, layout);
+    }
+}
+
+#[cfg(feature = "auto-collect")]
+#[test]
+fn test_auto_collect_uses_heap_bytes() {
+    GLOBAL.install();
+
+    use rust_cc::config::{pause_auto_collect, with_config, CollectionPolicy, FixedThresholdPolicy, PolicyContext};
+    use rust_cc::state::executions_count;
+
+    struct Checker;
+
+    impl CollectionPolicy for Checker {
+        fn should_collect(&mut self, ctx: &PolicyContext) -> bool {
+            let heap_bytes = ctx.heap_bytes.expect("The allocator isn't installed");
+            assert_eq!(ctx.managed_bytes(), heap_bytes.max(ctx.allocated_bytes) + ctx.external_bytes);
+            false
+        }
+    }
+
+    with_config(|config| config.set_collection_policy(Checker), || {
+        let _ = Cc::new(());
+    }).unwrap();
+
+    collect_cycles();
+    let threshold = heap_bytes().unwrap() + (1 << 20);
+    let policy = FixedThresholdPolicy::new(std::num::NonZeroUsize::new(threshold));
+
+    with_config(|config| config.set_collection_policy(policy), || {
+        let executions_counter = executions_count().unwrap();
+
+        {
+            // Cc::new would start a collection, since the buffer is allocated before the CcBox
+            let _guard = pause_auto_collect().unwrap();
+
+            // The CcBox is small, but the owned buffer is big
+            drop(buffer(1 << 21));
+        }
+        assert_eq!(executions_counter, executions_count().unwrap(), "Collected but shouldn't have collected.");
+
+        let _ = Cc::new(());
+        assert_eq!(executions_counter + 1, executions_count().unwrap(), "Didn't collected");
+    }).unwrap();
+}